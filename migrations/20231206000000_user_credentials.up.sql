@@ -0,0 +1,6 @@
+-- Add up migration script here
+CREATE TABLE user_credentials (
+    user_id INTEGER PRIMARY KEY REFERENCES users (id) ON DELETE CASCADE,
+    email VARCHAR NOT NULL UNIQUE,
+    password_hash VARCHAR NOT NULL
+);
@@ -0,0 +1,6 @@
+-- Add down migration script here
+DROP TABLE user_roles;
+
+DROP TABLE role_permissions;
+
+DROP TABLE roles;
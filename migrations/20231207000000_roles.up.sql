@@ -0,0 +1,23 @@
+-- Add up migration script here
+CREATE TABLE roles (
+    name VARCHAR PRIMARY KEY
+);
+
+CREATE TABLE role_permissions (
+    role VARCHAR NOT NULL REFERENCES roles (name) ON DELETE CASCADE,
+    permission VARCHAR NOT NULL,
+    PRIMARY KEY (role, permission)
+);
+
+CREATE TABLE user_roles (
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    role VARCHAR NOT NULL REFERENCES roles (name) ON DELETE CASCADE,
+    PRIMARY KEY (user_id, role)
+);
+
+INSERT INTO roles (name) VALUES ('admin'), ('editor'), ('viewer');
+
+INSERT INTO role_permissions (role, permission) VALUES
+    ('admin', 'users:write'),
+    ('admin', 'products:write'),
+    ('editor', 'products:write');
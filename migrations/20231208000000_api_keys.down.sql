@@ -0,0 +1,4 @@
+-- Add down migration script here
+DELETE FROM role_permissions WHERE permission = 'api_keys:manage';
+
+DROP TABLE api_keys;
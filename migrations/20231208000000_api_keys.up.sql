@@ -0,0 +1,15 @@
+-- Add up migration script here
+CREATE TABLE api_keys (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR NOT NULL,
+    prefix VARCHAR NOT NULL,
+    key_hash VARCHAR NOT NULL UNIQUE,
+    scopes TEXT[] NOT NULL,
+    created_by INTEGER REFERENCES users (id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    revoked_at TIMESTAMPTZ,
+    last_used_at TIMESTAMPTZ,
+    use_count BIGINT NOT NULL DEFAULT 0
+);
+
+INSERT INTO role_permissions (role, permission) VALUES ('admin', 'api_keys:manage');
@@ -0,0 +1,6 @@
+-- Add down migration script here
+ALTER TABLE products
+    DROP COLUMN sku,
+    DROP COLUMN price,
+    DROP COLUMN currency,
+    DROP COLUMN stock;
@@ -0,0 +1,15 @@
+-- Add up migration script here
+ALTER TABLE products
+    ADD COLUMN sku VARCHAR,
+    ADD COLUMN price BIGINT NOT NULL DEFAULT 0 CHECK (price >= 0),
+    ADD COLUMN currency VARCHAR(3) NOT NULL DEFAULT 'JPY' CHECK (currency ~ '^[A-Z]{3}$'),
+    ADD COLUMN stock INTEGER NOT NULL DEFAULT 0 CHECK (stock >= 0);
+
+-- 既存の商品にはidからSKUを振る
+UPDATE products SET sku = 'SKU-' || id;
+
+ALTER TABLE products
+    ALTER COLUMN sku SET NOT NULL,
+    ALTER COLUMN price DROP DEFAULT,
+    ALTER COLUMN currency DROP DEFAULT,
+    ADD CONSTRAINT products_sku_key UNIQUE (sku);
@@ -0,0 +1,24 @@
+-- Add up migration script here
+CREATE TABLE orders (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    currency VARCHAR(3) NOT NULL,
+    total BIGINT NOT NULL CHECK (total >= 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX orders_user_id_idx ON orders (user_id);
+
+-- 商品が後から変更・削除されても注文時の内容が残るように、名前・SKU・単価をコピーしておく
+CREATE TABLE order_items (
+    id SERIAL PRIMARY KEY,
+    order_id INTEGER NOT NULL REFERENCES orders (id) ON DELETE CASCADE,
+    product_id INTEGER REFERENCES products (id) ON DELETE SET NULL,
+    name VARCHAR NOT NULL,
+    sku VARCHAR NOT NULL,
+    unit_price BIGINT NOT NULL CHECK (unit_price >= 0),
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    subtotal BIGINT NOT NULL CHECK (subtotal >= 0)
+);
+
+CREATE INDEX order_items_order_id_idx ON order_items (order_id);
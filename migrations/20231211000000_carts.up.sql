@@ -0,0 +1,16 @@
+-- Add up migration script here
+-- ログイン中のユーザーのカートはuser_id、未ログインのカートはcookieに入れたtokenで引く
+CREATE TABLE carts (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER UNIQUE REFERENCES users (id) ON DELETE CASCADE,
+    token UUID UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CHECK ((user_id IS NULL) <> (token IS NULL))
+);
+
+CREATE TABLE cart_items (
+    cart_id INTEGER NOT NULL REFERENCES carts (id) ON DELETE CASCADE,
+    product_id INTEGER NOT NULL REFERENCES products (id) ON DELETE CASCADE,
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    PRIMARY KEY (cart_id, product_id)
+);
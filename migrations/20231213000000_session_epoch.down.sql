@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP INDEX user_credentials_lower_email_key;
+ALTER TABLE user_credentials DROP COLUMN session_epoch;
@@ -0,0 +1,6 @@
+-- Add up migration script here
+-- ログインのcookieに入れる世代。ログアウトとパスワードの変更で1つ進め、それより前に発行したcookieを無効にする
+ALTER TABLE user_credentials ADD COLUMN session_epoch INTEGER NOT NULL DEFAULT 0;
+
+-- メールアドレスは大文字小文字を区別せずに一意にする
+CREATE UNIQUE INDEX user_credentials_lower_email_key ON user_credentials (lower(email));
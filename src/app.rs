@@ -1,3 +1,6 @@
+use crate::auth::api_key::UsageBuffer;
+use crate::auth::jwt::JwtVerifier;
+use crate::config::Config;
 use crate::repositories::repositories::{create_repos, Repos};
 use crate::use_cases::use_cases::{create_use_cases, UseCases};
 use rocket::State;
@@ -8,16 +11,29 @@ pub type AppState = State<Arc<App>>;
 pub struct App {
     pub use_cases: UseCases,
     pub repos: Repos,
+    // jwtの設定が無い場合はBearerトークンをすべて401にする
+    pub jwt: Option<JwtVerifier>,
+    pub api_key_usage: UsageBuffer,
 }
 
 impl App {
     pub fn new(use_cases: UseCases, repos: Repos) -> Self {
-        Self { use_cases, repos }
+        Self {
+            use_cases,
+            repos,
+            jwt: None,
+            api_key_usage: UsageBuffer::default(),
+        }
     }
 }
 
-pub fn create_app() -> Arc<App> {
-    let repos = create_repos();
+pub fn create_app(config: &Config) -> Arc<App> {
+    let repos = create_repos(config.cache.as_ref());
     let use_cases = create_use_cases();
-    Arc::new(App::new(use_cases, repos))
+    let mut app = App::new(use_cases, repos);
+    app.jwt = config
+        .jwt
+        .as_ref()
+        .map(|jwt| JwtVerifier::new(jwt).expect("valid jwt configuration"));
+    Arc::new(app)
 }
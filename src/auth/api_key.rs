@@ -0,0 +1,158 @@
+use crate::app::App;
+use crate::db::Db;
+use crate::models::api_key_model::ApiKeyUsage;
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::Utc;
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::{Orbit, Rocket};
+use rocket_db_pools::Database;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+pub const API_KEY_HEADER: &str = "X-Api-Key";
+const KEY_PREFIX: &str = "rk_";
+// 一覧でキーを見分けるために保存する先頭の文字数
+const PREFIX_LEN: usize = 11;
+const FLUSH_INTERVAL: Duration = Duration::from_secs(10);
+
+pub fn generate_api_key() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    format!("{}{}", KEY_PREFIX, URL_SAFE_NO_PAD.encode(bytes))
+}
+
+// キーは十分に長いランダム値なので、パスワードと違いsaltや遅いハッシュは不要
+// 索引で引けるようにSHA-256のままで保存する
+pub fn hash_api_key(key: &str) -> String {
+    format!("{:x}", Sha256::digest(key.as_bytes()))
+}
+
+pub fn key_prefix(key: &str) -> &str {
+    key.get(..PREFIX_LEN).unwrap_or(key)
+}
+
+// リクエストごとにDBへ書き込まないよう、利用状況をメモリに溜めてまとめて書き込む
+#[derive(Default)]
+pub struct UsageBuffer {
+    entries: Mutex<HashMap<i32, ApiKeyUsage>>,
+}
+
+impl UsageBuffer {
+    pub fn record(&self, id: i32) {
+        let now = Utc::now();
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.entry(id).or_insert(ApiKeyUsage {
+            id,
+            count: 0,
+            last_used_at: now,
+        });
+        entry.count += 1;
+        entry.last_used_at = now;
+    }
+
+    pub fn drain(&self) -> Vec<ApiKeyUsage> {
+        let mut entries = self.entries.lock().unwrap();
+        let mut usage: Vec<ApiKeyUsage> = entries.drain().map(|(_, usage)| usage).collect();
+        // 同時に書き込む処理とロックの順番を揃える
+        usage.sort_by_key(|usage| usage.id);
+        usage
+    }
+}
+
+// 起動後は一定間隔で、終了時には残りを書き込むフェアリング
+pub struct UsageFlusher;
+
+impl UsageFlusher {
+    fn state(rocket: &Rocket<Orbit>) -> Option<(Arc<App>, PgPool)> {
+        let app = rocket.state::<Arc<App>>()?.clone();
+        let pool = PgPool::clone(Db::fetch(rocket)?);
+        Some((app, pool))
+    }
+
+    async fn flush(app: &App, pool: &PgPool) {
+        let usage = app.api_key_usage.drain();
+        if usage.is_empty() {
+            return;
+        }
+        let mut con = match pool.acquire().await {
+            Ok(con) => con,
+            Err(e) => {
+                tracing::warn!("{} ({}:{})", e, file!(), line!());
+                return;
+            }
+        };
+        // 書き込めなかった分は捨てる。利用状況の記録のためにAPIを止めることはしない
+        if let Err(e) = app
+            .use_cases
+            .api_key
+            .record_usage(&app.repos, &mut con, &usage)
+            .await
+        {
+            tracing::warn!("{} ({}:{})", e, file!(), line!());
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl Fairing for UsageFlusher {
+    fn info(&self) -> Info {
+        Info {
+            name: "API key usage flusher",
+            kind: Kind::Liftoff | Kind::Shutdown,
+        }
+    }
+
+    async fn on_liftoff(&self, rocket: &Rocket<Orbit>) {
+        let (app, pool) = match Self::state(rocket) {
+            Some(state) => state,
+            None => return,
+        };
+        rocket::tokio::spawn(async move {
+            let mut interval = rocket::tokio::time::interval(FLUSH_INTERVAL);
+            loop {
+                interval.tick().await;
+                Self::flush(&app, &pool).await;
+            }
+        });
+    }
+
+    async fn on_shutdown(&self, rocket: &Rocket<Orbit>) {
+        if let Some((app, pool)) = Self::state(rocket) {
+            Self::flush(&app, &pool).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_and_hash() {
+        let key = generate_api_key();
+        assert!(key.starts_with(KEY_PREFIX));
+        assert_ne!(key, generate_api_key());
+        assert_eq!(key_prefix(&key).len(), PREFIX_LEN);
+        assert_eq!(hash_api_key(&key), hash_api_key(&key));
+        assert_eq!(hash_api_key(&key).len(), 64);
+    }
+
+    #[test]
+    fn test_usage_buffer_accumulates_until_drained() {
+        let buffer = UsageBuffer::default();
+        buffer.record(2);
+        buffer.record(1);
+        buffer.record(2);
+        let usage = buffer.drain();
+        assert_eq!(
+            usage.iter().map(|u| (u.id, u.count)).collect::<Vec<_>>(),
+            vec![(1, 1), (2, 2)]
+        );
+        assert!(buffer.drain().is_empty());
+    }
+}
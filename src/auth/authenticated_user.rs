@@ -0,0 +1,110 @@
+use crate::app::AppState;
+use crate::db::ConnectionDb;
+use crate::error::app_error::AppError;
+use crate::error::catchers::GuardRejection;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use rocket::http::{Cookie, CookieJar, SameSite, Status};
+use rocket::request::{FromRequest, Outcome};
+use rocket::Request;
+
+pub const SESSION_COOKIE: &str = "session";
+
+// ログインの有効期間。cookieが盗まれても、この時間を過ぎれば使えなくなる
+const SESSION_HOURS: i64 = 12;
+
+// ログイン中のユーザー。コントローラの引数に取ると、未ログインのリクエストは401になる
+// セッションの世代の確認でコネクションを1つ使って返すので、ConnectionDbより前に書く
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuthenticatedUser {
+    pub user_id: i32,
+}
+
+impl AuthenticatedUser {
+    // secret_keyで暗号化されたprivate cookieにユーザーID、セッションの世代とログインした時刻を保存する
+    pub fn login(cookies: &CookieJar<'_>, user_id: i32, session_epoch: i32) {
+        cookies.add_private(Self::session_cookie(user_id, session_epoch, Utc::now()));
+    }
+
+    // 値は "ユーザーID:セッションの世代:ログイン時刻(UNIX秒)"。max_ageはブラウザ向けで、期限はparse_cookieで確認する
+    pub fn session_cookie(
+        user_id: i32,
+        session_epoch: i32,
+        issued_at: DateTime<Utc>,
+    ) -> Cookie<'static> {
+        Cookie::build(
+            SESSION_COOKIE,
+            format!("{}:{}:{}", user_id, session_epoch, issued_at.timestamp()),
+        )
+        .http_only(true)
+        .same_site(SameSite::Strict)
+        .max_age(rocket::time::Duration::hours(SESSION_HOURS))
+        .finish()
+    }
+
+    pub fn logout(cookies: &CookieJar<'_>) {
+        cookies.remove_private(Cookie::named(SESSION_COOKIE));
+    }
+
+    // ユーザーIDとセッションの世代を返す
+    // 期限切れや、世代の無い古い形式のcookieは未ログインとして扱う
+    fn parse_cookie(cookies: &CookieJar<'_>) -> Option<(i32, i32)> {
+        let cookie = cookies.get_private(SESSION_COOKIE)?;
+        let mut parts = cookie.value().split(':');
+        let user_id = parts.next()?.parse::<i32>().ok()?;
+        let session_epoch = parts.next()?.parse::<i32>().ok()?;
+        let issued_at = Utc.timestamp_opt(parts.next()?.parse().ok()?, 0).single()?;
+        if parts.next().is_some() {
+            return None;
+        }
+        let now = Utc::now();
+        if issued_at > now || now - issued_at >= Duration::hours(SESSION_HOURS) {
+            return None;
+        }
+        Some((user_id, session_epoch))
+    }
+
+    // ログインしていなくても失敗させたくないガード(カートなど)から使う
+    // cookieの世代がDBの世代と違えば、ログアウトかパスワードの変更で無効になったcookieとしてNoneを返す
+    pub async fn from_session(req: &Request<'_>) -> Result<Option<Self>, AppError> {
+        let (user_id, session_epoch) = match Self::parse_cookie(req.cookies()) {
+            Some(session) => session,
+            None => return Ok(None),
+        };
+        let app = req
+            .guard::<&AppState>()
+            .await
+            .succeeded()
+            .ok_or(AppError::InternalServerError)?;
+        let mut db = req
+            .guard::<ConnectionDb>()
+            .await
+            .succeeded()
+            .ok_or(AppError::InternalServerError)?;
+        let current = app
+            .use_cases
+            .auth
+            .session_epoch(&app.repos, &mut db, user_id)
+            .await?;
+        if current != Some(session_epoch) {
+            Self::logout(req.cookies());
+            return Ok(None);
+        }
+        Ok(Some(AuthenticatedUser { user_id }))
+    }
+}
+
+#[async_trait]
+impl<'r> FromRequest<'r> for AuthenticatedUser {
+    type Error = AppError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        match AuthenticatedUser::from_session(req).await {
+            Ok(Some(user)) => Outcome::Success(user),
+            Ok(None) => {
+                GuardRejection::reject(req, "Login is required");
+                Outcome::Failure((Status::Unauthorized, AppError::Unauthorized))
+            }
+            Err(e) => Outcome::Failure((Status::InternalServerError, e)),
+        }
+    }
+}
@@ -0,0 +1,437 @@
+use crate::app::AppState;
+use crate::auth::api_key::API_KEY_HEADER;
+use crate::auth::authenticated_user::AuthenticatedUser;
+use crate::auth::jwt::{bearer_token, Claims};
+use crate::db::ConnectionDb;
+use crate::error::app_error::AppError;
+use crate::error::catchers::GuardRejection;
+use rocket::http::Status;
+use rocket::outcome::try_outcome;
+use rocket::request::{FromRequest, Outcome};
+use rocket::Request;
+use std::marker::PhantomData;
+
+// ガードで要求する権限。role_permissionsテーブルのpermission、APIキーのscopeと同じ文字列
+pub trait Permission: Send + Sync {
+    const NAME: &'static str;
+}
+
+pub struct UsersWrite;
+
+impl Permission for UsersWrite {
+    const NAME: &'static str = "users:write";
+}
+
+pub struct ProductsWrite;
+
+impl Permission for ProductsWrite {
+    const NAME: &'static str = "products:write";
+}
+
+pub struct ApiKeysManage;
+
+impl Permission for ApiKeysManage {
+    const NAME: &'static str = "api_keys:manage";
+}
+
+// APIキーに付与できるscope。APIキーでAPIキーを発行できないよう、api_keys:manageは含めない
+pub const API_KEY_SCOPES: &[&str] = &[UsersWrite::NAME, ProductsWrite::NAME];
+
+// 認可されたリクエストの主体
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Principal {
+    User(AuthenticatedUser),
+    ApiKey { id: i32 },
+    Token { sub: String },
+}
+
+// 権限Pを持つユーザーまたはAPIキー。コントローラの引数に `_auth: Authorized<UsersWrite>` のように書く
+// X-Api-Keyヘッダがあればそのscopeを、Bearerトークンがあればそのrolesの権限を、
+// どちらも無ければログイン中のユーザーのロールを確認する
+// 認証できなければ401、権限が無ければ403になる
+// ガードは引数の順に解決される。権限の確認でコネクションを1つ使って返すので、ConnectionDbより前に書く
+// (後に書くとハンドラのコネクションを持ったままもう1つ取ることになり、プールが枯渇しうる)
+pub struct Authorized<P: Permission> {
+    pub principal: Principal,
+    permission: PhantomData<P>,
+}
+
+impl<P: Permission> Authorized<P> {
+    fn new(principal: Principal) -> Self {
+        Self {
+            principal,
+            permission: PhantomData,
+        }
+    }
+
+    // APIキーとBearerトークンの場合はNone
+    pub fn user_id(&self) -> Option<i32> {
+        match self.principal {
+            Principal::User(user) => Some(user.user_id),
+            Principal::ApiKey { .. } | Principal::Token { .. } => None,
+        }
+    }
+}
+
+fn forbidden<P: Permission>(req: &Request<'_>, detail: &str) -> Outcome<Authorized<P>, AppError> {
+    GuardRejection::reject(req, detail);
+    Outcome::Failure((Status::Forbidden, AppError::Forbidden))
+}
+
+async fn app_and_db<'r>(req: &'r Request<'_>) -> Option<(&'r AppState, ConnectionDb)> {
+    let app = req.guard::<&AppState>().await.succeeded()?;
+    let db = req.guard::<ConnectionDb>().await.succeeded()?;
+    Some((app, db))
+}
+
+async fn authorize_api_key<P: Permission>(
+    req: &Request<'_>,
+    key: &str,
+) -> Outcome<Authorized<P>, AppError> {
+    let (app, mut db) = match app_and_db(req).await {
+        Some(state) => state,
+        None => {
+            return Outcome::Failure((Status::InternalServerError, AppError::InternalServerError))
+        }
+    };
+    let api_key = match app
+        .use_cases
+        .api_key
+        .authenticate(&app.repos, &mut db, key)
+        .await
+    {
+        Ok(api_key) => api_key,
+        Err(AppError::Unauthorized) => {
+            GuardRejection::reject(req, "API key is invalid or revoked");
+            return Outcome::Failure((Status::Unauthorized, AppError::Unauthorized));
+        }
+        Err(e) => return Outcome::Failure((Status::InternalServerError, e)),
+    };
+    app.api_key_usage.record(api_key.id);
+    if !api_key.has_scope(P::NAME) {
+        tracing::info!(
+            api_key_id = api_key.id,
+            scope = P::NAME,
+            "permission denied"
+        );
+        return forbidden(req, &format!("API key scope '{}' is required", P::NAME));
+    }
+    Outcome::Success(Authorized::new(Principal::ApiKey { id: api_key.id }))
+}
+
+async fn authorize_user<P: Permission>(
+    req: &Request<'_>,
+    user: AuthenticatedUser,
+) -> Outcome<Authorized<P>, AppError> {
+    let (app, mut db) = match app_and_db(req).await {
+        Some(state) => state,
+        None => {
+            return Outcome::Failure((Status::InternalServerError, AppError::InternalServerError))
+        }
+    };
+    let permissions = match app
+        .use_cases
+        .auth
+        .permissions(&app.repos, &mut db, user.user_id)
+        .await
+    {
+        Ok(permissions) => permissions,
+        Err(e) => return Outcome::Failure((Status::InternalServerError, e)),
+    };
+    if !permissions.iter().any(|p| p == P::NAME) {
+        tracing::info!(
+            user_id = user.user_id,
+            permission = P::NAME,
+            "permission denied"
+        );
+        return forbidden(req, &format!("Permission '{}' is required", P::NAME));
+    }
+    Outcome::Success(Authorized::new(Principal::User(user)))
+}
+
+// トークンのrolesはユーザーのロールと同じくrole_permissionsで権限に変換する
+async fn authorize_token<P: Permission>(
+    req: &Request<'_>,
+    claims: Claims,
+) -> Outcome<Authorized<P>, AppError> {
+    let (app, mut db) = match app_and_db(req).await {
+        Some(state) => state,
+        None => {
+            return Outcome::Failure((Status::InternalServerError, AppError::InternalServerError))
+        }
+    };
+    let permissions = match app
+        .use_cases
+        .auth
+        .role_permissions(&app.repos, &mut db, &claims.roles)
+        .await
+    {
+        Ok(permissions) => permissions,
+        Err(e) => return Outcome::Failure((Status::InternalServerError, e)),
+    };
+    if !permissions.iter().any(|p| p == P::NAME) {
+        tracing::info!(sub = %claims.sub, permission = P::NAME, "permission denied");
+        return forbidden(req, &format!("Permission '{}' is required", P::NAME));
+    }
+    Outcome::Success(Authorized::new(Principal::Token { sub: claims.sub }))
+}
+
+#[async_trait]
+impl<'r, P: Permission> FromRequest<'r> for Authorized<P> {
+    type Error = AppError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        if let Some(key) = req.headers().get_one(API_KEY_HEADER) {
+            return authorize_api_key(req, key).await;
+        }
+        // トークンが不正な場合はClaimsガードがWWW-Authenticateを付けて401にする
+        if bearer_token(req).is_some() {
+            let claims = try_outcome!(req.guard::<Claims>().await);
+            return authorize_token(req, claims).await;
+        }
+        let user = try_outcome!(req.guard::<AuthenticatedUser>().await);
+        authorize_user(req, user).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Authorized, UsersWrite};
+    use crate::config::{Config, LoggingConfig};
+    use crate::db::Db;
+    use crate::error::app_error::AppError;
+    use crate::error::catchers::catchers;
+    use crate::models::api_key_model::ApiKey;
+    use crate::telemetry::logging::fmt_layer;
+    use crate::telemetry::redact::Redact;
+    use crate::test::app::create_app_for_test;
+    use crate::test::auth::{
+        bearer_header_for_test, jwt_verifier_for_test, session_auth_use_case_for_test,
+        session_cookie_for_test,
+    };
+    use crate::test::log::LogBuffer;
+    use crate::use_cases::api_key_use_case::MockApiKeyUseCase;
+    use chrono::Utc;
+    use rocket::fairing::AdHoc;
+    use rocket::http::Header;
+    use rocket::http::Status;
+    use rocket::local::asynchronous::Client;
+    use rocket::serde::json::Value;
+    use rocket_db_pools::Database;
+    use std::sync::Arc;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[post("/")]
+    fn write(auth: Authorized<UsersWrite>) -> String {
+        format!("{:?}", auth.user_id())
+    }
+
+    fn api_key(scopes: &[&str]) -> ApiKey {
+        ApiKey {
+            id: 5,
+            name: "partner".to_string(),
+            prefix: "rk_abcdefgh".to_string(),
+            key_hash: "hash".to_string(),
+            scopes: scopes.iter().map(|s| s.to_string()).collect(),
+            created_by: None,
+            created_at: Utc::now(),
+            revoked_at: None,
+            last_used_at: None,
+            use_count: 0,
+        }
+    }
+
+    async fn client(permissions: Vec<&'static str>) -> Client {
+        let mut mock_auth_use_case = session_auth_use_case_for_test();
+        let granted = permissions.clone();
+        mock_auth_use_case
+            .expect_permissions()
+            .returning(move |_, _, _| Ok(granted.iter().map(|p| p.to_string()).collect()));
+        let granted = permissions.clone();
+        mock_auth_use_case
+            .expect_role_permissions()
+            .returning(move |_, _, roles| {
+                assert_eq!(roles, ["editor".to_string()]);
+                Ok(granted.iter().map(|p| p.to_string()).collect())
+            });
+        let mut mock_api_key_use_case = MockApiKeyUseCase::new();
+        mock_api_key_use_case
+            .expect_authenticate()
+            .returning(move |_, _, key| match key {
+                "rk_valid" => Ok(api_key(&permissions)),
+                _ => Err(AppError::Unauthorized),
+            });
+        let mut app_state = create_app_for_test();
+        app_state.use_cases.auth = Box::new(mock_auth_use_case);
+        app_state.use_cases.api_key = Box::new(mock_api_key_use_case);
+        app_state.jwt = Some(jwt_verifier_for_test());
+
+        let rocket = rocket::build()
+            .manage(Arc::new(app_state))
+            .attach(Db::init())
+            .attach(AdHoc::config::<Config>())
+            .register("/", catchers())
+            .mount("/", routes![write]);
+        Client::tracked(rocket)
+            .await
+            .expect("valid rocket instance")
+    }
+
+    #[rocket::async_test]
+    async fn test_unauthorized_without_session() {
+        let client = client(vec!["users:write"]).await;
+        let response = client.post("/").dispatch().await;
+
+        assert_eq!(response.status(), Status::Unauthorized);
+        let body: Value = response.into_json().await.expect("valid problem json");
+        assert_eq!(body["detail"], "Login is required");
+    }
+
+    #[rocket::async_test]
+    async fn test_forbidden_without_permission() {
+        let client = client(vec!["products:write"]).await;
+        let response = client
+            .post("/")
+            .private_cookie(session_cookie_for_test(3))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Forbidden);
+        let body: Value = response.into_json().await.expect("valid problem json");
+        assert_eq!(body["code"], "FORBIDDEN");
+        assert_eq!(body["detail"], "Permission 'users:write' is required");
+    }
+
+    #[rocket::async_test]
+    async fn test_success_with_permission() {
+        let client = client(vec!["users:write"]).await;
+        let response = client
+            .post("/")
+            .private_cookie(session_cookie_for_test(3))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.into_string().await.unwrap(), "Some(3)");
+    }
+
+    #[rocket::async_test]
+    async fn test_api_key_scopes() {
+        let allowed = client(vec!["users:write"]).await;
+        let response = allowed
+            .post("/")
+            .header(Header::new("X-Api-Key", "rk_valid"))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.into_string().await.unwrap(), "None");
+
+        let denied = client(vec!["products:write"]).await;
+        let response = denied
+            .post("/")
+            .header(Header::new("X-Api-Key", "rk_valid"))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Forbidden);
+        let body: Value = response.into_json().await.expect("valid problem json");
+        assert_eq!(body["detail"], "API key scope 'users:write' is required");
+    }
+
+    #[rocket::async_test]
+    async fn test_invalid_api_key() {
+        let client = client(vec!["users:write"]).await;
+        let response = client
+            .post("/")
+            .header(Header::new("X-Api-Key", "rk_revoked"))
+            .private_cookie(session_cookie_for_test(3))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Unauthorized);
+        let body: Value = response.into_json().await.expect("valid problem json");
+        assert_eq!(body["detail"], "API key is invalid or revoked");
+    }
+
+    #[rocket::async_test]
+    async fn test_bearer_token_roles() {
+        let allowed = client(vec!["users:write"]).await;
+        let response = allowed
+            .post("/")
+            .header(bearer_header_for_test(&["editor"]))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.into_string().await.unwrap(), "None");
+
+        let denied = client(vec!["products:write"]).await;
+        let response = denied
+            .post("/")
+            .header(bearer_header_for_test(&["editor"]))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Forbidden);
+        let body: Value = response.into_json().await.expect("valid problem json");
+        assert_eq!(body["detail"], "Permission 'users:write' is required");
+    }
+
+    #[rocket::async_test]
+    async fn test_invalid_bearer_token() {
+        let client = client(vec!["users:write"]).await;
+        let response = client
+            .post("/")
+            .header(Header::new("Authorization", "Bearer not-a-jwt"))
+            .private_cookie(session_cookie_for_test(3))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Unauthorized);
+        assert!(response.headers().get_one("WWW-Authenticate").is_some());
+    }
+
+    #[rocket::async_test]
+    async fn test_permission_denied_log_is_redacted() {
+        let config = LoggingConfig {
+            filter: Some("info".to_string()),
+            redact: vec!["sub".to_string(), "user_id".to_string()],
+            ..LoggingConfig::default()
+        };
+        let buffer = LogBuffer::default();
+        let writer = buffer.clone();
+        let layer = fmt_layer(
+            &config,
+            &Redact::new(&config.redact),
+            move || writer.clone(),
+            false,
+        )
+        .unwrap();
+        let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(layer));
+
+        let client = client(vec!["products:write"]).await;
+        let response = client
+            .post("/")
+            .private_cookie(session_cookie_for_test(48213))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Forbidden);
+        let response = client
+            .post("/")
+            .header(bearer_header_for_test(&["editor"]))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Forbidden);
+
+        let logs = buffer.contents();
+        assert!(
+            logs.contains("permission denied user_id=[REDACTED] permission=\"users:write\""),
+            "{}",
+            logs
+        );
+        assert!(
+            logs.contains("permission denied sub=[REDACTED]"),
+            "{}",
+            logs
+        );
+        assert!(!logs.contains("48213"), "{}", logs);
+        assert!(!logs.contains("test-service"), "{}", logs);
+    }
+}
@@ -0,0 +1,83 @@
+use crate::app::AppState;
+use crate::auth::authenticated_user::AuthenticatedUser;
+use crate::db::ConnectionDb;
+use crate::error::app_error::AppError;
+use crate::models::cart_model::CartOwner;
+use rocket::http::{Cookie, CookieJar, SameSite, Status};
+use rocket::request::{FromRequest, Outcome};
+use rocket::time::Duration;
+use rocket::Request;
+use uuid::Uuid;
+
+pub const CART_COOKIE: &str = "cart";
+
+// 匿名のカートを識別するトークンを保存するcookieの有効期間
+const CART_COOKIE_DAYS: i64 = 30;
+
+// リクエストのカートの持ち主。ログイン中ならユーザー、そうでなければcookieのトークン
+// 未ログインでcookieが無ければ新しいトークンを発行するので、このガードは失敗しない
+// 匿名のカートを移すときにコネクションを1つ使って返すので、コントローラではConnectionDbより前に書く
+pub struct CartSession {
+    pub owner: CartOwner,
+}
+
+impl CartSession {
+    fn token(cookies: &CookieJar<'_>) -> Option<Uuid> {
+        cookies
+            .get_private(CART_COOKIE)
+            .and_then(|cookie| Uuid::parse_str(cookie.value()).ok())
+    }
+
+    fn issue_token(cookies: &CookieJar<'_>) -> Uuid {
+        let token = Uuid::new_v4();
+        let cookie = Cookie::build(CART_COOKIE, token.to_string())
+            .http_only(true)
+            .same_site(SameSite::Lax)
+            .max_age(Duration::days(CART_COOKIE_DAYS))
+            .finish();
+        cookies.add_private(cookie);
+        token
+    }
+}
+
+#[async_trait]
+impl<'r> FromRequest<'r> for CartSession {
+    type Error = AppError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let cookies = req.cookies();
+        let user = match AuthenticatedUser::from_session(req).await {
+            Ok(Some(user)) => user,
+            Ok(None) => {
+                let token = CartSession::token(cookies)
+                    .unwrap_or_else(|| CartSession::issue_token(cookies));
+                return Outcome::Success(CartSession {
+                    owner: CartOwner::Anonymous(token),
+                });
+            }
+            Err(e) => return Outcome::Failure((Status::InternalServerError, e)),
+        };
+        // ログイン後の最初のリクエストで、匿名のカートをユーザーのカートに移す
+        if let Some(token) = CartSession::token(cookies) {
+            let claimed = match req.guard::<&AppState>().await.succeeded() {
+                Some(app) => match req.guard::<ConnectionDb>().await.succeeded() {
+                    Some(mut db) => {
+                        app.use_cases
+                            .cart
+                            .claim(&app.repos, &mut db, user.user_id, token)
+                            .await
+                    }
+                    None => Err(AppError::InternalServerError),
+                },
+                None => Err(AppError::InternalServerError),
+            };
+            if let Err(e) = claimed {
+                return Outcome::Failure((Status::InternalServerError, e));
+            }
+            cookies.remove_private(Cookie::named(CART_COOKIE));
+        }
+        Outcome::Success(CartSession {
+            owner: CartOwner::User(user.user_id),
+        })
+    }
+}
@@ -0,0 +1,357 @@
+use crate::app::AppState;
+use crate::config::JwtConfig;
+use crate::error::app_error::AppError;
+use crate::error::catchers::GuardRejection;
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use rocket::http::Status;
+use rocket::outcome::Outcome;
+use rocket::request::{self, FromRequest};
+use rocket::Request;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use thiserror::Error;
+use utoipa::ToSchema;
+
+const REALM: &str = "rust-rocket-sqlx-sample";
+
+#[derive(Debug, Error)]
+pub enum JwtError {
+    #[error("token has no kid and more than one key is configured")]
+    MissingKid,
+    #[error("unknown kid {0}")]
+    UnknownKid(String),
+    #[error("key {kid} is invalid: {message}")]
+    InvalidKey { kid: String, message: String },
+    #[error("{0}")]
+    Token(#[from] jsonwebtoken::errors::Error),
+}
+
+// Bearerトークンのクレーム。コントローラの引数に取ると、トークンが無いか不正なリクエストは401になる
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Claims {
+    pub sub: String,
+    #[serde(default)]
+    pub roles: Vec<String>,
+    pub exp: u64,
+}
+
+impl Claims {
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|r| r == role)
+    }
+}
+
+// kidごとの検証鍵。鍵のアルゴリズム以外で署名されたトークンは受け付けない
+pub struct JwtVerifier {
+    keys: HashMap<String, (DecodingKey, Validation)>,
+}
+
+impl JwtVerifier {
+    pub fn new(config: &JwtConfig) -> Result<Self, JwtError> {
+        let mut keys = HashMap::new();
+        for key in &config.keys {
+            let invalid = |message: &str| JwtError::InvalidKey {
+                kid: key.kid.clone(),
+                message: message.to_string(),
+            };
+            let algorithm = Algorithm::from_str(&key.algorithm)
+                .map_err(|_| invalid("unsupported algorithm"))?;
+            let decoding_key = match (algorithm, &key.secret, &key.public_key) {
+                (Algorithm::HS256, Some(secret), _) => DecodingKey::from_secret(secret.as_bytes()),
+                (Algorithm::RS256, _, Some(public_key)) => {
+                    DecodingKey::from_rsa_pem(public_key.as_bytes())
+                        .map_err(|e| invalid(&e.to_string()))?
+                }
+                (Algorithm::HS256, None, _) => return Err(invalid("HS256 requires secret")),
+                (Algorithm::RS256, _, None) => return Err(invalid("RS256 requires public_key")),
+                _ => return Err(invalid("only HS256 and RS256 are supported")),
+            };
+            let mut validation = Validation::new(algorithm);
+            validation.leeway = config.leeway.unwrap_or(validation.leeway);
+            if let Some(issuer) = &config.issuer {
+                validation.set_issuer(&[issuer]);
+            }
+            if let Some(audience) = &config.audience {
+                validation.set_audience(&[audience]);
+            }
+            keys.insert(key.kid.clone(), (decoding_key, validation));
+        }
+        Ok(Self { keys })
+    }
+
+    pub fn verify(&self, token: &str) -> Result<Claims, JwtError> {
+        let header = decode_header(token)?;
+        let (key, validation) = match header.kid {
+            Some(kid) => self.keys.get(&kid).ok_or(JwtError::UnknownKid(kid))?,
+            // 鍵が1つだけならkidを省略できる
+            None if self.keys.len() == 1 => self.keys.values().next().unwrap(),
+            None => return Err(JwtError::MissingKid),
+        };
+        let data = decode::<Claims>(token, key, validation)?;
+        Ok(data.claims)
+    }
+}
+
+pub fn bearer_token<'r>(req: &'r Request<'_>) -> Option<&'r str> {
+    let value = req.headers().get_one("Authorization")?;
+    let (scheme, token) = value.split_once(' ')?;
+    if scheme.eq_ignore_ascii_case("Bearer") {
+        Some(token.trim())
+    } else {
+        None
+    }
+}
+
+fn reject(
+    req: &Request<'_>,
+    detail: &str,
+    error: Option<&str>,
+) -> request::Outcome<Claims, AppError> {
+    // RFC 6750: トークンが無い場合はerrorを付けない
+    let challenge = match error {
+        Some(error) => format!(
+            "Bearer realm=\"{}\", error=\"invalid_token\", error_description=\"{}\"",
+            REALM,
+            error.replace('"', "'")
+        ),
+        None => format!("Bearer realm=\"{}\"", REALM),
+    };
+    GuardRejection::reject_with_challenge(req, detail, &challenge);
+    Outcome::Failure((Status::Unauthorized, AppError::Unauthorized))
+}
+
+#[async_trait]
+impl<'r> FromRequest<'r> for Claims {
+    type Error = AppError;
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let app = match req.guard::<&AppState>().await {
+            Outcome::Success(app) => app,
+            _ => {
+                return Outcome::Failure((
+                    Status::InternalServerError,
+                    AppError::InternalServerError,
+                ))
+            }
+        };
+        let verifier = match &app.jwt {
+            Some(verifier) => verifier,
+            None => return reject(req, "Bearer authentication is not configured", None),
+        };
+        let token = match bearer_token(req) {
+            Some(token) => token,
+            None => return reject(req, "Bearer token is required", None),
+        };
+        match verifier.verify(token) {
+            Ok(claims) => Outcome::Success(claims),
+            Err(e) => {
+                tracing::info!("invalid bearer token: {}", e);
+                let message = e.to_string();
+                reject(
+                    req,
+                    &format!("Invalid bearer token: {}", message),
+                    Some(&message),
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::JwtKeyConfig;
+    use crate::error::catchers::catchers;
+    use crate::test::app::create_app_for_test;
+    use jsonwebtoken::{encode, get_current_timestamp, EncodingKey, Header};
+    use rocket::http::Header as HttpHeader;
+    use rocket::local::asynchronous::Client;
+    use rocket::serde::json::Value;
+    use std::sync::Arc;
+
+    const RSA_PRIVATE_KEY: &str = include_str!("../test/fixture/keys/rsa_private.pem");
+    const RSA_PUBLIC_KEY: &str = include_str!("../test/fixture/keys/rsa_public.pem");
+
+    fn hs256_key(kid: &str, secret: &str) -> JwtKeyConfig {
+        JwtKeyConfig {
+            kid: kid.to_string(),
+            algorithm: "HS256".to_string(),
+            secret: Some(secret.to_string()),
+            public_key: None,
+        }
+    }
+
+    fn rs256_key(kid: &str) -> JwtKeyConfig {
+        JwtKeyConfig {
+            kid: kid.to_string(),
+            algorithm: "RS256".to_string(),
+            secret: None,
+            public_key: Some(RSA_PUBLIC_KEY.to_string()),
+        }
+    }
+
+    fn verifier(keys: Vec<JwtKeyConfig>) -> JwtVerifier {
+        let config = JwtConfig {
+            keys,
+            issuer: None,
+            audience: None,
+            leeway: Some(0),
+        };
+        JwtVerifier::new(&config).unwrap()
+    }
+
+    fn claims(exp: u64) -> Claims {
+        Claims {
+            sub: "billing-service".to_string(),
+            roles: vec!["editor".to_string()],
+            exp,
+        }
+    }
+
+    fn hs256_token(kid: Option<&str>, secret: &str, claims: &Claims) -> String {
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = kid.map(|kid| kid.to_string());
+        encode(
+            &header,
+            claims,
+            &EncodingKey::from_secret(secret.as_bytes()),
+        )
+        .unwrap()
+    }
+
+    fn rs256_token(kid: &str, claims: &Claims) -> String {
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = Some(kid.to_string());
+        let key = EncodingKey::from_rsa_pem(RSA_PRIVATE_KEY.as_bytes()).unwrap();
+        encode(&header, claims, &key).unwrap()
+    }
+
+    fn valid_claims() -> Claims {
+        claims(get_current_timestamp() + 60)
+    }
+
+    #[test]
+    fn test_verify_hs256_with_rotated_keys() {
+        let verifier = verifier(vec![
+            hs256_key("old", "old secret"),
+            hs256_key("new", "new secret"),
+        ]);
+        let claims = valid_claims();
+        let old = hs256_token(Some("old"), "old secret", &claims);
+        let new = hs256_token(Some("new"), "new secret", &claims);
+        assert_eq!(verifier.verify(&old).unwrap(), claims);
+        assert_eq!(verifier.verify(&new).unwrap(), claims);
+        assert!(verifier.verify(&new).unwrap().has_role("editor"));
+
+        let wrong_secret = hs256_token(Some("new"), "old secret", &claims);
+        assert!(verifier.verify(&wrong_secret).is_err());
+        let unknown = hs256_token(Some("retired"), "old secret", &claims);
+        assert!(matches!(
+            verifier.verify(&unknown),
+            Err(JwtError::UnknownKid(_))
+        ));
+        let no_kid = hs256_token(None, "new secret", &claims);
+        assert!(matches!(
+            verifier.verify(&no_kid),
+            Err(JwtError::MissingKid)
+        ));
+    }
+
+    #[test]
+    fn test_verify_rs256() {
+        let verifier = verifier(vec![rs256_key("rsa")]);
+        let claims = valid_claims();
+        assert_eq!(
+            verifier.verify(&rs256_token("rsa", &claims)).unwrap(),
+            claims
+        );
+    }
+
+    #[test]
+    fn test_verify_rejects_algorithm_mismatch() {
+        // RS256の公開鍵をHS256の共通鍵として使ったトークンを受け付けない
+        let verifier = verifier(vec![rs256_key("rsa")]);
+        let token = hs256_token(Some("rsa"), RSA_PUBLIC_KEY, &valid_claims());
+        assert!(verifier.verify(&token).is_err());
+    }
+
+    #[test]
+    fn test_verify_expired() {
+        let verifier = verifier(vec![hs256_key("key", "secret")]);
+        let token = hs256_token(None, "secret", &claims(get_current_timestamp() - 10));
+        assert_eq!(
+            verifier.verify(&token).unwrap_err().to_string(),
+            "ExpiredSignature"
+        );
+    }
+
+    #[get("/")]
+    fn whoami(claims: Claims) -> String {
+        claims.sub
+    }
+
+    async fn client() -> Client {
+        let mut app_state = create_app_for_test();
+        app_state.jwt = Some(verifier(vec![hs256_key("key", "secret")]));
+        let rocket = rocket::build()
+            .manage(Arc::new(app_state))
+            .register("/", catchers())
+            .mount("/", routes![whoami]);
+        Client::tracked(rocket)
+            .await
+            .expect("valid rocket instance")
+    }
+
+    #[rocket::async_test]
+    async fn test_guard_success() {
+        let client = client().await;
+        let token = hs256_token(Some("key"), "secret", &valid_claims());
+        let response = client
+            .get("/")
+            .header(HttpHeader::new(
+                "Authorization",
+                format!("Bearer {}", token),
+            ))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.into_string().await.unwrap(), "billing-service");
+    }
+
+    #[rocket::async_test]
+    async fn test_guard_missing_token() {
+        let client = client().await;
+        let response = client.get("/").dispatch().await;
+
+        assert_eq!(response.status(), Status::Unauthorized);
+        assert_eq!(
+            response.headers().get_one("WWW-Authenticate"),
+            Some("Bearer realm=\"rust-rocket-sqlx-sample\"")
+        );
+        let body: Value = response.into_json().await.expect("valid problem json");
+        assert_eq!(body["detail"], "Bearer token is required");
+    }
+
+    #[rocket::async_test]
+    async fn test_guard_invalid_token() {
+        let client = client().await;
+        let token = hs256_token(Some("key"), "wrong", &valid_claims());
+        let response = client
+            .get("/")
+            .header(HttpHeader::new(
+                "Authorization",
+                format!("Bearer {}", token),
+            ))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Unauthorized);
+        let challenge = response.headers().get_one("WWW-Authenticate").unwrap();
+        assert!(challenge.contains("error=\"invalid_token\""));
+        let body: Value = response.into_json().await.expect("valid problem json");
+        assert_eq!(body["code"], "UNAUTHORIZED");
+        assert_eq!(body["detail"], "Invalid bearer token: InvalidSignature");
+    }
+}
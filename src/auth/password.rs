@@ -0,0 +1,74 @@
+use crate::error::app_error::AppError;
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use tokio::task::{spawn_blocking, JoinError};
+
+pub fn hash_password(password: &str) -> Result<String, AppError> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| {
+            tracing::error!("{} ({}:{})", e, file!(), line!());
+            AppError::InternalServerError
+        })
+}
+
+pub fn verify_password(password: &str, password_hash: &str) -> bool {
+    match PasswordHash::new(password_hash) {
+        Ok(hash) => Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .is_ok(),
+        Err(_) => false,
+    }
+}
+
+// argon2は1回に数十ミリ秒CPUを使うので、非同期のコードからはブロッキング用のスレッドで実行する
+pub async fn hash_password_blocking(password: &str) -> Result<String, AppError> {
+    let password = password.to_string();
+    spawn_blocking(move || hash_password(&password))
+        .await
+        .map_err(join_error)?
+}
+
+pub async fn verify_password_blocking(
+    password: &str,
+    password_hash: &str,
+) -> Result<bool, AppError> {
+    let password = password.to_string();
+    let password_hash = password_hash.to_string();
+    spawn_blocking(move || verify_password(&password, &password_hash))
+        .await
+        .map_err(join_error)
+}
+
+fn join_error(e: JoinError) -> AppError {
+    tracing::error!("{} ({}:{})", e, file!(), line!());
+    AppError::InternalServerError
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_and_verify_password() {
+        let hash = hash_password("correct horse").unwrap();
+        assert!(hash.starts_with("$argon2"));
+        assert!(verify_password("correct horse", &hash));
+        assert!(!verify_password("wrong horse", &hash));
+        assert!(!verify_password("correct horse", "not a hash"));
+    }
+
+    #[tokio::test]
+    async fn test_hash_and_verify_password_blocking() {
+        let hash = hash_password_blocking("correct horse").await.unwrap();
+        assert!(verify_password_blocking("correct horse", &hash)
+            .await
+            .unwrap());
+        assert!(!verify_password_blocking("wrong horse", &hash)
+            .await
+            .unwrap());
+    }
+}
@@ -0,0 +1,174 @@
+use crate::app::create_app;
+use crate::cli::config_command::{self, ConfigCommand};
+use crate::cli::migrate_command::{self, MigrateCommand};
+use crate::cli::product_command::{self, ProductCommand};
+use crate::cli::user_command::{self, UserCommand};
+use crate::config::Config;
+use crate::db::Db;
+use crate::error::app_error::AppError;
+use clap::{Parser, Subcommand};
+use rocket::figment::Figment;
+use rocket_db_pools::Database;
+use sqlx::migrate::MigrateError;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::PgPool;
+use std::io::BufReader;
+use thiserror::Error;
+use tracing_subscriber::EnvFilter;
+
+// サブコマンドが無い場合はserveと同じ
+#[derive(Parser, Debug)]
+#[command(version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Start the HTTP server
+    Serve,
+    /// Apply, revert or list the embedded migrations
+    Migrate {
+        #[command(subcommand)]
+        command: MigrateCommand,
+    },
+    /// Manage users
+    Users {
+        #[command(subcommand)]
+        command: UserCommand,
+    },
+    /// Import, export or delete products
+    Products {
+        #[command(subcommand)]
+        command: ProductCommand,
+    },
+    /// Inspect the configuration
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+}
+
+#[derive(Debug, Error)]
+pub enum CliError {
+    #[error("[CliError::Config] {0}")]
+    Config(Box<rocket::figment::Error>),
+    #[error("[CliError::Sqlx] {0}")]
+    Sqlx(#[from] sqlx::Error),
+    #[error("[CliError::Migrate] {0}")]
+    Migrate(#[from] MigrateError),
+    #[error("[CliError::App] {0}")]
+    App(#[from] AppError),
+    #[error("[CliError::Io] {0}")]
+    Io(#[from] std::io::Error),
+    #[error("[CliError::Json] {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("[CliError::Invalid] {0}")]
+    Invalid(String),
+    // config checkで問題が見つかった場合。内容は出力済み
+    #[error("[CliError::CheckFailed] configuration has errors")]
+    CheckFailed,
+}
+
+impl From<rocket::figment::Error> for CliError {
+    fn from(e: rocket::figment::Error) -> Self {
+        CliError::Config(Box::new(e))
+    }
+}
+
+// serve以外のコマンドを実行する。出力は標準出力に、ログ(log_into!のエラーなど)は標準エラー出力に出す
+pub async fn run(command: Command, figment: &Figment) -> Result<(), CliError> {
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")),
+        )
+        .init();
+    let stdout = &mut std::io::stdout();
+    match command {
+        Command::Serve => unreachable!("serve is handled by main"),
+        Command::Config { command } => config_command::run(figment, command, stdout).await,
+        Command::Migrate { command } => {
+            let config: Config = figment.extract()?;
+            let pool = connect(&config).await?;
+            let result = migrate_command::run(&pool, command, stdout).await;
+            pool.close().await;
+            result
+        }
+        Command::Users { command } => {
+            let config: Config = figment.extract()?;
+            let app = create_app(&config);
+            let pool = connect(&config).await?;
+            let result = user_command::run(
+                &app,
+                &mut *pool.acquire().await?,
+                command,
+                &mut BufReader::new(std::io::stdin()),
+                stdout,
+            )
+            .await;
+            pool.close().await;
+            result
+        }
+        Command::Products { command } => {
+            let config: Config = figment.extract()?;
+            let app = create_app(&config);
+            let pool = connect(&config).await?;
+            let result =
+                product_command::run(&app, &mut *pool.acquire().await?, command, stdout).await;
+            pool.close().await;
+            result
+        }
+    }
+}
+
+// Rocket.tomlのdatabasesの設定でつなぐ
+pub async fn connect(config: &Config) -> Result<PgPool, CliError> {
+    let database = config
+        .databases
+        .get(Db::NAME)
+        .ok_or_else(|| CliError::Invalid(format!("databases.{} is not set", Db::NAME)))?;
+    let pool = PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&database.url)
+        .await?;
+    Ok(pool)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let cli = Cli::try_parse_from(["app"]).unwrap();
+        assert!(cli.command.is_none());
+
+        let cli = Cli::try_parse_from(["app", "serve"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Serve)));
+
+        let cli = Cli::try_parse_from(["app", "users", "delete", "3", "5"]).unwrap();
+        match cli.command {
+            Some(Command::Users { command }) => {
+                assert_eq!(command, UserCommand::Delete { ids: vec![3, 5] })
+            }
+            command => panic!("unexpected command {:?}", command),
+        }
+
+        let cli =
+            Cli::try_parse_from(["app", "migrate", "down", "--target", "20231207000000"]).unwrap();
+        match cli.command {
+            Some(Command::Migrate { command }) => assert_eq!(
+                command,
+                MigrateCommand::Down {
+                    target: Some(20231207000000)
+                }
+            ),
+            command => panic!("unexpected command {:?}", command),
+        }
+
+        assert!(Cli::try_parse_from(["app", "migrate", "sideways"]).is_err());
+        assert!(Cli::try_parse_from(["app", "users", "create", "--age", "20"]).is_err());
+    }
+}
@@ -0,0 +1,169 @@
+use crate::app::App;
+use crate::auth::jwt::JwtVerifier;
+use crate::cli::cli::{connect, CliError};
+use crate::config::{Config, LoggingConfig};
+use crate::models::health_model::HealthStatus;
+use crate::repositories::repositories::create_repos;
+use crate::use_cases::use_cases::create_use_cases;
+use clap::Subcommand;
+use rocket::figment::Figment;
+use std::io::Write;
+use tracing_subscriber::EnvFilter;
+
+#[derive(Subcommand, Debug, PartialEq, Eq)]
+pub enum ConfigCommand {
+    /// Validate the configuration and check the database, migrations and Redis
+    Check,
+}
+
+pub async fn run(
+    figment: &Figment,
+    command: ConfigCommand,
+    out: &mut impl Write,
+) -> Result<(), CliError> {
+    match command {
+        ConfigCommand::Check => check(figment, out).await,
+    }
+}
+
+// 問題があればすべて出力してからCheckFailedを返す。秘密鍵などの値は出力しない
+async fn check(figment: &Figment, out: &mut impl Write) -> Result<(), CliError> {
+    let config: Config = match figment.extract() {
+        Ok(config) => config,
+        Err(e) => {
+            writeln!(out, "{:<12} error: {}", "config", e)?;
+            return Err(CliError::CheckFailed);
+        }
+    };
+    writeln!(out, "{:<12} ok", "config")?;
+    let mut failed = false;
+
+    let logging = config.logging.clone().unwrap_or_default();
+    let result = check_logging(&logging);
+    failed |= result.is_err();
+    report(out, "logging", result)?;
+
+    let result = match &config.jwt {
+        Some(jwt) => JwtVerifier::new(jwt).map(|_| ()).map_err(|e| e.to_string()),
+        None => Ok(()),
+    };
+    failed |= result.is_err();
+    if config.jwt.is_some() {
+        report(out, "jwt", result)?;
+    } else {
+        writeln!(out, "{:<12} not configured", "jwt")?;
+    }
+
+    // create_app()は不正なjwtの設定でpanicするので、ここではJWTを使わないAppを作る
+    let app = App::new(create_use_cases(), create_repos(config.cache.as_ref()));
+    match connect(&config).await {
+        Ok(pool) => {
+            let health = app.use_cases.health.readiness(&app.repos, &pool).await;
+            for (name, check) in health.checks {
+                let result = match check.status {
+                    HealthStatus::Up => Ok(()),
+                    HealthStatus::Down => Err(check.error.unwrap_or_default()),
+                };
+                failed |= result.is_err();
+                report(out, &name, result)?;
+            }
+            pool.close().await;
+        }
+        Err(e) => {
+            failed = true;
+            report(out, "database", Err(e.to_string()))?;
+        }
+    }
+
+    if failed {
+        Err(CliError::CheckFailed)
+    } else {
+        Ok(())
+    }
+}
+
+fn check_logging(logging: &LoggingConfig) -> Result<(), String> {
+    let filters = [
+        logging.filter.as_deref(),
+        logging
+            .otlp
+            .as_ref()
+            .and_then(|otlp| otlp.filter.as_deref()),
+    ];
+    for filter in filters.into_iter().flatten() {
+        EnvFilter::try_new(filter).map_err(|e| format!("invalid filter {:?}: {}", filter, e))?;
+    }
+    Ok(())
+}
+
+fn report(out: &mut impl Write, name: &str, result: Result<(), String>) -> std::io::Result<()> {
+    match result {
+        Ok(()) => writeln!(out, "{:<12} ok", name),
+        Err(e) => writeln!(out, "{:<12} error: {}", name, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocket::figment::providers::{Format, Toml};
+    use std::env;
+
+    fn figment(toml: &str) -> Figment {
+        dotenv::dotenv().ok();
+        let db_url = env::var("DATABASE_URL_TEST").expect("DATABASE_URL_TEST must be set");
+        Figment::new().merge(Toml::string(&format!(
+            "[databases.hoge]\nurl = {:?}\n{}",
+            db_url, toml
+        )))
+    }
+
+    async fn check_output(figment: Figment) -> (Result<(), CliError>, String) {
+        let mut out = Vec::new();
+        let result = run(&figment, ConfigCommand::Check, &mut out).await;
+        (result, String::from_utf8(out).unwrap())
+    }
+
+    #[rocket::async_test]
+    async fn test_check_ok() {
+        let (result, output) = check_output(figment("")).await;
+        assert!(result.is_ok(), "{}", output);
+        assert_eq!(
+            output,
+            "config       ok\n\
+             logging      ok\n\
+             jwt          not configured\n\
+             database     ok\n\
+             migrations   ok\n"
+        );
+    }
+
+    #[rocket::async_test]
+    async fn test_check_reports_every_error() {
+        let toml = r#"
+            [logging]
+            filter = "info,=["
+
+            [[jwt.keys]]
+            kid = "k1"
+            algorithm = "HS256"
+        "#;
+        let (result, output) = check_output(figment(toml)).await;
+        assert!(matches!(result, Err(CliError::CheckFailed)));
+        assert!(
+            output.contains("logging      error: invalid filter"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("jwt          error: key k1 is invalid"),
+            "{}",
+            output
+        );
+        assert!(output.contains("database     ok"), "{}", output);
+
+        let (result, output) = check_output(Figment::new()).await;
+        assert!(matches!(result, Err(CliError::CheckFailed)));
+        assert!(output.starts_with("config       error: "), "{}", output);
+    }
+}
@@ -0,0 +1,56 @@
+use crate::cli::cli::CliError;
+use crate::migration;
+use clap::Subcommand;
+use sqlx::PgPool;
+use std::io::Write;
+
+#[derive(Subcommand, Debug, PartialEq, Eq)]
+pub enum MigrateCommand {
+    /// Apply all pending migrations
+    Up,
+    /// Revert the latest migration, or every migration newer than --target
+    Down {
+        #[arg(long)]
+        target: Option<i64>,
+    },
+    /// List migrations and whether they are applied
+    Status,
+}
+
+pub async fn run(
+    pool: &PgPool,
+    command: MigrateCommand,
+    out: &mut impl Write,
+) -> Result<(), CliError> {
+    match command {
+        MigrateCommand::Up => {
+            let applied = migration::up(pool).await?;
+            if applied.is_empty() {
+                writeln!(out, "No pending migrations")?;
+            }
+            for version in applied {
+                writeln!(out, "Applied {}", version)?;
+            }
+        }
+        MigrateCommand::Down { target } => {
+            for version in migration::down(pool, target).await? {
+                writeln!(out, "Reverted {}", version)?;
+            }
+        }
+        MigrateCommand::Status => {
+            for migration in migration::status(&mut *pool.acquire().await?).await? {
+                let status = if migration.applied {
+                    "applied"
+                } else {
+                    "pending"
+                };
+                writeln!(
+                    out,
+                    "{} {:<8} {}",
+                    migration.version, status, migration.description
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
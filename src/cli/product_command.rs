@@ -0,0 +1,192 @@
+use crate::app::App;
+use crate::cli::cli::CliError;
+use crate::db::DbCon;
+use crate::dto::product_dto::ProductInput;
+use crate::error::app_error::AppError;
+use crate::use_cases::unit_of_work::UnitOfWork;
+use clap::Subcommand;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::PathBuf;
+use validator::Validate;
+
+#[derive(Subcommand, Debug, PartialEq, Eq)]
+pub enum ProductCommand {
+    /// Write all products as a JSON array
+    Export {
+        /// Output file (default: stdout)
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Create products from a JSON array like the export output ("-" reads stdin)
+    Import { file: PathBuf },
+    /// Delete products by id
+    Delete {
+        #[arg(required = true)]
+        ids: Vec<i32>,
+    },
+}
+
+pub async fn run(
+    app: &App,
+    con: &mut DbCon,
+    command: ProductCommand,
+    out: &mut impl Write,
+) -> Result<(), CliError> {
+    match command {
+        ProductCommand::Export { output } => {
+            let products = app.use_cases.product.find_all(&app.repos, con).await?;
+            match output {
+                Some(path) => {
+                    let mut file = BufWriter::new(File::create(&path)?);
+                    serde_json::to_writer_pretty(&mut file, &products)?;
+                    file.flush()?;
+                    writeln!(out, "Exported {} products", products.len())?;
+                }
+                None => {
+                    serde_json::to_writer_pretty(&mut *out, &products)?;
+                    writeln!(out)?;
+                }
+            }
+        }
+        ProductCommand::Import { file } => {
+            let products = if file.as_os_str() == "-" {
+                read_products(std::io::stdin().lock())?
+            } else {
+                read_products(BufReader::new(File::open(&file)?))?
+            };
+            let count = import(app, con, &products).await?;
+            writeln!(out, "Imported {} products", count)?;
+        }
+        ProductCommand::Delete { ids } => {
+            // 途中で失敗した場合はすべて取り消す
+            let mut uow = UnitOfWork::begin(con).await?;
+            for id in &ids {
+                app.use_cases
+                    .product
+                    .delete(&app.repos, uow.con(), *id)
+                    .await
+                    .map_err(|e| match e {
+                        AppError::NotFound => {
+                            CliError::Invalid(format!("product {} not found", id))
+                        }
+                        e => CliError::App(e),
+                    })?;
+            }
+            uow.commit().await?;
+            writeln!(out, "Deleted {} products", ids.len())?;
+        }
+    }
+    Ok(())
+}
+
+// exportの出力をそのまま読めるように、idは無視する
+fn read_products(reader: impl Read) -> Result<Vec<ProductInput>, CliError> {
+    let products: Vec<ProductInput> = serde_json::from_reader(reader)?;
+    for (index, product) in products.iter().enumerate() {
+        product
+            .validate()
+            .map_err(|e| CliError::Invalid(format!("products[{}]: {}", index, e)))?;
+    }
+    Ok(products)
+}
+
+// すべて登録するか、1件も登録しない
+async fn import(app: &App, con: &mut DbCon, products: &[ProductInput]) -> Result<usize, CliError> {
+    let mut uow = UnitOfWork::begin(con).await?;
+    for product in products {
+        app.use_cases
+            .product
+            .create(&app.repos, uow.con(), product)
+            .await?;
+    }
+    uow.commit().await?;
+    Ok(products.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::product_model::Product;
+    use crate::repositories::repositories::create_repos;
+    use crate::test::db::create_tx_for_test;
+    use crate::use_cases::use_cases::create_use_cases;
+
+    fn app() -> App {
+        App::new(create_use_cases(), create_repos(None))
+    }
+
+    #[rocket::async_test]
+    async fn test_import_export_and_delete() {
+        let app = app();
+        let mut tx = create_tx_for_test().await.unwrap();
+        let json = r#"[
+            {"name": "cli_import_a", "sku": "CLI-IMPORT-A", "price": 100, "currency": "JPY", "stock": 1},
+            {"id": 99, "name": "cli_import_b", "sku": "CLI-IMPORT-B", "price": 250, "currency": "USD", "stock": 0}
+        ]"#;
+        let products = read_products(json.as_bytes()).unwrap();
+        assert_eq!(import(&app, &mut tx, &products).await.unwrap(), 2);
+
+        let mut out = Vec::new();
+        let command = ProductCommand::Export { output: None };
+        run(&app, &mut tx, command, &mut out).await.unwrap();
+        let exported: Vec<Product> = serde_json::from_slice(&out).unwrap();
+        let imported: Vec<&Product> = exported
+            .iter()
+            .filter(|product| product.name.starts_with("cli_import_"))
+            .collect();
+        assert_eq!(imported.len(), 2);
+        assert_eq!(imported[1].sku, "CLI-IMPORT-B");
+        assert_eq!(imported[1].price, 250);
+        assert_eq!(imported[1].currency, "USD");
+        let ids: Vec<i32> = imported.iter().map(|product| product.id).collect();
+
+        let mut out = Vec::new();
+        let command = ProductCommand::Delete { ids: ids.clone() };
+        run(&app, &mut tx, command, &mut out).await.unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "Deleted 2 products\n");
+        for id in ids {
+            let product = app.repos.product.find_by_id(&mut tx, id).await.unwrap();
+            assert!(product.is_none());
+        }
+        tx.rollback().await.unwrap();
+    }
+
+    #[rocket::async_test]
+    async fn test_delete_is_rolled_back_when_a_product_is_missing() {
+        let app = app();
+        let mut tx = create_tx_for_test().await.unwrap();
+        let json = r#"[{"name": "cli_delete_test", "sku": "CLI-DELETE", "price": 0, "currency": "JPY", "stock": 0}]"#;
+        let products = read_products(json.as_bytes()).unwrap();
+        import(&app, &mut tx, &products).await.unwrap();
+        let id = sqlx::query_scalar!("SELECT id FROM products WHERE name = 'cli_delete_test'")
+            .fetch_one(&mut *tx)
+            .await
+            .unwrap();
+
+        let command = ProductCommand::Delete { ids: vec![id, -1] };
+        let result = run(&app, &mut tx, command, &mut Vec::new()).await;
+        assert!(
+            matches!(result, Err(CliError::Invalid(message)) if message == "product -1 not found")
+        );
+        let product = app.repos.product.find_by_id(&mut tx, id).await.unwrap();
+        assert!(product.is_some());
+        tx.rollback().await.unwrap();
+    }
+
+    #[test]
+    fn test_read_products_validates() {
+        let json = r#"[
+            {"name": "ok", "sku": "OK-1", "price": 100, "currency": "JPY", "stock": 1},
+            {"name": "ok", "sku": "OK-2", "price": 100, "currency": "jpy", "stock": 1}
+        ]"#;
+        let result = read_products(json.as_bytes());
+        assert!(
+            matches!(result, Err(CliError::Invalid(message)) if message.starts_with("products[1]"))
+        );
+        assert!(matches!(
+            read_products(r#"[{"name": "ok"}]"#.as_bytes()),
+            Err(CliError::Json(_))
+        ));
+    }
+}
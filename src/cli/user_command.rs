@@ -0,0 +1,283 @@
+use crate::app::App;
+use crate::cli::cli::CliError;
+use crate::db::DbCon;
+use crate::dto::auth_dto::RegisterForm;
+use crate::dto::user_dto::UserName;
+use crate::use_cases::unit_of_work::UnitOfWork;
+use clap::Subcommand;
+use std::io::{BufRead, Write};
+use validator::{Validate, ValidationErrors};
+
+#[derive(Subcommand, Debug, PartialEq, Eq)]
+pub enum UserCommand {
+    /// List all users (id, name, age)
+    List,
+    /// Create a user. With --email a login is created and its password is read from stdin
+    Create {
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        age: i32,
+        #[arg(long)]
+        email: Option<String>,
+        /// Role to grant, e.g. admin
+        #[arg(long)]
+        role: Option<String>,
+    },
+    /// Delete users by id
+    Delete {
+        #[arg(required = true)]
+        ids: Vec<i32>,
+    },
+}
+
+pub async fn run(
+    app: &App,
+    con: &mut DbCon,
+    command: UserCommand,
+    input: &mut impl BufRead,
+    out: &mut impl Write,
+) -> Result<(), CliError> {
+    match command {
+        UserCommand::List => {
+            for user in app.use_cases.user.find_all(&app.repos, con).await? {
+                let age = user.age.map(|age| age.to_string()).unwrap_or_default();
+                writeln!(out, "{}\t{}\t{}", user.id, user.name, age)?;
+            }
+        }
+        UserCommand::Create {
+            name,
+            age,
+            email,
+            role,
+        } => {
+            // POST /users/add や POST /auth/register と同じ規則で検証する
+            let register_form = match email {
+                Some(email) => {
+                    let form = RegisterForm {
+                        name: name.clone(),
+                        age,
+                        email,
+                        password: read_password(input)?,
+                    };
+                    form.validate().map_err(invalid)?;
+                    Some(form)
+                }
+                None => {
+                    UserName {
+                        name: name.clone(),
+                        age,
+                    }
+                    .validate()
+                    .map_err(invalid)?;
+                    None
+                }
+            };
+            // ロールの付与まで1つのトランザクションで行う
+            let mut uow = UnitOfWork::begin(con).await?;
+            let user = match register_form {
+                Some(form) => {
+                    app.use_cases
+                        .auth
+                        .register(
+                            &app.repos,
+                            uow.con(),
+                            &form.name,
+                            form.age,
+                            &form.email,
+                            &form.password,
+                        )
+                        .await?
+                }
+                None => {
+                    app.use_cases
+                        .user
+                        .create(&app.repos, uow.con(), &name, age)
+                        .await?
+                }
+            };
+            if let Some(role) = role {
+                app.use_cases
+                    .auth
+                    .assign_role(&app.repos, uow.con(), user.id, &role)
+                    .await?;
+            }
+            uow.commit().await?;
+            writeln!(out, "Created user {}", user.id)?;
+        }
+        UserCommand::Delete { ids } => {
+            // 途中で失敗した場合はすべて取り消す
+            let mut uow = UnitOfWork::begin(con).await?;
+            for id in &ids {
+                app.use_cases
+                    .user
+                    .delete(&app.repos, uow.con(), *id)
+                    .await?;
+            }
+            uow.commit().await?;
+            writeln!(out, "Deleted {} users", ids.len())?;
+        }
+    }
+    Ok(())
+}
+
+fn invalid(e: ValidationErrors) -> CliError {
+    CliError::Invalid(e.to_string())
+}
+
+// コマンドライン引数はpsなどで見えてしまうので、パスワードは標準入力の1行目から読む
+fn read_password(input: &mut impl BufRead) -> Result<String, CliError> {
+    let mut password = String::new();
+    input.read_line(&mut password)?;
+    let password = password.trim_end_matches(['\r', '\n']);
+    if password.is_empty() {
+        return Err(CliError::Invalid(
+            "password must be given on stdin".to_string(),
+        ));
+    }
+    Ok(password.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repositories::repositories::create_repos;
+    use crate::test::db::create_tx_for_test;
+    use crate::use_cases::use_cases::create_use_cases;
+    use std::io::Cursor;
+
+    fn app() -> App {
+        App::new(create_use_cases(), create_repos(None))
+    }
+
+    async fn run_command(
+        app: &App,
+        con: &mut DbCon,
+        command: UserCommand,
+        input: &str,
+    ) -> Result<String, CliError> {
+        let mut out = Vec::new();
+        run(app, con, command, &mut Cursor::new(input), &mut out).await?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    fn created_id(output: &str) -> i32 {
+        output
+            .trim()
+            .strip_prefix("Created user ")
+            .unwrap()
+            .parse()
+            .unwrap()
+    }
+
+    #[rocket::async_test]
+    async fn test_create_list_and_delete() {
+        let app = app();
+        let mut tx = create_tx_for_test().await.unwrap();
+        let command = UserCommand::Create {
+            name: "cli_user_test".to_string(),
+            age: 20,
+            email: Some("cli_admin@example.com".to_string()),
+            role: Some("admin".to_string()),
+        };
+        let output = run_command(&app, &mut tx, command, "secret password\n")
+            .await
+            .unwrap();
+        let id = created_id(&output);
+
+        let (user, _) = app
+            .use_cases
+            .auth
+            .login(
+                &app.repos,
+                &mut tx,
+                "cli_admin@example.com",
+                "secret password",
+            )
+            .await
+            .unwrap();
+        assert_eq!(user.id, id);
+        let roles = app.repos.role.find_roles(&mut tx, id).await.unwrap();
+        assert_eq!(roles, vec!["admin", "viewer"]);
+
+        let output = run_command(&app, &mut tx, UserCommand::List, "")
+            .await
+            .unwrap();
+        assert!(output.contains(&format!("{}\tcli_user_test\t20\n", id)));
+
+        let command = UserCommand::Delete { ids: vec![id] };
+        let output = run_command(&app, &mut tx, command, "").await.unwrap();
+        assert_eq!(output, "Deleted 1 users\n");
+        let user = app.repos.user.find_by_id(&mut tx, id).await.unwrap();
+        assert!(user.is_none());
+        tx.rollback().await.unwrap();
+    }
+
+    #[rocket::async_test]
+    async fn test_create_is_rolled_back_on_unknown_role() {
+        let app = app();
+        let mut tx = create_tx_for_test().await.unwrap();
+        let command = UserCommand::Create {
+            name: "cli_role_test".to_string(),
+            age: 20,
+            email: None,
+            role: Some("owner".to_string()),
+        };
+        let result = run_command(&app, &mut tx, command, "").await;
+        assert!(matches!(result, Err(CliError::App(e)) if e.status_code() == 400));
+        let count = sqlx::query_scalar!("SELECT COUNT(*) FROM users WHERE name = 'cli_role_test'")
+            .fetch_one(&mut *tx)
+            .await
+            .unwrap();
+        assert_eq!(count, Some(0));
+        tx.rollback().await.unwrap();
+    }
+
+    #[rocket::async_test]
+    async fn test_create_validates_input() {
+        let app = app();
+        let mut tx = create_tx_for_test().await.unwrap();
+        let command = UserCommand::Create {
+            name: " ".to_string(),
+            age: 20,
+            email: None,
+            role: None,
+        };
+        let result = run_command(&app, &mut tx, command, "").await;
+        assert!(matches!(result, Err(CliError::Invalid(_))));
+
+        let command = UserCommand::Create {
+            name: "cli_password_test".to_string(),
+            age: 20,
+            email: Some("cli_password@example.com".to_string()),
+            role: None,
+        };
+        let result = run_command(&app, &mut tx, command, "\n").await;
+        assert!(matches!(result, Err(CliError::Invalid(_))));
+
+        // POST /auth/register と同じく、短いパスワードや不正なメールアドレスは登録しない
+        let command = UserCommand::Create {
+            name: "cli_password_test".to_string(),
+            age: 20,
+            email: Some("cli_password@example.com".to_string()),
+            role: Some("admin".to_string()),
+        };
+        let result = run_command(&app, &mut tx, command, "x\n").await;
+        assert!(matches!(result, Err(CliError::Invalid(e)) if e.contains("password")));
+        let command = UserCommand::Create {
+            name: "cli_password_test".to_string(),
+            age: 20,
+            email: Some("foo".to_string()),
+            role: None,
+        };
+        let result = run_command(&app, &mut tx, command, "secret password\n").await;
+        assert!(matches!(result, Err(CliError::Invalid(e)) if e.contains("email")));
+        let count =
+            sqlx::query_scalar!("SELECT COUNT(*) FROM users WHERE name = 'cli_password_test'")
+                .fetch_one(&mut *tx)
+                .await
+                .unwrap();
+        assert_eq!(count, Some(0));
+        tx.rollback().await.unwrap();
+    }
+}
@@ -8,9 +8,112 @@ pub struct DatabaseConfig {
     pub max_connections: Option<u32>,
     pub connect_timeout: Option<u32>,
     pub idle_timeout: Option<u64>,
+    // 起動時に未適用のマイグレーションを適用する
+    pub migrate_on_start: Option<bool>,
+}
+
+// redis_urlが無い場合はプロセス内キャッシュを使う
+#[derive(Deserialize, Debug, Clone)]
+pub struct CacheConfig {
+    pub redis_url: Option<String>,
+    pub ttl: Option<u64>,
+}
+
+// 検証に使う鍵。HS256はsecret、RS256はPEM形式のpublic_keyを指定する
+#[derive(Deserialize, Debug, Clone)]
+pub struct JwtKeyConfig {
+    pub kid: String,
+    pub algorithm: String,
+    pub secret: Option<String>,
+    pub public_key: Option<String>,
+}
+
+// 鍵のローテーション中は新旧の鍵を両方keysに並べておく
+#[derive(Deserialize, Debug, Clone)]
+pub struct JwtConfig {
+    pub keys: Vec<JwtKeyConfig>,
+    pub issuer: Option<String>,
+    pub audience: Option<String>,
+    pub leeway: Option<u64>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Pretty,
+    Json,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    Minutely,
+    Hourly,
+    #[default]
+    Daily,
+    Never,
+}
+
+// 標準出力に加えてdirectoryにも書き出す
+#[derive(Deserialize, Debug, Clone)]
+pub struct LogFileConfig {
+    pub directory: String,
+    pub prefix: Option<String>,
+    #[serde(default)]
+    pub rotation: LogRotation,
+}
+
+// spanをOTLP/HTTP (protobuf) でcollectorへ送る。endpointは/v1/tracesを除いたURL (例: "http://localhost:4318")
+// filterはログとは別に指定し、省略時は"info"
+#[derive(Deserialize, Debug, Clone)]
+pub struct OtlpConfig {
+    pub endpoint: String,
+    pub service_name: Option<String>,
+    pub filter: Option<String>,
+    pub sample_ratio: Option<f64>,
+}
+
+// filterはEnvFilterの書式 (例: "info,rust_rocket_sqlx_sample::repositories=debug")
+// 環境変数RUST_LOGがあればそちらを優先する
+#[derive(Deserialize, Debug, Clone)]
+pub struct LoggingConfig {
+    #[serde(default)]
+    pub format: LogFormat,
+    pub filter: Option<String>,
+    pub file: Option<LogFileConfig>,
+    #[serde(default)]
+    pub span_timing: bool,
+    #[serde(default = "default_redact")]
+    pub redact: Vec<String>,
+    pub otlp: Option<OtlpConfig>,
+}
+
+fn default_redact() -> Vec<String> {
+    ["name", "email", "password"]
+        .iter()
+        .map(|field| field.to_string())
+        .collect()
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            format: LogFormat::default(),
+            filter: None,
+            file: None,
+            span_timing: false,
+            redact: default_redact(),
+            otlp: None,
+        }
+    }
 }
 
 #[derive(Deserialize, Debug)]
 pub struct Config {
     pub databases: Map<String, DatabaseConfig>,
+    pub cache: Option<CacheConfig>,
+    pub jwt: Option<JwtConfig>,
+    pub logging: Option<LoggingConfig>,
 }
@@ -0,0 +1,204 @@
+use crate::app::AppState;
+use crate::auth::authorized::{ApiKeysManage, Authorized};
+use crate::controllers::validated::Validated;
+use crate::db::ConnectionDb;
+use crate::dto::api_key_dto::ApiKeyForm;
+use crate::error::app_error::AppError;
+use crate::error::problem::Problem;
+use crate::models::api_key_model::{ApiKey, MintedApiKey};
+use rocket::serde::json::Json;
+use tracing::instrument;
+
+#[utoipa::path(
+    get,
+    path = "/api-keys",
+    tag = "api-keys",
+    responses(
+        (status = 200, body = [ApiKey]),
+        (status = 401, response = Problem),
+        (status = 403, response = Problem),
+        (status = 500, response = Problem),
+    ),
+    security(("session" = []), ("bearer" = []))
+)]
+#[get("/")]
+#[instrument(name = "api_key_controller/index", skip_all)]
+async fn index(
+    app: &AppState,
+    _auth: Authorized<ApiKeysManage>,
+    mut db: ConnectionDb,
+) -> Result<Json<Vec<ApiKey>>, AppError> {
+    let api_keys = app.use_cases.api_key.find_all(&app.repos, &mut db).await?;
+    Ok(Json(api_keys))
+}
+
+// 発行したキーはこのレスポンスでしか返さない
+#[utoipa::path(
+    post,
+    path = "/api-keys",
+    tag = "api-keys",
+    request_body = ApiKeyForm,
+    responses(
+        (status = 200, body = MintedApiKey),
+        (status = 400, response = Problem),
+        (status = 401, response = Problem),
+        (status = 403, response = Problem),
+        (status = 422, response = Problem),
+        (status = 500, response = Problem),
+    ),
+    security(("session" = []), ("bearer" = []))
+)]
+#[post("/", data = "<form>")]
+#[instrument(name = "api_key_controller/mint", skip_all)]
+async fn mint(
+    app: &AppState,
+    auth: Authorized<ApiKeysManage>,
+    mut db: ConnectionDb,
+    form: Validated<ApiKeyForm>,
+) -> Result<Json<MintedApiKey>, AppError> {
+    let form = form.into_inner();
+    let minted = app
+        .use_cases
+        .api_key
+        .mint(
+            &app.repos,
+            &mut db,
+            &form.name,
+            &form.scopes,
+            auth.user_id(),
+        )
+        .await?;
+    Ok(Json(minted))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api-keys/{id}",
+    tag = "api-keys",
+    params(("id" = i32, Path, description = "id")),
+    responses(
+        (status = 200, body = ApiKey),
+        (status = 401, response = Problem),
+        (status = 403, response = Problem),
+        (status = 404, response = Problem),
+        (status = 500, response = Problem),
+    ),
+    security(("session" = []), ("bearer" = []))
+)]
+#[delete("/<id>")]
+#[instrument(name = "api_key_controller/revoke", skip_all, fields(id = %id))]
+async fn revoke(
+    app: &AppState,
+    _auth: Authorized<ApiKeysManage>,
+    mut db: ConnectionDb,
+    id: i32,
+) -> Result<Json<ApiKey>, AppError> {
+    let api_key = app
+        .use_cases
+        .api_key
+        .revoke(&app.repos, &mut db, id)
+        .await?;
+    Ok(Json(api_key))
+}
+
+pub fn routes() -> Vec<rocket::Route> {
+    routes![index, mint, revoke]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Config;
+    use crate::db::Db;
+    use crate::error::catchers::catchers;
+    use crate::models::api_key_model::{ApiKey, MintedApiKey};
+    use crate::test::app::create_app_for_test;
+    use crate::test::auth::{authorized_auth_use_case_for_test, session_cookie_for_test};
+    use crate::use_cases::api_key_use_case::MockApiKeyUseCase;
+    use chrono::Utc;
+    use rocket::fairing::AdHoc;
+    use rocket::http::{ContentType, Status};
+    use rocket::local::asynchronous::Client;
+    use rocket::serde::json::Value;
+    use rocket_db_pools::Database;
+    use std::sync::Arc;
+
+    async fn client(mock_api_key_use_case: MockApiKeyUseCase) -> Client {
+        let mut app_state = create_app_for_test();
+        app_state.use_cases.api_key = Box::new(mock_api_key_use_case);
+        app_state.use_cases.auth = Box::new(authorized_auth_use_case_for_test());
+
+        let rocket = rocket::build()
+            .manage(Arc::new(app_state))
+            .attach(Db::init())
+            .attach(AdHoc::config::<Config>())
+            .register("/", catchers())
+            .mount("/", super::routes());
+        Client::tracked(rocket)
+            .await
+            .expect("valid rocket instance")
+    }
+
+    #[rocket::async_test]
+    async fn test_mint_returns_key_once() {
+        let mut mock_api_key_use_case = MockApiKeyUseCase::new();
+        mock_api_key_use_case
+            .expect_mint()
+            .withf(|_, _, name, scopes, created_by| {
+                name == "partner" && scopes == ["products:write"] && *created_by == Some(1)
+            })
+            .returning(|_, _, name, scopes, created_by| {
+                Ok(MintedApiKey {
+                    api_key: ApiKey {
+                        id: 1,
+                        name: name.to_string(),
+                        prefix: "rk_abcdefgh".to_string(),
+                        key_hash: "hash".to_string(),
+                        scopes: scopes.to_vec(),
+                        created_by,
+                        created_at: Utc::now(),
+                        revoked_at: None,
+                        last_used_at: None,
+                        use_count: 0,
+                    },
+                    key: "rk_abcdefghijk".to_string(),
+                })
+            });
+        let client = client(mock_api_key_use_case).await;
+
+        let response = client
+            .post("/")
+            .private_cookie(session_cookie_for_test(1))
+            .header(ContentType::JSON)
+            .body(r#"{"name":"partner","scopes":["products:write"]}"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        let body: Value = response.into_json().await.unwrap();
+        assert_eq!(body["key"], "rk_abcdefghijk");
+        assert_eq!(body["prefix"], "rk_abcdefgh");
+        assert!(body.get("key_hash").is_none());
+    }
+
+    #[rocket::async_test]
+    async fn test_mint_unknown_scope() {
+        let mut mock_api_key_use_case = MockApiKeyUseCase::new();
+        mock_api_key_use_case.expect_mint().never();
+        let client = client(mock_api_key_use_case).await;
+
+        let response = client
+            .post("/")
+            .private_cookie(session_cookie_for_test(1))
+            .header(ContentType::JSON)
+            .body(r#"{"name":"partner","scopes":["api_keys:manage"]}"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+        let body: Value = response.into_json().await.unwrap();
+        assert!(body["errors"]["scopes"][0]
+            .as_str()
+            .unwrap()
+            .starts_with("unknown scope api_keys:manage"));
+    }
+}
@@ -0,0 +1,399 @@
+use crate::app::AppState;
+use crate::auth::authenticated_user::AuthenticatedUser;
+use crate::auth::jwt::Claims;
+use crate::controllers::validated::Validated;
+use crate::db::ConnectionDb;
+use crate::dto::auth_dto::{ChangePasswordForm, LoginForm, RegisterForm};
+use crate::error::app_error::AppError;
+use crate::error::problem::Problem;
+use crate::models::user_model::User;
+use rocket::http::CookieJar;
+use rocket::serde::json::Json;
+use tracing::instrument;
+
+#[utoipa::path(
+    post,
+    path = "/auth/register",
+    tag = "auth",
+    request_body = RegisterForm,
+    responses(
+        (status = 200, body = User),
+        (status = 400, response = Problem),
+        (status = 409, response = Problem),
+        (status = 422, response = Problem),
+        (status = 500, response = Problem),
+    )
+)]
+#[post("/register", data = "<form>")]
+#[instrument(name = "auth_controller/register", skip_all)]
+async fn register(
+    app: &AppState,
+    mut db: ConnectionDb,
+    form: Validated<RegisterForm>,
+) -> Result<Json<User>, AppError> {
+    let form = form.into_inner();
+    let user = app
+        .use_cases
+        .auth
+        .register(
+            &app.repos,
+            &mut db,
+            &form.name,
+            form.age,
+            &form.email,
+            &form.password,
+        )
+        .await?;
+    Ok(Json(user))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/login",
+    tag = "auth",
+    request_body = LoginForm,
+    responses(
+        (status = 200, description = "Sets the session cookie", body = User),
+        (status = 400, response = Problem),
+        (status = 401, response = Problem),
+        (status = 422, response = Problem),
+        (status = 500, response = Problem),
+    )
+)]
+#[post("/login", data = "<form>")]
+#[instrument(name = "auth_controller/login", skip_all)]
+async fn login(
+    app: &AppState,
+    mut db: ConnectionDb,
+    cookies: &CookieJar<'_>,
+    form: Validated<LoginForm>,
+) -> Result<Json<User>, AppError> {
+    let form = form.into_inner();
+    let (user, session_epoch) = app
+        .use_cases
+        .auth
+        .login(&app.repos, &mut db, &form.email, &form.password)
+        .await?;
+    AuthenticatedUser::login(cookies, user.id, session_epoch);
+    Ok(Json(user))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/logout",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Removes the session cookie and revokes every session of the user"),
+        (status = 500, response = Problem),
+    )
+)]
+#[post("/logout")]
+#[instrument(name = "auth_controller/logout", skip_all)]
+async fn logout(
+    app: &AppState,
+    auth: Result<AuthenticatedUser, AppError>,
+    mut db: ConnectionDb,
+    cookies: &CookieJar<'_>,
+) -> Result<(), AppError> {
+    match auth {
+        // コピーされたcookieも使えなくなるよう、セッションの世代を進める
+        Ok(auth) => {
+            app.use_cases
+                .auth
+                .logout(&app.repos, &mut db, auth.user_id)
+                .await?
+        }
+        // ログインしていなければcookieを消すだけ
+        Err(AppError::Unauthorized) => {}
+        Err(e) => return Err(e),
+    }
+    AuthenticatedUser::logout(cookies);
+    Ok(())
+}
+
+#[utoipa::path(
+    put,
+    path = "/auth/password",
+    tag = "auth",
+    request_body = ChangePasswordForm,
+    responses(
+        (status = 200, description = "Reissues the session cookie and revokes the other sessions"),
+        (status = 400, response = Problem),
+        (status = 401, response = Problem),
+        (status = 422, response = Problem),
+        (status = 500, response = Problem),
+    ),
+    security(("session" = []))
+)]
+#[put("/password", data = "<form>")]
+#[instrument(name = "auth_controller/change_password", skip_all, fields(user_id = %auth.user_id))]
+async fn change_password(
+    app: &AppState,
+    auth: AuthenticatedUser,
+    mut db: ConnectionDb,
+    cookies: &CookieJar<'_>,
+    form: Validated<ChangePasswordForm>,
+) -> Result<(), AppError> {
+    let form = form.into_inner();
+    let session_epoch = app
+        .use_cases
+        .auth
+        .change_password(
+            &app.repos,
+            &mut db,
+            auth.user_id,
+            &form.current_password,
+            &form.new_password,
+        )
+        .await?;
+    // 変更したセッションだけはログインしたままにする
+    AuthenticatedUser::login(cookies, auth.user_id, session_epoch);
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/me",
+    tag = "auth",
+    responses(
+        (status = 200, body = User),
+        (status = 401, response = Problem),
+        (status = 500, response = Problem),
+    ),
+    security(("session" = []))
+)]
+#[get("/me")]
+#[instrument(name = "auth_controller/me", skip_all, fields(user_id = %auth.user_id))]
+async fn me(
+    app: &AppState,
+    auth: AuthenticatedUser,
+    mut db: ConnectionDb,
+) -> Result<Json<User>, AppError> {
+    let user = app
+        .use_cases
+        .auth
+        .current_user(&app.repos, &mut db, auth.user_id)
+        .await?;
+    Ok(Json(user))
+}
+
+// Bearerトークンで呼び出すサービス向け。検証済みのクレームを返す
+#[utoipa::path(
+    get,
+    path = "/auth/token",
+    tag = "auth",
+    responses(
+        (status = 200, body = Claims),
+        (status = 401, response = Problem),
+    ),
+    security(("bearer" = []))
+)]
+#[get("/token")]
+#[instrument(name = "auth_controller/token", skip_all, fields(sub = %claims.sub))]
+async fn token(claims: Claims) -> Json<Claims> {
+    Json(claims)
+}
+
+pub fn routes() -> Vec<rocket::Route> {
+    routes![register, login, logout, change_password, me, token]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::auth::authenticated_user::{AuthenticatedUser, SESSION_COOKIE};
+    use crate::config::Config;
+    use crate::db::Db;
+    use crate::error::app_error::AppError;
+    use crate::error::catchers::catchers;
+    use crate::test::app::create_app_for_test;
+    use crate::test::fixture::user::user_fixture;
+    use crate::use_cases::auth_use_case::MockAuthUseCase;
+    use chrono::{Duration, Utc};
+    use rocket::fairing::AdHoc;
+    use rocket::http::{ContentType, Cookie, Status};
+    use rocket::local::asynchronous::Client;
+    use rocket_db_pools::Database;
+    use std::sync::atomic::{AtomicI32, Ordering};
+    use std::sync::Arc;
+
+    async fn client(mock_auth_use_case: MockAuthUseCase) -> Client {
+        let mut app_state = create_app_for_test();
+        app_state.use_cases.auth = Box::new(mock_auth_use_case);
+
+        let rocket = rocket::build()
+            .manage(Arc::new(app_state))
+            .attach(Db::init())
+            .attach(AdHoc::config::<Config>())
+            .register("/", catchers())
+            .mount("/", super::routes());
+        Client::tracked(rocket)
+            .await
+            .expect("valid rocket instance")
+    }
+
+    // session_epochはログアウトとパスワードの変更で進む
+    fn session_auth_use_case(session_epoch: Arc<AtomicI32>) -> MockAuthUseCase {
+        let mut mock_auth_use_case = MockAuthUseCase::new();
+        let epoch = session_epoch.clone();
+        mock_auth_use_case
+            .expect_login()
+            .returning(move |_, _, _, _| Ok((user_fixture(7), epoch.load(Ordering::SeqCst))));
+        let epoch = session_epoch.clone();
+        mock_auth_use_case
+            .expect_session_epoch()
+            .returning(move |_, _, _| Ok(Some(epoch.load(Ordering::SeqCst))));
+        let epoch = session_epoch.clone();
+        mock_auth_use_case
+            .expect_logout()
+            .returning(move |_, _, _| {
+                epoch.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            });
+        mock_auth_use_case
+            .expect_change_password()
+            .returning(move |_, _, _, current, _| match current {
+                "password" => Ok(session_epoch.fetch_add(1, Ordering::SeqCst) + 1),
+                _ => Err(AppError::new(400, "current password is incorrect")),
+            });
+        mock_auth_use_case
+            .expect_current_user()
+            .returning(|_, _, user_id| Ok(user_fixture(user_id as usize)));
+        mock_auth_use_case
+    }
+
+    async fn login(client: &Client) -> Cookie<'static> {
+        let response = client
+            .post("/login")
+            .header(ContentType::JSON)
+            .body(r#"{"email":"taro@example.com","password":"password"}"#)
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        response.cookies().get_private(SESSION_COOKIE).unwrap()
+    }
+
+    #[rocket::async_test]
+    async fn test_login_and_logout() {
+        let session_epoch = Arc::new(AtomicI32::new(0));
+        let client = client(session_auth_use_case(session_epoch.clone())).await;
+
+        let response = client.get("/me").dispatch().await;
+        assert_eq!(response.status(), Status::Unauthorized);
+
+        login(&client).await;
+        let response = client.get("/me").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        assert!(response.into_string().await.unwrap().contains(r#""id":7"#));
+
+        let response = client.post("/logout").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(session_epoch.load(Ordering::SeqCst), 1);
+        let response = client.get("/me").dispatch().await;
+        assert_eq!(response.status(), Status::Unauthorized);
+
+        // ログインしていなくてもログアウトはできる
+        let response = client.post("/logout").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(session_epoch.load(Ordering::SeqCst), 1);
+    }
+
+    #[rocket::async_test]
+    async fn test_logout_revokes_copied_cookie() {
+        let client = client(session_auth_use_case(Arc::new(AtomicI32::new(0)))).await;
+
+        let copied = login(&client).await;
+        client.post("/logout").dispatch().await;
+
+        let response = client.get("/me").private_cookie(copied).dispatch().await;
+        assert_eq!(response.status(), Status::Unauthorized);
+    }
+
+    #[rocket::async_test]
+    async fn test_change_password_revokes_other_sessions() {
+        let client = client(session_auth_use_case(Arc::new(AtomicI32::new(0)))).await;
+
+        let other = login(&client).await;
+        let response = client
+            .put("/password")
+            .header(ContentType::JSON)
+            .body(r#"{"current_password":"wrong","new_password":"new password"}"#)
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::BadRequest);
+
+        let response = client
+            .put("/password")
+            .header(ContentType::JSON)
+            .body(r#"{"current_password":"password","new_password":"new password"}"#)
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+
+        // 変更したセッションには新しい世代のcookieが発行される
+        let response = client.get("/me").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let response = client.get("/me").private_cookie(other).dispatch().await;
+        assert_eq!(response.status(), Status::Unauthorized);
+    }
+
+    #[rocket::async_test]
+    async fn test_change_password_requires_login() {
+        let client = client(session_auth_use_case(Arc::new(AtomicI32::new(0)))).await;
+        let response = client
+            .put("/password")
+            .header(ContentType::JSON)
+            .body(r#"{"current_password":"password","new_password":"new password"}"#)
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Unauthorized);
+    }
+
+    #[rocket::async_test]
+    async fn test_login_failure() {
+        let mut mock_auth_use_case = MockAuthUseCase::new();
+        mock_auth_use_case
+            .expect_login()
+            .returning(|_, _, _, _| Err(AppError::Unauthorized));
+        let client = client(mock_auth_use_case).await;
+
+        let response = client
+            .post("/login")
+            .header(ContentType::JSON)
+            .body(r#"{"email":"taro@example.com","password":"wrong"}"#)
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Unauthorized);
+        assert!(response.cookies().get_private("session").is_none());
+    }
+
+    #[rocket::async_test]
+    async fn test_expired_session() {
+        let client = client(session_auth_use_case(Arc::new(AtomicI32::new(0)))).await;
+
+        let issued_at = Utc::now() - Duration::hours(1);
+        let response = client
+            .get("/me")
+            .private_cookie(AuthenticatedUser::session_cookie(7, 0, issued_at))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+
+        let issued_at = Utc::now() - Duration::days(1);
+        let response = client
+            .get("/me")
+            .private_cookie(AuthenticatedUser::session_cookie(7, 0, issued_at))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Unauthorized);
+
+        // 期限や世代の無い以前の形式のcookieは受け付けない
+        for value in ["7".to_string(), format!("7:{}", Utc::now().timestamp())] {
+            let response = client
+                .get("/me")
+                .private_cookie(Cookie::new(SESSION_COOKIE, value))
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::Unauthorized);
+        }
+    }
+}
@@ -0,0 +1,357 @@
+use crate::app::AppState;
+use crate::auth::cart_session::CartSession;
+use crate::controllers::validated::Validated;
+use crate::db::ConnectionDb;
+use crate::dto::cart_dto::{CartItemForm, CartQuantityForm};
+use crate::error::app_error::AppError;
+use crate::error::problem::Problem;
+use crate::models::cart_model::CartView;
+use crate::models::order_model::OrderDetail;
+use rocket::serde::json::Json;
+use tracing::instrument;
+
+#[utoipa::path(
+    get,
+    path = "/carts",
+    tag = "carts",
+    responses(
+        (status = 200, body = CartView),
+        (status = 500, response = Problem),
+    )
+)]
+#[get("/")]
+#[instrument(name = "cart_controller/show", skip_all)]
+async fn show(
+    app: &AppState,
+    session: CartSession,
+    mut db: ConnectionDb,
+) -> Result<Json<CartView>, AppError> {
+    let cart = app
+        .use_cases
+        .cart
+        .view(&app.repos, &mut db, &session.owner)
+        .await?;
+    Ok(Json(cart))
+}
+
+#[utoipa::path(
+    post,
+    path = "/carts/items",
+    tag = "carts",
+    request_body = CartItemForm,
+    responses(
+        (status = 200, body = CartView),
+        (status = 400, response = Problem),
+        (status = 422, response = Problem),
+        (status = 500, response = Problem),
+    )
+)]
+#[post("/items", data = "<form>")]
+#[instrument(name = "cart_controller/add_item", skip_all)]
+async fn add_item(
+    app: &AppState,
+    session: CartSession,
+    mut db: ConnectionDb,
+    form: Validated<CartItemForm>,
+) -> Result<Json<CartView>, AppError> {
+    let form = form.into_inner();
+    let cart = app
+        .use_cases
+        .cart
+        .add_item(
+            &app.repos,
+            &mut db,
+            &session.owner,
+            form.product_id,
+            form.quantity,
+        )
+        .await?;
+    Ok(Json(cart))
+}
+
+#[utoipa::path(
+    put,
+    path = "/carts/items/{product_id}",
+    tag = "carts",
+    params(("product_id" = i32, Path, description = "product id")),
+    request_body = CartQuantityForm,
+    responses(
+        (status = 200, body = CartView),
+        (status = 404, response = Problem),
+        (status = 422, response = Problem),
+        (status = 500, response = Problem),
+    )
+)]
+#[put("/items/<product_id>", data = "<form>")]
+#[instrument(name = "cart_controller/set_quantity", skip_all, fields(product_id = %product_id))]
+async fn set_quantity(
+    app: &AppState,
+    session: CartSession,
+    mut db: ConnectionDb,
+    product_id: i32,
+    form: Validated<CartQuantityForm>,
+) -> Result<Json<CartView>, AppError> {
+    let form = form.into_inner();
+    let cart = app
+        .use_cases
+        .cart
+        .set_quantity(
+            &app.repos,
+            &mut db,
+            &session.owner,
+            product_id,
+            form.quantity,
+        )
+        .await?;
+    Ok(Json(cart))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/carts/items/{product_id}",
+    tag = "carts",
+    params(("product_id" = i32, Path, description = "product id")),
+    responses(
+        (status = 200, body = CartView),
+        (status = 404, response = Problem),
+        (status = 500, response = Problem),
+    )
+)]
+#[delete("/items/<product_id>")]
+#[instrument(name = "cart_controller/remove_item", skip_all, fields(product_id = %product_id))]
+async fn remove_item(
+    app: &AppState,
+    session: CartSession,
+    mut db: ConnectionDb,
+    product_id: i32,
+) -> Result<Json<CartView>, AppError> {
+    let cart = app
+        .use_cases
+        .cart
+        .remove_item(&app.repos, &mut db, &session.owner, product_id)
+        .await?;
+    Ok(Json(cart))
+}
+
+// 匿名のカートのままでは注文できないので、ログインしていなければ401を返す
+#[utoipa::path(
+    post,
+    path = "/carts/checkout",
+    tag = "carts",
+    responses(
+        (status = 200, body = OrderDetail),
+        (status = 400, response = Problem),
+        (status = 401, response = Problem),
+        (status = 409, response = Problem),
+        (status = 500, response = Problem),
+    ),
+    security(("session" = []))
+)]
+#[post("/checkout")]
+#[instrument(name = "cart_controller/checkout", skip_all)]
+async fn checkout(
+    app: &AppState,
+    session: CartSession,
+    mut db: ConnectionDb,
+) -> Result<Json<OrderDetail>, AppError> {
+    let order = app
+        .use_cases
+        .cart
+        .checkout(&app.repos, &mut db, &session.owner)
+        .await?;
+    Ok(Json(order))
+}
+
+pub fn routes() -> Vec<rocket::Route> {
+    routes![show, add_item, set_quantity, remove_item, checkout]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::auth::cart_session::CART_COOKIE;
+    use crate::config::Config;
+    use crate::db::Db;
+    use crate::error::app_error::AppError;
+    use crate::error::catchers::catchers;
+    use crate::models::cart_model::{CartOwner, CartView};
+    use crate::test::app::create_app_for_test;
+    use crate::test::auth::session_cookie_for_test;
+    use crate::test::fixture::cart::cart_view_fixture;
+    use crate::test::fixture::order::order_detail_fixture;
+    use crate::use_cases::cart_use_case::MockCartUseCase;
+    use rocket::fairing::AdHoc;
+    use rocket::http::{ContentType, Cookie, Status};
+    use rocket::local::asynchronous::Client;
+    use rocket::serde::json::Value;
+    use rocket_db_pools::Database;
+    use std::sync::Arc;
+    use uuid::Uuid;
+
+    async fn client(mock_cart_use_case: MockCartUseCase) -> Client {
+        let mut app_state = create_app_for_test();
+        app_state.use_cases.cart = Box::new(mock_cart_use_case);
+
+        let rocket = rocket::build()
+            .manage(Arc::new(app_state))
+            .attach(Db::init())
+            .attach(AdHoc::config::<Config>())
+            .register("/", catchers())
+            .mount("/", super::routes());
+        Client::tracked(rocket)
+            .await
+            .expect("valid rocket instance")
+    }
+
+    // 未ログインで最初のリクエストならトークンのcookieが発行され、次からはそれが使われる
+    #[rocket::async_test]
+    async fn test_anonymous_cart_issues_cookie() {
+        let mut mock_cart_use_case = MockCartUseCase::new();
+        mock_cart_use_case
+            .expect_add_item()
+            .withf(|_, _, owner, product_id, quantity| {
+                matches!(owner, CartOwner::Anonymous(_)) && *product_id == 3 && *quantity == 2
+            })
+            .returning(|_, _, _, _, _| Ok(cart_view_fixture()));
+        mock_cart_use_case
+            .expect_view()
+            .returning(|_, _, _| Ok(cart_view_fixture()));
+        let client = client(mock_cart_use_case).await;
+        let response = client
+            .post("/items")
+            .header(ContentType::JSON)
+            .body(r#"{"product_id":3,"quantity":2}"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        let token = response.cookies().get_private(CART_COOKIE).unwrap();
+        let token = Uuid::parse_str(token.value()).unwrap();
+        let body: Value = response.into_json().await.unwrap();
+        assert_eq!(body["total"], 240);
+
+        // 2回目はcookieを送り返すだけで、新しいトークンは発行しない
+        let response = client.get("/").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        assert!(response.cookies().get(CART_COOKIE).is_none());
+        assert_eq!(
+            client.cookies().get_private(CART_COOKIE).unwrap().value(),
+            token.to_string()
+        );
+    }
+
+    // ログインしたら匿名のカートをユーザーのカートに移し、トークンのcookieを消す
+    #[rocket::async_test]
+    async fn test_login_claims_anonymous_cart() {
+        let token = Uuid::new_v4();
+        let mut mock_cart_use_case = MockCartUseCase::new();
+        mock_cart_use_case
+            .expect_claim()
+            .withf(move |_, _, user_id, claimed| *user_id == 1 && *claimed == token)
+            .times(1)
+            .returning(|_, _, _, _| Ok(()));
+        mock_cart_use_case
+            .expect_view()
+            .withf(|_, _, owner| *owner == CartOwner::User(1))
+            .returning(|_, _, _| Ok(cart_view_fixture()));
+        let client = client(mock_cart_use_case).await;
+        let response = client
+            .get("/")
+            .private_cookie(session_cookie_for_test(1))
+            .private_cookie(Cookie::new(CART_COOKIE, token.to_string()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        let removed = response.cookies().get(CART_COOKIE).unwrap();
+        assert_eq!(removed.value(), "");
+    }
+
+    #[rocket::async_test]
+    async fn test_add_item_invalid_quantity() {
+        let mut mock_cart_use_case = MockCartUseCase::new();
+        mock_cart_use_case.expect_add_item().never();
+        let client = client(mock_cart_use_case).await;
+        let response = client
+            .post("/items")
+            .header(ContentType::JSON)
+            .body(r#"{"product_id":3,"quantity":0}"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+        let body: Value = response.into_json().await.unwrap();
+        assert_eq!(body["errors"]["quantity"][0], "must be between 1 and 1000");
+    }
+
+    #[rocket::async_test]
+    async fn test_remove_missing_item() {
+        let mut mock_cart_use_case = MockCartUseCase::new();
+        mock_cart_use_case
+            .expect_remove_item()
+            .returning(|_, _, _, _| Err(AppError::NotFound));
+        let client = client(mock_cart_use_case).await;
+        let response = client.delete("/items/3").dispatch().await;
+
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[rocket::async_test]
+    async fn test_checkout() {
+        let mut mock_cart_use_case = MockCartUseCase::new();
+        mock_cart_use_case
+            .expect_checkout()
+            .withf(|_, _, owner| *owner == CartOwner::User(1))
+            .returning(|_, _, _| Ok(order_detail_fixture(10, 1)));
+        let client = client(mock_cart_use_case).await;
+        let response = client
+            .post("/checkout")
+            .private_cookie(session_cookie_for_test(1))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        let body: Value = response.into_json().await.unwrap();
+        assert_eq!(body["id"], 10);
+        assert_eq!(body["items"][0]["quantity"], 2);
+    }
+
+    #[rocket::async_test]
+    async fn test_checkout_out_of_stock() {
+        let mut mock_cart_use_case = MockCartUseCase::new();
+        mock_cart_use_case.expect_checkout().returning(|_, _, _| {
+            Err(AppError::OutOfStock {
+                product_id: 3,
+                available: 1,
+            })
+        });
+        let client = client(mock_cart_use_case).await;
+        let response = client
+            .post("/checkout")
+            .private_cookie(session_cookie_for_test(1))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Conflict);
+        let body: Value = response.into_json().await.unwrap();
+        assert_eq!(body["product_id"], 3);
+    }
+
+    #[rocket::async_test]
+    async fn test_empty_cart() {
+        let mut mock_cart_use_case = MockCartUseCase::new();
+        mock_cart_use_case.expect_view().returning(|_, _, _| {
+            Ok(CartView {
+                items: vec![],
+                currency: None,
+                total: 0,
+            })
+        });
+        let client = client(mock_cart_use_case).await;
+        let response = client.get("/").dispatch().await;
+
+        assert_eq!(response.status(), Status::Ok);
+        let body: Value = response.into_json().await.unwrap();
+        assert_eq!(body["items"], Value::Array(vec![]));
+        assert_eq!(body["currency"], Value::Null);
+    }
+}
@@ -0,0 +1,158 @@
+use crate::app::AppState;
+use crate::auth::authorized::{Authorized, ProductsWrite};
+use crate::controllers::validated::Validated;
+use crate::db::ConnectionDb;
+use crate::dto::category_dto::CategoryInput;
+use crate::error::app_error::AppError;
+use crate::error::problem::Problem;
+use crate::models::category_model::Category;
+use rocket::serde::json::Json;
+use tracing::instrument;
+
+// 木はparent_idでたどる。並びはidの昇順
+#[utoipa::path(
+    get,
+    path = "/categories",
+    tag = "categories",
+    responses(
+        (status = 200, body = [Category]),
+        (status = 500, response = Problem),
+    )
+)]
+#[get("/")]
+#[instrument(name = "category_controller/index", skip_all)]
+async fn index(app: &AppState, mut db: ConnectionDb) -> Result<Json<Vec<Category>>, AppError> {
+    let categories = app.use_cases.category.find_all(&app.repos, &mut db).await?;
+    Ok(Json(categories))
+}
+
+#[utoipa::path(
+    post,
+    path = "/categories",
+    tag = "categories",
+    request_body = CategoryInput,
+    responses(
+        (status = 200, body = Category),
+        (status = 400, response = Problem),
+        (status = 401, response = Problem),
+        (status = 403, response = Problem),
+        (status = 422, response = Problem),
+        (status = 500, response = Problem),
+    ),
+    security(("session" = []), ("api_key" = []), ("bearer" = []))
+)]
+#[post("/", data = "<input>")]
+#[instrument(name = "category_controller/add", skip_all)]
+async fn add(
+    app: &AppState,
+    _auth: Authorized<ProductsWrite>,
+    mut db: ConnectionDb,
+    input: Validated<CategoryInput>,
+) -> Result<Json<Category>, AppError> {
+    let input = input.into_inner();
+    let category = app
+        .use_cases
+        .category
+        .create(&app.repos, &mut db, &input)
+        .await?;
+    Ok(Json(category))
+}
+
+pub fn routes() -> Vec<rocket::Route> {
+    routes![index, add]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app_err;
+    use crate::config::Config;
+    use crate::db::Db;
+    use crate::error::catchers::catchers;
+    use crate::models::category_model::Category;
+    use crate::test::app::create_app_for_test;
+    use crate::test::auth::{authorized_auth_use_case_for_test, session_cookie_for_test};
+    use crate::use_cases::category_use_case::MockCategoryUseCase;
+    use rocket::fairing::AdHoc;
+    use rocket::http::{ContentType, Status};
+    use rocket::local::asynchronous::Client;
+    use rocket::serde::json::Value;
+    use rocket_db_pools::Database;
+    use std::sync::Arc;
+
+    async fn client(mock_category_use_case: MockCategoryUseCase) -> Client {
+        let mut app_state = create_app_for_test();
+        app_state.use_cases.category = Box::new(mock_category_use_case);
+        app_state.use_cases.auth = Box::new(authorized_auth_use_case_for_test());
+
+        let rocket = rocket::build()
+            .manage(Arc::new(app_state))
+            .attach(Db::init())
+            .attach(AdHoc::config::<Config>())
+            .register("/", catchers())
+            .mount("/", super::routes());
+        Client::tracked(rocket)
+            .await
+            .expect("valid rocket instance")
+    }
+
+    #[rocket::async_test]
+    async fn test_add_success() {
+        let mut mock_category_use_case = MockCategoryUseCase::new();
+        mock_category_use_case
+            .expect_create()
+            .withf(|_, _, input| input.name == "citrus" && input.parent_id == Some(1))
+            .returning(|_, _, input| {
+                Ok(Category {
+                    id: 2,
+                    name: input.name.clone(),
+                    parent_id: input.parent_id,
+                })
+            });
+        let client = client(mock_category_use_case).await;
+        let response = client
+            .post("/")
+            .private_cookie(session_cookie_for_test(1))
+            .header(ContentType::JSON)
+            .body(r#"{"name":"citrus","parent_id":1}"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        let body: Value = response.into_json().await.unwrap();
+        assert_eq!(body["id"], 2);
+        assert_eq!(body["parent_id"], 1);
+    }
+
+    #[rocket::async_test]
+    async fn test_add_unknown_parent() {
+        let mut mock_category_use_case = MockCategoryUseCase::new();
+        mock_category_use_case
+            .expect_create()
+            .returning(|_, _, _| app_err!(400, "category 9 does not exist"));
+        let client = client(mock_category_use_case).await;
+        let response = client
+            .post("/")
+            .private_cookie(session_cookie_for_test(1))
+            .header(ContentType::JSON)
+            .body(r#"{"name":"citrus","parent_id":9}"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::BadRequest);
+    }
+
+    #[rocket::async_test]
+    async fn test_add_unauthorized_without_session() {
+        let mut mock_category_use_case = MockCategoryUseCase::new();
+        mock_category_use_case.expect_create().never();
+        let client = client(mock_category_use_case).await;
+        let response = client
+            .post("/")
+            .header(ContentType::JSON)
+            .body(r#"{"name":"citrus"}"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Unauthorized);
+    }
+}
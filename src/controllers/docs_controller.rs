@@ -0,0 +1,289 @@
+use crate::auth::api_key::API_KEY_HEADER;
+use crate::auth::authenticated_user::SESSION_COOKIE;
+use crate::auth::jwt::Claims;
+use crate::controllers::{
+    api_key_controller, auth_controller, cart_controller, category_controller, health_controller,
+    order_controller, product_controller, user_controller,
+};
+use crate::dto::api_key_dto::ApiKeyForm;
+use crate::dto::auth_dto::{ChangePasswordForm, LoginForm, RegisterForm};
+use crate::dto::cart_dto::{CartItemForm, CartQuantityForm};
+use crate::dto::category_dto::CategoryInput;
+use crate::dto::order_dto::{OrderForm, OrderItemInput};
+use crate::dto::product_dto::{ProductCategoriesForm, ProductInput, ProductTagsForm};
+use crate::dto::user_dto::UserName;
+use crate::error::app_error::AppError;
+use crate::error::problem::{ErrorCode, Problem};
+use crate::models::api_key_model::MintedApiKey;
+use crate::models::cart_model::{CartLine, CartView};
+use crate::models::category_model::Category;
+use crate::models::health_model::{HealthCheck, HealthReport, HealthStatus};
+use crate::models::order_model::{Order, OrderDetail, OrderItem};
+use crate::models::page_model::{ProductPage, UserPage};
+use crate::models::product_model::Product;
+use crate::models::user_model::User;
+use rocket::http::ContentType;
+use rocket::response::Redirect;
+use rocket::Either;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::instrument;
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+use utoipa_swagger_ui::Config;
+
+// Swagger UIの静的ファイルはutoipa-swagger-uiがバイナリに埋め込んでいる
+type SwaggerUiFile = (ContentType, Vec<u8>);
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "rust-rocket-sqlx-sample"),
+    paths(
+        auth_controller::register,
+        auth_controller::login,
+        auth_controller::logout,
+        auth_controller::change_password,
+        auth_controller::me,
+        auth_controller::token,
+        api_key_controller::index,
+        api_key_controller::mint,
+        api_key_controller::revoke,
+        user_controller::index,
+        user_controller::add,
+        user_controller::update,
+        user_controller::delete,
+        product_controller::index,
+        product_controller::add,
+        product_controller::show,
+        product_controller::update,
+        product_controller::delete,
+        product_controller::in_category,
+        product_controller::tagged,
+        product_controller::set_categories,
+        product_controller::set_tags,
+        category_controller::index,
+        category_controller::add,
+        order_controller::index,
+        order_controller::place,
+        order_controller::show,
+        cart_controller::show,
+        cart_controller::add_item,
+        cart_controller::set_quantity,
+        cart_controller::remove_item,
+        cart_controller::checkout,
+        health_controller::live,
+        health_controller::ready,
+    ),
+    components(
+        schemas(
+            RegisterForm,
+            LoginForm,
+            ChangePasswordForm,
+            Claims,
+            ApiKeyForm,
+            crate::models::api_key_model::ApiKey,
+            MintedApiKey,
+            User,
+            UserName,
+            UserPage,
+            Product,
+            ProductInput,
+            ProductPage,
+            ProductCategoriesForm,
+            ProductTagsForm,
+            Category,
+            CategoryInput,
+            Order,
+            OrderItem,
+            OrderDetail,
+            OrderForm,
+            OrderItemInput,
+            CartView,
+            CartLine,
+            CartItemForm,
+            CartQuantityForm,
+            Problem,
+            ErrorCode,
+            HealthReport,
+            HealthCheck,
+            HealthStatus
+        ),
+        responses(Problem)
+    ),
+    modifiers(&SecuritySchemes),
+    tags(
+        (name = "auth", description = "Password accounts and sessions"),
+        (name = "api-keys", description = "Scoped API keys"),
+        (name = "users", description = "Users"),
+        (name = "products", description = "Products"),
+        (name = "categories", description = "Product category tree"),
+        (name = "orders", description = "Orders of the logged-in user"),
+        (name = "carts", description = "Shopping cart of the current session"),
+        (name = "health", description = "Liveness and readiness probes")
+    )
+)]
+pub struct ApiDoc;
+
+// 書き込みのAPIはログインのcookie、APIキーかBearerトークンで呼び出す
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "session",
+            SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::with_description(
+                SESSION_COOKIE,
+                "Set by POST /auth/login",
+            ))),
+        );
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::with_description(
+                API_KEY_HEADER,
+                "Minted with POST /api-keys",
+            ))),
+        );
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .description(Some("Signed with a key configured under [default.jwt]"))
+                    .build(),
+            ),
+        );
+    }
+}
+
+#[get("/openapi.json")]
+#[instrument(name = "docs_controller/openapi", skip_all)]
+fn openapi() -> Result<(ContentType, String), AppError> {
+    let json = ApiDoc::openapi().to_pretty_json().map_err(|e| {
+        tracing::error!("{} ({}:{})", e, file!(), line!());
+        AppError::InternalServerError
+    })?;
+    Ok((ContentType::JSON, json))
+}
+
+// index.htmlは相対パスでファイルを読み込むので、/docs と /docs/ は /docs/index.html にリダイレクトする
+// (Rocketは末尾のスラッシュを取り除いてからルーティングするため、/docs/ のままでは区別できない)
+#[get("/docs/<tail..>")]
+fn docs(tail: PathBuf) -> Result<Option<Either<Redirect, SwaggerUiFile>>, AppError> {
+    let path = tail.to_string_lossy();
+    if path.is_empty() {
+        return Ok(Some(Either::Left(Redirect::to("/docs/index.html"))));
+    }
+    let config = Arc::new(Config::from("/openapi.json"));
+    match utoipa_swagger_ui::serve(&path, config) {
+        Ok(Some(file)) => {
+            let content_type =
+                ContentType::parse_flexible(&file.content_type).unwrap_or(ContentType::Binary);
+            Ok(Some(Either::Right((content_type, file.bytes.into_owned()))))
+        }
+        Ok(None) => Ok(None),
+        Err(e) => {
+            tracing::error!("{} ({}:{})", e, file!(), line!());
+            Err(AppError::InternalServerError)
+        }
+    }
+}
+
+pub fn routes() -> Vec<rocket::Route> {
+    routes![openapi, docs]
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::http::{ContentType, Status};
+    use rocket::local::asynchronous::Client;
+    use rocket::serde::json::Value;
+
+    async fn client() -> Client {
+        let rocket = rocket::build().mount("/", super::routes());
+        Client::tracked(rocket)
+            .await
+            .expect("valid rocket instance")
+    }
+
+    #[rocket::async_test]
+    async fn test_openapi_json() {
+        let client = client().await;
+        let response = client.get("/openapi.json").dispatch().await;
+
+        assert_eq!(response.status(), Status::Ok);
+        let body: Value = response.into_json().await.expect("valid openapi json");
+        assert!(body["openapi"].as_str().unwrap().starts_with("3."));
+        for path in [
+            "/users",
+            "/users/add",
+            "/users/{id}",
+            "/products",
+            "/products/{id}",
+            "/health/ready",
+            "/auth/register",
+            "/auth/login",
+            "/auth/logout",
+            "/auth/password",
+            "/auth/me",
+            "/auth/token",
+            "/api-keys",
+            "/api-keys/{id}",
+        ] {
+            assert!(body["paths"][path].is_object(), "{} is missing", path);
+        }
+        let schemas = &body["components"]["schemas"];
+        assert!(schemas["UserPage"].is_object());
+        assert_eq!(schemas["UserName"]["properties"]["age"]["maximum"], 32.0);
+        for schema in [
+            "RegisterForm",
+            "LoginForm",
+            "ChangePasswordForm",
+            "ApiKeyForm",
+            "ApiKey",
+            "MintedApiKey",
+        ] {
+            assert!(schemas[schema].is_object(), "{} is missing", schema);
+        }
+        // key_hashはレスポンスに含めないので、スキーマにも出さない
+        assert!(schemas["ApiKey"]["properties"]["key_hash"].is_null());
+        // extensionsは任意のプロパティとしてallOfの1つ目に入る
+        assert!(schemas["Problem"]["allOf"][1]["properties"]["type"].is_object());
+        let responses = &body["paths"]["/users/{id}"]["delete"]["responses"];
+        assert_eq!(responses["403"]["$ref"], "#/components/responses/Problem");
+        assert!(body["components"]["securitySchemes"]["api_key"].is_object());
+        assert_eq!(
+            body["components"]["securitySchemes"]["bearer"]["scheme"],
+            "bearer"
+        );
+        let security = &body["paths"]["/products/{id}"]["put"]["security"];
+        assert!(security
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|s| s["bearer"].is_array()));
+    }
+
+    #[rocket::async_test]
+    async fn test_swagger_ui() {
+        let client = client().await;
+        let response = client.get("/docs").dispatch().await;
+        assert_eq!(response.status(), Status::SeeOther);
+        assert_eq!(
+            response.headers().get_one("Location"),
+            Some("/docs/index.html")
+        );
+
+        let response = client.get("/docs/index.html").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.content_type(), Some(ContentType::HTML));
+
+        let response = client.get("/docs/swagger-initializer.js").dispatch().await;
+        assert!(response
+            .into_string()
+            .await
+            .unwrap()
+            .contains("/openapi.json"));
+    }
+}
@@ -0,0 +1,120 @@
+use crate::app::AppState;
+use crate::db::Db;
+use crate::models::health_model::HealthReport;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use std::collections::BTreeMap;
+use tracing::instrument;
+
+// プロセスが動いていれば常に200を返す。依存先はチェックしない
+#[utoipa::path(
+    get,
+    path = "/health/live",
+    tag = "health",
+    responses((status = 200, body = HealthReport))
+)]
+#[get("/live")]
+#[instrument(name = "health_controller/live", skip_all)]
+fn live() -> Json<HealthReport> {
+    Json(HealthReport::new(BTreeMap::new()))
+}
+
+// 必須のチェックが1つでも失敗したら503を返す
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    tag = "health",
+    responses(
+        (status = 200, body = HealthReport),
+        (status = 503, body = HealthReport),
+    )
+)]
+#[get("/ready")]
+#[instrument(name = "health_controller/ready", skip_all)]
+async fn ready(app: &AppState, db: &Db) -> (Status, Json<HealthReport>) {
+    let report = app.use_cases.health.readiness(&app.repos, db).await;
+    let status = if report.is_up() {
+        Status::Ok
+    } else {
+        Status::ServiceUnavailable
+    };
+    (status, Json(report))
+}
+
+pub fn routes() -> Vec<rocket::Route> {
+    routes![live, ready]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Config;
+    use crate::db::Db;
+    use crate::models::health_model::{HealthCheck, HealthReport};
+    use crate::test::app::create_app_for_test;
+    use crate::use_cases::health_use_case::MockHealthUseCase;
+    use rocket::fairing::AdHoc;
+    use rocket::http::Status;
+    use rocket::local::asynchronous::Client;
+    use rocket::serde::json::Value;
+    use rocket_db_pools::Database;
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    async fn client(report: HealthReport) -> Client {
+        let mut mock_health_use_case = MockHealthUseCase::new();
+        mock_health_use_case
+            .expect_readiness()
+            .returning(move |_, _| report.clone());
+        let mut app_state = create_app_for_test();
+        app_state.use_cases.health = Box::new(mock_health_use_case);
+        let rocket = rocket::build()
+            .manage(Arc::new(app_state))
+            .attach(Db::init())
+            .attach(AdHoc::config::<Config>())
+            .mount("/health", super::routes());
+        Client::tracked(rocket)
+            .await
+            .expect("valid rocket instance")
+    }
+
+    fn report(database: Result<(), String>) -> HealthReport {
+        let mut checks = BTreeMap::new();
+        checks.insert(
+            "database".to_string(),
+            HealthCheck::new(true, Duration::from_millis(3), database),
+        );
+        HealthReport::new(checks)
+    }
+
+    #[rocket::async_test]
+    async fn test_live() {
+        let client = client(report(Ok(()))).await;
+        let response = client.get("/health/live").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let body: Value = response.into_json().await.unwrap();
+        assert_eq!(body["status"], "up");
+    }
+
+    #[rocket::async_test]
+    async fn test_ready() {
+        let client = client(report(Ok(()))).await;
+        let response = client.get("/health/ready").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let body: Value = response.into_json().await.unwrap();
+        assert_eq!(body["status"], "up");
+        assert_eq!(body["checks"]["database"]["status"], "up");
+        assert_eq!(body["checks"]["database"]["latency_ms"], 3.0);
+        assert!(body["checks"]["database"].get("error").is_none());
+    }
+
+    #[rocket::async_test]
+    async fn test_not_ready() {
+        let client = client(report(Err("connection refused".to_string()))).await;
+        let response = client.get("/health/ready").dispatch().await;
+        assert_eq!(response.status(), Status::ServiceUnavailable);
+        let body: Value = response.into_json().await.unwrap();
+        assert_eq!(body["status"], "down");
+        assert_eq!(body["checks"]["database"]["error"], "connection refused");
+    }
+}
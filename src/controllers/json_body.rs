@@ -0,0 +1,71 @@
+use rocket::data::{self, Data, FromData};
+use rocket::outcome::Outcome;
+use rocket::serde::json::{self, Json};
+use rocket::Request;
+use serde::Deserialize;
+use std::ops::Deref;
+
+// Json<T>の代わりに使うデータガード。パースエラーをcatcherから参照できるように保存する
+#[derive(Debug)]
+pub struct JsonBody<T>(pub T);
+
+impl<T> JsonBody<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for JsonBody<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct JsonBodyError {
+    pub message: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+impl JsonBodyError {
+    // catcherから呼ばれ、同じリクエストで保存されたパースエラーを返す
+    pub fn from_request(req: &Request<'_>) -> Option<JsonBodyError> {
+        req.local_cache(|| None::<JsonBodyError>).clone()
+    }
+}
+
+impl<'a> From<&json::Error<'a>> for JsonBodyError {
+    fn from(error: &json::Error<'a>) -> Self {
+        match error {
+            json::Error::Io(e) => JsonBodyError {
+                message: e.to_string(),
+                line: None,
+                column: None,
+            },
+            json::Error::Parse(_, e) => JsonBodyError {
+                message: e.to_string(),
+                line: Some(e.line()),
+                column: Some(e.column()),
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl<'r, T: Deserialize<'r>> FromData<'r> for JsonBody<T> {
+    type Error = json::Error<'r>;
+
+    async fn from_data(req: &'r Request<'_>, data: Data<'r>) -> data::Outcome<'r, Self> {
+        match <Json<T> as FromData<'r>>::from_data(req, data).await {
+            Outcome::Success(value) => Outcome::Success(JsonBody(value.into_inner())),
+            Outcome::Failure((status, e)) => {
+                req.local_cache(|| Some(JsonBodyError::from(&e)));
+                Outcome::Failure((status, e))
+            }
+            Outcome::Forward(data) => Outcome::Forward(data),
+        }
+    }
+}
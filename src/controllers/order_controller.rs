@@ -0,0 +1,245 @@
+use crate::app::AppState;
+use crate::auth::authenticated_user::AuthenticatedUser;
+use crate::controllers::validated::Validated;
+use crate::db::ConnectionDb;
+use crate::dto::order_dto::OrderForm;
+use crate::error::app_error::AppError;
+use crate::error::problem::Problem;
+use crate::models::order_model::{Order, OrderDetail};
+use rocket::serde::json::Json;
+use tracing::instrument;
+
+#[utoipa::path(
+    get,
+    path = "/orders",
+    tag = "orders",
+    responses(
+        (status = 200, body = [Order]),
+        (status = 401, response = Problem),
+        (status = 500, response = Problem),
+    ),
+    security(("session" = []))
+)]
+#[get("/")]
+#[instrument(name = "order_controller/index", skip_all)]
+async fn index(
+    app: &AppState,
+    user: AuthenticatedUser,
+    mut db: ConnectionDb,
+) -> Result<Json<Vec<Order>>, AppError> {
+    let orders = app
+        .use_cases
+        .order
+        .find_by_user(&app.repos, &mut db, user.user_id)
+        .await?;
+    Ok(Json(orders))
+}
+
+#[utoipa::path(
+    post,
+    path = "/orders",
+    tag = "orders",
+    request_body = OrderForm,
+    responses(
+        (status = 200, body = OrderDetail),
+        (status = 400, response = Problem),
+        (status = 401, response = Problem),
+        (status = 409, response = Problem),
+        (status = 422, response = Problem),
+        (status = 500, response = Problem),
+    ),
+    security(("session" = []))
+)]
+#[post("/", data = "<form>")]
+#[instrument(name = "order_controller/place", skip_all)]
+async fn place(
+    app: &AppState,
+    user: AuthenticatedUser,
+    mut db: ConnectionDb,
+    form: Validated<OrderForm>,
+) -> Result<Json<OrderDetail>, AppError> {
+    let form = form.into_inner();
+    let order = app
+        .use_cases
+        .order
+        .place(&app.repos, &mut db, user.user_id, &form.items)
+        .await?;
+    Ok(Json(order))
+}
+
+#[utoipa::path(
+    get,
+    path = "/orders/{id}",
+    tag = "orders",
+    params(("id" = i32, Path, description = "id")),
+    responses(
+        (status = 200, body = OrderDetail),
+        (status = 401, response = Problem),
+        (status = 404, response = Problem),
+        (status = 500, response = Problem),
+    ),
+    security(("session" = []))
+)]
+#[get("/<id>")]
+#[instrument(name = "order_controller/show", skip_all, fields(id = %id))]
+async fn show(
+    app: &AppState,
+    user: AuthenticatedUser,
+    mut db: ConnectionDb,
+    id: i32,
+) -> Result<Json<OrderDetail>, AppError> {
+    let order = app
+        .use_cases
+        .order
+        .find_by_id(&app.repos, &mut db, user.user_id, id)
+        .await?;
+    Ok(Json(order))
+}
+
+pub fn routes() -> Vec<rocket::Route> {
+    routes![index, place, show]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Config;
+    use crate::db::Db;
+    use crate::error::app_error::AppError;
+    use crate::error::catchers::catchers;
+    use crate::test::app::create_app_for_test;
+    use crate::test::auth::session_cookie_for_test;
+    use crate::test::fixture::order::order_detail_fixture;
+    use crate::use_cases::order_use_case::MockOrderUseCase;
+    use rocket::fairing::AdHoc;
+    use rocket::http::{ContentType, Status};
+    use rocket::local::asynchronous::Client;
+    use rocket::serde::json::Value;
+    use rocket_db_pools::Database;
+    use std::sync::Arc;
+
+    async fn client(mock_order_use_case: MockOrderUseCase) -> Client {
+        let mut app_state = create_app_for_test();
+        app_state.use_cases.order = Box::new(mock_order_use_case);
+
+        let rocket = rocket::build()
+            .manage(Arc::new(app_state))
+            .attach(Db::init())
+            .attach(AdHoc::config::<Config>())
+            .register("/", catchers())
+            .mount("/", super::routes());
+        Client::tracked(rocket)
+            .await
+            .expect("valid rocket instance")
+    }
+
+    #[rocket::async_test]
+    async fn test_place_success() {
+        let mut mock_order_use_case = MockOrderUseCase::new();
+        mock_order_use_case
+            .expect_place()
+            .withf(|_, _, user_id, items| {
+                *user_id == 1 && items.len() == 2 && items[0].product_id == 3
+            })
+            .returning(|_, _, user_id, _| Ok(order_detail_fixture(10, user_id)));
+        let client = client(mock_order_use_case).await;
+        let response = client
+            .post("/")
+            .private_cookie(session_cookie_for_test(1))
+            .header(ContentType::JSON)
+            .body(r#"{"items":[{"product_id":3,"quantity":2},{"product_id":4,"quantity":1}]}"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        let body: Value = response.into_json().await.unwrap();
+        assert_eq!(body["id"], 10);
+        assert_eq!(body["total"], 240);
+        assert_eq!(body["items"][0]["quantity"], 2);
+    }
+
+    #[rocket::async_test]
+    async fn test_place_unauthorized_without_session() {
+        let mut mock_order_use_case = MockOrderUseCase::new();
+        mock_order_use_case.expect_place().never();
+        let client = client(mock_order_use_case).await;
+        let response = client
+            .post("/")
+            .header(ContentType::JSON)
+            .body(r#"{"items":[{"product_id":3,"quantity":2}]}"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Unauthorized);
+    }
+
+    #[rocket::async_test]
+    async fn test_place_invalid_items() {
+        let mut mock_order_use_case = MockOrderUseCase::new();
+        mock_order_use_case.expect_place().never();
+        let client = client(mock_order_use_case).await;
+        let response = client
+            .post("/")
+            .private_cookie(session_cookie_for_test(1))
+            .header(ContentType::JSON)
+            .body(r#"{"items":[{"product_id":3,"quantity":1},{"product_id":4,"quantity":0}]}"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+        let body: Value = response.into_json().await.unwrap();
+        assert_eq!(
+            body["errors"]["items[1].quantity"][0],
+            "must be between 1 and 1000"
+        );
+
+        let response = client
+            .post("/")
+            .private_cookie(session_cookie_for_test(1))
+            .header(ContentType::JSON)
+            .body(r#"{"items":[]}"#)
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+    }
+
+    #[rocket::async_test]
+    async fn test_place_out_of_stock() {
+        let mut mock_order_use_case = MockOrderUseCase::new();
+        mock_order_use_case.expect_place().returning(|_, _, _, _| {
+            Err(AppError::OutOfStock {
+                product_id: 3,
+                available: 1,
+            })
+        });
+        let client = client(mock_order_use_case).await;
+        let response = client
+            .post("/")
+            .private_cookie(session_cookie_for_test(1))
+            .header(ContentType::JSON)
+            .body(r#"{"items":[{"product_id":3,"quantity":2}]}"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Conflict);
+        let body: Value = response.into_json().await.unwrap();
+        assert_eq!(body["code"], "CONFLICT");
+        assert_eq!(body["product_id"], 3);
+        assert_eq!(body["available"], 1);
+    }
+
+    #[rocket::async_test]
+    async fn test_show_not_found() {
+        let mut mock_order_use_case = MockOrderUseCase::new();
+        mock_order_use_case
+            .expect_find_by_id()
+            .returning(|_, _, _, _| Err(AppError::NotFound));
+        let client = client(mock_order_use_case).await;
+        let response = client
+            .get("/7")
+            .private_cookie(session_cookie_for_test(1))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::NotFound);
+    }
+}
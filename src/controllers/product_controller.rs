@@ -1,36 +1,320 @@
 use crate::app::AppState;
+use crate::auth::authorized::{Authorized, ProductsWrite};
+use crate::controllers::validated::Validated;
 use crate::db::ConnectionDb;
-use crate::dto::product_dto::ProductName;
+use crate::dto::page_dto::PageQuery;
+use crate::dto::page_dto::{query_error, ListQuery};
+use crate::dto::product_dto::{
+    ProductCategoriesForm, ProductFilterQuery, ProductInput, ProductTagsForm,
+};
 use crate::error::app_error::AppError;
-use crate::models::product_model::Product;
+use crate::error::problem::Problem;
+use crate::models::category_model::Category;
+use crate::models::page_model::Page;
+use crate::models::product_model::{Product, ProductFilter};
+use rocket::form::{Errors, Strict};
 use rocket::serde::json::Json;
 use tracing::instrument;
 
-#[get("/")]
+#[utoipa::path(
+    get,
+    path = "/products",
+    tag = "products",
+    params(PageQuery, ProductFilterQuery),
+    responses(
+        (status = 200, body = crate::models::page_model::ProductPage),
+        (status = 400, response = Problem),
+        (status = 500, response = Problem),
+    )
+)]
+#[get("/?<query..>")]
 #[instrument(name = "product_controller/index", skip_all)]
-async fn index(app: &AppState, mut db: ConnectionDb) -> Result<Json<Vec<Product>>, AppError> {
-    let products = app.use_cases.product.find_all(&app.repos, &mut db).await?;
+async fn index(
+    app: &AppState,
+    mut db: ConnectionDb,
+    query: Result<Strict<ListQuery<ProductFilterQuery>>, Errors<'_>>,
+) -> Result<Json<Page<Product>>, AppError> {
+    let query = query.map_err(query_error)?.into_inner();
+    let page = query.page.to_page_request(Product::SORTABLE_COLUMNS)?;
+    let filter = ProductFilter::from(query.filter);
+    let products = app
+        .use_cases
+        .product
+        .find_page(&app.repos, &mut db, &filter, &page)
+        .await?;
     Ok(Json(products))
 }
 
-#[post("/add", data = "<name>")]
+#[utoipa::path(
+    post,
+    path = "/products/add",
+    tag = "products",
+    request_body = ProductInput,
+    responses(
+        (status = 200, body = Product),
+        (status = 400, response = Problem),
+        (status = 401, response = Problem),
+        (status = 403, response = Problem),
+        (status = 409, response = Problem),
+        (status = 422, response = Problem),
+        (status = 500, response = Problem),
+    ),
+    security(("session" = []), ("api_key" = []), ("bearer" = []))
+)]
+#[post("/add", data = "<input>")]
 #[instrument(name = "product_controller/add", skip_all)]
 async fn add(
     app: &AppState,
+    _auth: Authorized<ProductsWrite>,
+    mut db: ConnectionDb,
+    input: Validated<ProductInput>,
+) -> Result<Json<Product>, AppError> {
+    let input = input.into_inner();
+    let product = app
+        .use_cases
+        .product
+        .create(&app.repos, &mut db, &input)
+        .await?;
+    Ok(Json(product))
+}
+
+#[utoipa::path(
+    get,
+    path = "/products/{id}",
+    tag = "products",
+    params(("id" = i32, Path, description = "id")),
+    responses(
+        (status = 200, body = Product),
+        (status = 404, response = Problem),
+        (status = 500, response = Problem),
+    )
+)]
+#[get("/<id>")]
+#[instrument(name = "product_controller/show", skip_all, fields(id = %id))]
+async fn show(app: &AppState, mut db: ConnectionDb, id: i32) -> Result<Json<Product>, AppError> {
+    let product = app
+        .use_cases
+        .product
+        .find_by_id(&app.repos, &mut db, id)
+        .await?;
+    Ok(Json(product))
+}
+
+#[utoipa::path(
+    put,
+    path = "/products/{id}",
+    tag = "products",
+    params(("id" = i32, Path, description = "id")),
+    request_body = ProductInput,
+    responses(
+        (status = 200, body = Product),
+        (status = 400, response = Problem),
+        (status = 401, response = Problem),
+        (status = 403, response = Problem),
+        (status = 404, response = Problem),
+        (status = 409, response = Problem),
+        (status = 422, response = Problem),
+        (status = 500, response = Problem),
+    ),
+    security(("session" = []), ("api_key" = []), ("bearer" = []))
+)]
+#[put("/<id>", data = "<input>")]
+#[instrument(name = "product_controller/update", skip_all, fields(id = %id))]
+async fn update(
+    app: &AppState,
+    _auth: Authorized<ProductsWrite>,
     mut db: ConnectionDb,
-    name: Json<ProductName>,
+    id: i32,
+    input: Validated<ProductInput>,
 ) -> Result<Json<Product>, AppError> {
-    let name = name.into_inner().name;
+    let input = input.into_inner();
     let product = app
         .use_cases
         .product
-        .create(&app.repos, &mut db, &name)
+        .update(&app.repos, &mut db, id, &input)
         .await?;
     Ok(Json(product))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/products/{id}",
+    tag = "products",
+    params(("id" = i32, Path, description = "id")),
+    responses(
+        (status = 200, description = "Deleted"),
+        (status = 401, response = Problem),
+        (status = 403, response = Problem),
+        (status = 404, response = Problem),
+        (status = 500, response = Problem),
+    ),
+    security(("session" = []), ("api_key" = []), ("bearer" = []))
+)]
+#[delete("/<id>")]
+#[instrument(name = "product_controller/delete", skip_all, fields(id = %id))]
+async fn delete(
+    app: &AppState,
+    _auth: Authorized<ProductsWrite>,
+    mut db: ConnectionDb,
+    id: i32,
+) -> Result<(), AppError> {
+    app.use_cases
+        .product
+        .delete(&app.repos, &mut db, id)
+        .await?;
+    Ok(())
+}
+
+// カテゴリとその子孫のカテゴリに属する商品。絞り込みとページングはGET /productsと同じ
+#[utoipa::path(
+    get,
+    path = "/products/category/{category_id}",
+    tag = "products",
+    params(
+        ("category_id" = i32, Path, description = "category id"),
+        PageQuery,
+        ProductFilterQuery
+    ),
+    responses(
+        (status = 200, body = crate::models::page_model::ProductPage),
+        (status = 400, response = Problem),
+        (status = 404, response = Problem),
+        (status = 500, response = Problem),
+    )
+)]
+#[get("/category/<category_id>?<query..>")]
+#[instrument(name = "product_controller/in_category", skip_all, fields(category_id = %category_id))]
+async fn in_category(
+    app: &AppState,
+    mut db: ConnectionDb,
+    category_id: i32,
+    query: Result<Strict<ListQuery<ProductFilterQuery>>, Errors<'_>>,
+) -> Result<Json<Page<Product>>, AppError> {
+    let query = query.map_err(query_error)?.into_inner();
+    let page = query.page.to_page_request(Product::SORTABLE_COLUMNS)?;
+    let filter = ProductFilter::from(query.filter);
+    let products = app
+        .use_cases
+        .product
+        .find_page_in_category(&app.repos, &mut db, category_id, &filter, &page)
+        .await?;
+    Ok(Json(products))
+}
+
+#[utoipa::path(
+    get,
+    path = "/products/tag/{tag}",
+    tag = "products",
+    params(("tag" = String, Path, description = "tag"), PageQuery, ProductFilterQuery),
+    responses(
+        (status = 200, body = crate::models::page_model::ProductPage),
+        (status = 400, response = Problem),
+        (status = 500, response = Problem),
+    )
+)]
+#[get("/tag/<tag>?<query..>")]
+#[instrument(name = "product_controller/tagged", skip_all, fields(tag = %tag))]
+async fn tagged(
+    app: &AppState,
+    mut db: ConnectionDb,
+    tag: &str,
+    query: Result<Strict<ListQuery<ProductFilterQuery>>, Errors<'_>>,
+) -> Result<Json<Page<Product>>, AppError> {
+    let query = query.map_err(query_error)?.into_inner();
+    let page = query.page.to_page_request(Product::SORTABLE_COLUMNS)?;
+    let filter = ProductFilter {
+        tag: Some(tag.to_string()),
+        ..ProductFilter::from(query.filter)
+    };
+    let products = app
+        .use_cases
+        .product
+        .find_page(&app.repos, &mut db, &filter, &page)
+        .await?;
+    Ok(Json(products))
+}
+
+#[utoipa::path(
+    put,
+    path = "/products/{id}/categories",
+    tag = "products",
+    params(("id" = i32, Path, description = "id")),
+    request_body = ProductCategoriesForm,
+    responses(
+        (status = 200, body = [Category]),
+        (status = 400, response = Problem),
+        (status = 401, response = Problem),
+        (status = 403, response = Problem),
+        (status = 404, response = Problem),
+        (status = 422, response = Problem),
+        (status = 500, response = Problem),
+    ),
+    security(("session" = []), ("api_key" = []), ("bearer" = []))
+)]
+#[put("/<id>/categories", data = "<form>")]
+#[instrument(name = "product_controller/set_categories", skip_all, fields(id = %id))]
+async fn set_categories(
+    app: &AppState,
+    _auth: Authorized<ProductsWrite>,
+    mut db: ConnectionDb,
+    id: i32,
+    form: Validated<ProductCategoriesForm>,
+) -> Result<Json<Vec<Category>>, AppError> {
+    let form = form.into_inner();
+    let categories = app
+        .use_cases
+        .product
+        .set_categories(&app.repos, &mut db, id, &form.category_ids)
+        .await?;
+    Ok(Json(categories))
+}
+
+#[utoipa::path(
+    put,
+    path = "/products/{id}/tags",
+    tag = "products",
+    params(("id" = i32, Path, description = "id")),
+    request_body = ProductTagsForm,
+    responses(
+        (status = 200, body = [String]),
+        (status = 401, response = Problem),
+        (status = 403, response = Problem),
+        (status = 404, response = Problem),
+        (status = 422, response = Problem),
+        (status = 500, response = Problem),
+    ),
+    security(("session" = []), ("api_key" = []), ("bearer" = []))
+)]
+#[put("/<id>/tags", data = "<form>")]
+#[instrument(name = "product_controller/set_tags", skip_all, fields(id = %id))]
+async fn set_tags(
+    app: &AppState,
+    _auth: Authorized<ProductsWrite>,
+    mut db: ConnectionDb,
+    id: i32,
+    form: Validated<ProductTagsForm>,
+) -> Result<Json<Vec<String>>, AppError> {
+    let form = form.into_inner();
+    let tags = app
+        .use_cases
+        .product
+        .set_tags(&app.repos, &mut db, id, &form.tags)
+        .await?;
+    Ok(Json(tags))
+}
+
 pub fn routes() -> Vec<rocket::Route> {
-    routes![index, add]
+    routes![
+        index,
+        add,
+        show,
+        update,
+        delete,
+        in_category,
+        tagged,
+        set_categories,
+        set_tags
+    ]
 }
 
 #[cfg(test)]
@@ -38,21 +322,39 @@ mod tests {
     use crate::app_err;
     use crate::config::Config;
     use crate::db::Db;
+    use crate::error::app_error::AppError;
+    use crate::error::catchers::catchers;
+    use crate::models::page_model::Page;
     use crate::test::app::create_app_for_test;
-    use crate::test::fixture::product::products_fixture;
+    use crate::test::auth::{
+        authorized_auth_use_case_for_test, bearer_header_for_test, jwt_verifier_for_test,
+        session_cookie_for_test,
+    };
+    use crate::test::fixture::product::{product_fixture, products_fixture};
+    use crate::use_cases::auth_use_case::MockAuthUseCase;
     use crate::use_cases::product_use_case::MockProductUseCase;
     use rocket::fairing::AdHoc;
-    use rocket::http::Status;
+    use rocket::http::{ContentType, Status};
     use rocket::local::asynchronous::Client;
+    use rocket::serde::json::Value;
     use rocket_db_pools::Database;
     use std::sync::Arc;
 
+    const PRODUCT_JSON: &str =
+        r#"{"name":"orange","sku":"ORANGE-1","price":150,"currency":"JPY","stock":3}"#;
+
     #[rocket::async_test]
     async fn test_index_success() {
         let mut mock_product_use_case = MockProductUseCase::new();
         mock_product_use_case
-            .expect_find_all()
-            .returning(|_, _| Ok(products_fixture(5)));
+            .expect_find_page()
+            .returning(|_, _, _, _| {
+                Ok(Page {
+                    items: products_fixture(5),
+                    next_cursor: None,
+                    total: None,
+                })
+            });
 
         let mut app_state = create_app_for_test();
         app_state.use_cases.product = Box::new(mock_product_use_case);
@@ -74,8 +376,8 @@ mod tests {
     async fn test_index_fail() {
         let mut mock_product_use_case = MockProductUseCase::new();
         mock_product_use_case
-            .expect_find_all()
-            .returning(|_, _| app_err!(500, "error"));
+            .expect_find_page()
+            .returning(|_, _, _, _| app_err!(500, "error"));
 
         let mut app_state = create_app_for_test();
         app_state.use_cases.product = Box::new(mock_product_use_case);
@@ -91,7 +393,387 @@ mod tests {
         let response = client.get("/").dispatch().await;
 
         assert_eq!(response.status(), Status::InternalServerError);
-        let body_str = response.into_string().await.expect("valid body string");
-        assert_eq!(body_str, "error");
+        assert_eq!(
+            response.content_type(),
+            Some(ContentType::new("application", "problem+json"))
+        );
+        let body: Value = response.into_json().await.expect("valid problem json");
+        assert_eq!(body["status"], 500);
+        assert_eq!(body["detail"], "error");
+        assert_eq!(body["code"], "INTERNAL_SERVER_ERROR");
+        assert_eq!(body["instance"], "/");
+    }
+
+    #[rocket::async_test]
+    async fn test_show_success() {
+        let mut mock_product_use_case = MockProductUseCase::new();
+        mock_product_use_case
+            .expect_find_by_id()
+            .returning(|_, _, id| Ok(product_fixture(id as usize)));
+
+        let mut app_state = create_app_for_test();
+        app_state.use_cases.product = Box::new(mock_product_use_case);
+
+        let rocket = rocket::build()
+            .manage(Arc::new(app_state))
+            .attach(Db::init())
+            .attach(AdHoc::config::<Config>())
+            .mount("/", routes![super::show]);
+        let client = Client::tracked(rocket)
+            .await
+            .expect("valid rocket instance");
+        let response = client.get("/7").dispatch().await;
+
+        assert_eq!(response.status(), Status::Ok);
+    }
+
+    #[rocket::async_test]
+    async fn test_show_not_found() {
+        let mut mock_product_use_case = MockProductUseCase::new();
+        mock_product_use_case
+            .expect_find_by_id()
+            .returning(|_, _, _| Err(AppError::NotFound));
+
+        let mut app_state = create_app_for_test();
+        app_state.use_cases.product = Box::new(mock_product_use_case);
+
+        let rocket = rocket::build()
+            .manage(Arc::new(app_state))
+            .attach(Db::init())
+            .attach(AdHoc::config::<Config>())
+            .mount("/", routes![super::show]);
+        let client = Client::tracked(rocket)
+            .await
+            .expect("valid rocket instance");
+        let response = client.get("/7").dispatch().await;
+
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[rocket::async_test]
+    async fn test_update_success() {
+        let mut mock_product_use_case = MockProductUseCase::new();
+        mock_product_use_case
+            .expect_update()
+            .returning(|_, _, id, _| Ok(product_fixture(id as usize)));
+
+        let mut app_state = create_app_for_test();
+        app_state.use_cases.product = Box::new(mock_product_use_case);
+        app_state.use_cases.auth = Box::new(authorized_auth_use_case_for_test());
+
+        let rocket = rocket::build()
+            .manage(Arc::new(app_state))
+            .attach(Db::init())
+            .attach(AdHoc::config::<Config>())
+            .mount("/", routes![super::update]);
+        let client = Client::tracked(rocket)
+            .await
+            .expect("valid rocket instance");
+        let response = client
+            .put("/7")
+            .private_cookie(session_cookie_for_test(1))
+            .header(ContentType::JSON)
+            .body(PRODUCT_JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+    }
+
+    #[rocket::async_test]
+    async fn test_update_with_bearer_token() {
+        let mut mock_product_use_case = MockProductUseCase::new();
+        mock_product_use_case
+            .expect_update()
+            .returning(|_, _, id, _| Ok(product_fixture(id as usize)));
+        let mut mock_auth_use_case = MockAuthUseCase::new();
+        mock_auth_use_case
+            .expect_role_permissions()
+            .withf(|_, _, roles| roles == ["editor".to_string()])
+            .returning(|_, _, _| Ok(vec!["products:write".to_string()]));
+
+        let mut app_state = create_app_for_test();
+        app_state.use_cases.product = Box::new(mock_product_use_case);
+        app_state.use_cases.auth = Box::new(mock_auth_use_case);
+        app_state.jwt = Some(jwt_verifier_for_test());
+
+        let rocket = rocket::build()
+            .manage(Arc::new(app_state))
+            .attach(Db::init())
+            .attach(AdHoc::config::<Config>())
+            .mount("/", routes![super::update]);
+        let client = Client::tracked(rocket)
+            .await
+            .expect("valid rocket instance");
+        let response = client
+            .put("/7")
+            .header(bearer_header_for_test(&["editor"]))
+            .header(ContentType::JSON)
+            .body(PRODUCT_JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+    }
+
+    #[rocket::async_test]
+    async fn test_delete_not_found() {
+        let mut mock_product_use_case = MockProductUseCase::new();
+        mock_product_use_case
+            .expect_delete()
+            .returning(|_, _, _| Err(AppError::NotFound));
+
+        let mut app_state = create_app_for_test();
+        app_state.use_cases.product = Box::new(mock_product_use_case);
+        app_state.use_cases.auth = Box::new(authorized_auth_use_case_for_test());
+
+        let rocket = rocket::build()
+            .manage(Arc::new(app_state))
+            .attach(Db::init())
+            .attach(AdHoc::config::<Config>())
+            .mount("/", routes![super::delete]);
+        let client = Client::tracked(rocket)
+            .await
+            .expect("valid rocket instance");
+        let response = client
+            .delete("/7")
+            .private_cookie(session_cookie_for_test(1))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[rocket::async_test]
+    async fn test_add_unauthorized_without_session() {
+        let mut mock_product_use_case = MockProductUseCase::new();
+        mock_product_use_case.expect_create().never();
+
+        let mut app_state = create_app_for_test();
+        app_state.use_cases.product = Box::new(mock_product_use_case);
+
+        let rocket = rocket::build()
+            .manage(Arc::new(app_state))
+            .attach(Db::init())
+            .attach(AdHoc::config::<Config>())
+            .mount("/", routes![super::add]);
+        let client = Client::tracked(rocket)
+            .await
+            .expect("valid rocket instance");
+        let response = client
+            .post("/add")
+            .header(ContentType::JSON)
+            .body(PRODUCT_JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Unauthorized);
+    }
+
+    #[rocket::async_test]
+    async fn test_add_duplicate_sku_is_conflict() {
+        let mut mock_product_use_case = MockProductUseCase::new();
+        mock_product_use_case
+            .expect_create()
+            .returning(|_, _, _| app_err!(409, "sku ORANGE-1 is already in use"));
+
+        let mut app_state = create_app_for_test();
+        app_state.use_cases.product = Box::new(mock_product_use_case);
+        app_state.use_cases.auth = Box::new(authorized_auth_use_case_for_test());
+
+        let rocket = rocket::build()
+            .manage(Arc::new(app_state))
+            .attach(Db::init())
+            .attach(AdHoc::config::<Config>())
+            .mount("/", routes![super::add]);
+        let client = Client::tracked(rocket)
+            .await
+            .expect("valid rocket instance");
+        let response = client
+            .post("/add")
+            .private_cookie(session_cookie_for_test(1))
+            .header(ContentType::JSON)
+            .body(PRODUCT_JSON)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Conflict);
+        let body: Value = response.into_json().await.expect("valid problem json");
+        assert_eq!(body["code"], "CONFLICT");
+    }
+
+    #[rocket::async_test]
+    async fn test_add_invalid_input() {
+        let mut mock_product_use_case = MockProductUseCase::new();
+        mock_product_use_case.expect_create().never();
+
+        let mut app_state = create_app_for_test();
+        app_state.use_cases.product = Box::new(mock_product_use_case);
+        app_state.use_cases.auth = Box::new(authorized_auth_use_case_for_test());
+
+        let rocket = rocket::build()
+            .manage(Arc::new(app_state))
+            .attach(Db::init())
+            .attach(AdHoc::config::<Config>())
+            .register("/", catchers())
+            .mount("/", routes![super::add]);
+        let client = Client::tracked(rocket)
+            .await
+            .expect("valid rocket instance");
+        let response = client
+            .post("/add")
+            .private_cookie(session_cookie_for_test(1))
+            .header(ContentType::JSON)
+            .body(r#"{"name":"orange","sku":"ORANGE 1","price":-1,"currency":"yen","stock":3}"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+        let body: Value = response.into_json().await.expect("valid problem json");
+        assert_eq!(
+            body["errors"]["currency"][0],
+            "must be an ISO 4217 currency code"
+        );
+        assert_eq!(body["errors"]["price"][0], "must not be negative");
+        assert_eq!(
+            body["errors"]["sku"][0],
+            "must consist of letters, digits, '-' and '_'"
+        );
+    }
+
+    #[rocket::async_test]
+    async fn test_in_category_not_found() {
+        let mut mock_product_use_case = MockProductUseCase::new();
+        mock_product_use_case
+            .expect_find_page_in_category()
+            .withf(|_, _, category_id, _, _| *category_id == 9)
+            .returning(|_, _, _, _, _| Err(AppError::NotFound));
+
+        let mut app_state = create_app_for_test();
+        app_state.use_cases.product = Box::new(mock_product_use_case);
+
+        let rocket = rocket::build()
+            .manage(Arc::new(app_state))
+            .attach(Db::init())
+            .attach(AdHoc::config::<Config>())
+            .mount("/", routes![super::in_category]);
+        let client = Client::tracked(rocket)
+            .await
+            .expect("valid rocket instance");
+        let response = client.get("/category/9?limit=5").dispatch().await;
+
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[rocket::async_test]
+    async fn test_tagged_sets_tag_filter() {
+        let mut mock_product_use_case = MockProductUseCase::new();
+        mock_product_use_case
+            .expect_find_page()
+            .withf(|_, _, filter, _| {
+                filter.tag.as_deref() == Some("red") && filter.name_like.as_deref() == Some("app")
+            })
+            .returning(|_, _, _, _| {
+                Ok(Page {
+                    items: products_fixture(2),
+                    next_cursor: None,
+                    total: None,
+                })
+            });
+
+        let mut app_state = create_app_for_test();
+        app_state.use_cases.product = Box::new(mock_product_use_case);
+
+        let rocket = rocket::build()
+            .manage(Arc::new(app_state))
+            .attach(Db::init())
+            .attach(AdHoc::config::<Config>())
+            .mount("/", routes![super::tagged]);
+        let client = Client::tracked(rocket)
+            .await
+            .expect("valid rocket instance");
+        let response = client.get("/tag/red?name_like=app").dispatch().await;
+
+        assert_eq!(response.status(), Status::Ok);
+        let body: Value = response.into_json().await.unwrap();
+        assert_eq!(body["items"].as_array().unwrap().len(), 2);
+    }
+
+    #[rocket::async_test]
+    async fn test_set_tags() {
+        let mut mock_product_use_case = MockProductUseCase::new();
+        mock_product_use_case
+            .expect_set_tags()
+            .withf(|_, _, id, tags| *id == 7 && tags.len() == 2)
+            .returning(|_, _, _, tags| Ok(tags.iter().map(|tag| tag.to_lowercase()).collect()));
+
+        let mut app_state = create_app_for_test();
+        app_state.use_cases.product = Box::new(mock_product_use_case);
+        app_state.use_cases.auth = Box::new(authorized_auth_use_case_for_test());
+
+        let rocket = rocket::build()
+            .manage(Arc::new(app_state))
+            .attach(Db::init())
+            .attach(AdHoc::config::<Config>())
+            .register("/", catchers())
+            .mount("/", routes![super::set_tags]);
+        let client = Client::tracked(rocket)
+            .await
+            .expect("valid rocket instance");
+        let response = client
+            .put("/7/tags")
+            .private_cookie(session_cookie_for_test(1))
+            .header(ContentType::JSON)
+            .body(r#"{"tags":["Red","organic"]}"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        let body: Value = response.into_json().await.unwrap();
+        assert_eq!(body, serde_json::json!(["red", "organic"]));
+
+        let response = client
+            .put("/7/tags")
+            .private_cookie(session_cookie_for_test(1))
+            .header(ContentType::JSON)
+            .body(r#"{"tags":["  "]}"#)
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+        let body: Value = response.into_json().await.unwrap();
+        assert_eq!(
+            body["errors"]["tags"][0],
+            "each tag must be 1 to 50 characters"
+        );
+    }
+
+    #[rocket::async_test]
+    async fn test_set_categories_unknown_category() {
+        let mut mock_product_use_case = MockProductUseCase::new();
+        mock_product_use_case
+            .expect_set_categories()
+            .returning(|_, _, _, _| app_err!(400, "category 9 does not exist"));
+
+        let mut app_state = create_app_for_test();
+        app_state.use_cases.product = Box::new(mock_product_use_case);
+        app_state.use_cases.auth = Box::new(authorized_auth_use_case_for_test());
+
+        let rocket = rocket::build()
+            .manage(Arc::new(app_state))
+            .attach(Db::init())
+            .attach(AdHoc::config::<Config>())
+            .mount("/", routes![super::set_categories]);
+        let client = Client::tracked(rocket)
+            .await
+            .expect("valid rocket instance");
+        let response = client
+            .put("/7/categories")
+            .private_cookie(session_cookie_for_test(1))
+            .header(ContentType::JSON)
+            .body(r#"{"category_ids":[1,9]}"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::BadRequest);
     }
 }
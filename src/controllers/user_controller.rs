@@ -1,42 +1,82 @@
 use std::io::IntoInnerError;
 
 use crate::app::AppState;
+use crate::auth::authorized::{Authorized, UsersWrite};
+use crate::controllers::validated::Validated;
 use crate::db::ConnectionDb;
-use crate::dto::user_dto::UserName;
+use crate::dto::page_dto::PageQuery;
+use crate::dto::page_dto::{query_error, ListQuery};
+use crate::dto::user_dto::{UserFilterQuery, UserName};
 use crate::error::app_error::AppError;
-use crate::models::user_model::User;
+use crate::error::problem::Problem;
+use crate::models::page_model::Page;
+use crate::models::user_model::{User, UserFilter};
+use rocket::form::{Errors, Strict};
 use rocket::serde::json::Json;
 use tracing::instrument;
 
-#[get("/")]
+#[utoipa::path(
+    get,
+    path = "/users",
+    tag = "users",
+    params(PageQuery, UserFilterQuery),
+    responses(
+        (status = 200, body = crate::models::page_model::UserPage),
+        (status = 400, response = Problem),
+        (status = 500, response = Problem),
+    )
+)]
+#[get("/?<query..>")]
 #[instrument(name = "user_controller/index", skip_all)]
-async fn index(app: &AppState, mut db: ConnectionDb) -> Result<Json<Vec<User>>, AppError> {
-    let users = app.use_cases.user.find_all(&app.repos, &mut db).await?;
+async fn index(
+    app: &AppState,
+    mut db: ConnectionDb,
+    query: Result<Strict<ListQuery<UserFilterQuery>>, Errors<'_>>,
+) -> Result<Json<Page<User>>, AppError> {
+    let query = query.map_err(query_error)?.into_inner();
+    let page = query.page.to_page_request(User::SORTABLE_COLUMNS)?;
+    let filter = UserFilter::from(query.filter);
+    let users = app
+        .use_cases
+        .user
+        .find_page(&app.repos, &mut db, &filter, &page)
+        .await?;
     Ok(Json(users))
 }
 
 // post request with json body
+#[utoipa::path(
+    post,
+    path = "/users/add",
+    tag = "users",
+    request_body = UserName,
+    responses(
+        (status = 200, body = User),
+        (status = 400, response = Problem),
+        (status = 401, response = Problem),
+        (status = 403, response = Problem),
+        (status = 422, response = Problem),
+        (status = 500, response = Problem),
+    ),
+    security(("session" = []), ("api_key" = []), ("bearer" = []))
+)]
 #[post("/add", data = "<user_json>")]
 #[instrument(name = "user_controller/add", skip_all)]
 async fn add(
     app: &AppState,
+    _auth: Authorized<UsersWrite>,
     mut db: ConnectionDb,
-    user_json: Json<UserName>,
+    user_json: Validated<UserName>,
 ) -> Result<Json<User>, AppError> {
 
     tracing::info!("==Stating processing...");
     let inner = user_json.into_inner();
     
-    tracing::info!("Received request to add user. Name: {}, Age: {}", inner.name, inner.age);
+    tracing::info!(name = %inner.name, age = inner.age, "Received request to add user");
 
     let name = inner.name;
     let age = inner.age;
 
-    // send 400 if age > 32
-    if age > 32 {
-        return Err(AppError::new(400, "age must be less than 32"));
-    }
-
     let user = app
         .use_cases
         .user
@@ -45,13 +85,31 @@ async fn add(
     Ok(Json(user))
 }
 
+#[utoipa::path(
+    put,
+    path = "/users/{id}",
+    tag = "users",
+    params(("id" = i32, Path, description = "id")),
+    request_body = UserName,
+    responses(
+        (status = 200, body = User),
+        (status = 400, response = Problem),
+        (status = 401, response = Problem),
+        (status = 403, response = Problem),
+        (status = 404, response = Problem),
+        (status = 422, response = Problem),
+        (status = 500, response = Problem),
+    ),
+    security(("session" = []), ("api_key" = []), ("bearer" = []))
+)]
 #[put("/<id>", data = "<user_json>")]
 #[instrument(name = "user_controller/update", skip_all, fields(id = %id))]
 async fn update(
     app: &AppState,
+    _auth: Authorized<UsersWrite>,
     mut db: ConnectionDb,
     id: i32,
-    user_json: Json<UserName>,
+    user_json: Validated<UserName>,
 ) -> Result<Json<User>, AppError> {
 
     let inner = user_json.into_inner();
@@ -67,9 +125,28 @@ async fn update(
     Ok(Json(user))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/users/{id}",
+    tag = "users",
+    params(("id" = i32, Path, description = "id")),
+    responses(
+        (status = 200, description = "Deleted"),
+        (status = 401, response = Problem),
+        (status = 403, response = Problem),
+        (status = 404, response = Problem),
+        (status = 500, response = Problem),
+    ),
+    security(("session" = []), ("api_key" = []), ("bearer" = []))
+)]
 #[delete("/<id>")]
 #[instrument(name = "user_controller/delete", skip_all, fields(id = %id))]
-async fn delete(app: &AppState, mut db: ConnectionDb, id: i32) -> Result<(), AppError> {
+async fn delete(
+    app: &AppState,
+    _auth: Authorized<UsersWrite>,
+    mut db: ConnectionDb,
+    id: i32,
+) -> Result<(), AppError> {
     app.use_cases.user.delete(&app.repos, &mut db, id).await?;
     Ok(())
 }
@@ -83,12 +160,18 @@ mod tests {
     use crate::app_err;
     use crate::config::Config;
     use crate::db::Db;
+    use crate::error::catchers::catchers;
+    use crate::models::page_model::Page;
     use crate::test::app::create_app_for_test;
+    use crate::test::auth::{
+        authorized_auth_use_case_for_test, session_auth_use_case_for_test, session_cookie_for_test,
+    };
     use crate::test::fixture::user::users_fixture;
     use crate::use_cases::user_use_case::MockUserUseCase;
     use rocket::fairing::AdHoc;
-    use rocket::http::Status;
+    use rocket::http::{ContentType, Status};
     use rocket::local::asynchronous::Client;
+    use rocket::serde::json::Value;
     use rocket_db_pools::Database;
     use std::sync::Arc;
 
@@ -96,8 +179,14 @@ mod tests {
     async fn test_index_success() {
         let mut mock_user_use_case = MockUserUseCase::new();
         mock_user_use_case
-            .expect_find_all()
-            .returning(|_, _| Ok(users_fixture(5)));
+            .expect_find_page()
+            .returning(|_, _, _, _| {
+                Ok(Page {
+                    items: users_fixture(5),
+                    next_cursor: None,
+                    total: None,
+                })
+            });
 
         let mut app_state = create_app_for_test();
         app_state.use_cases.user = Box::new(mock_user_use_case);
@@ -119,8 +208,8 @@ mod tests {
     async fn test_index_fail() {
         let mut mock_user_use_case = MockUserUseCase::new();
         mock_user_use_case
-            .expect_find_all()
-            .returning(|_, _| app_err!(500, "error!"));
+            .expect_find_page()
+            .returning(|_, _, _, _| app_err!(500, "error!"));
 
         let mut app_state = create_app_for_test();
         app_state.use_cases.user = Box::new(mock_user_use_case);
@@ -136,7 +225,101 @@ mod tests {
         let response = client.get("/").dispatch().await;
 
         assert_eq!(response.status(), Status::InternalServerError);
-        let body_str = response.into_string().await.expect("valid body string");
-        assert_eq!(body_str, "error!");
+        assert_eq!(
+            response.content_type(),
+            Some(ContentType::new("application", "problem+json"))
+        );
+        let body: Value = response.into_json().await.expect("valid problem json");
+        assert_eq!(body["status"], 500);
+        assert_eq!(body["detail"], "error!");
+        assert_eq!(body["code"], "INTERNAL_SERVER_ERROR");
+        assert_eq!(body["instance"], "/");
+    }
+
+    #[rocket::async_test]
+    async fn test_add_invalid_age() {
+        let mut mock_user_use_case = MockUserUseCase::new();
+        mock_user_use_case.expect_create().never();
+
+        let mut app_state = create_app_for_test();
+        app_state.use_cases.user = Box::new(mock_user_use_case);
+        app_state.use_cases.auth = Box::new(authorized_auth_use_case_for_test());
+
+        let rocket = rocket::build()
+            .manage(Arc::new(app_state))
+            .attach(Db::init())
+            .attach(AdHoc::config::<Config>())
+            .register("/", catchers())
+            .mount("/", routes![super::add]);
+        let client = Client::tracked(rocket)
+            .await
+            .expect("valid rocket instance");
+        let response = client
+            .post("/add")
+            .private_cookie(session_cookie_for_test(1))
+            .header(ContentType::JSON)
+            .body(r#"{"name":"taro","age":33}"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+        let body: Value = response.into_json().await.expect("valid problem json");
+        assert_eq!(body["errors"]["age"][0], "must be between 0 and 32");
+    }
+
+    #[rocket::async_test]
+    async fn test_index_unknown_query_field() {
+        let mut mock_user_use_case = MockUserUseCase::new();
+        mock_user_use_case.expect_find_page().never();
+
+        let mut app_state = create_app_for_test();
+        app_state.use_cases.user = Box::new(mock_user_use_case);
+
+        let rocket = rocket::build()
+            .manage(Arc::new(app_state))
+            .attach(Db::init())
+            .attach(AdHoc::config::<Config>())
+            .mount("/", routes![super::index]);
+        let client = Client::tracked(rocket)
+            .await
+            .expect("valid rocket instance");
+        let response = client.get("/?age_gte=18&password=x").dispatch().await;
+
+        assert_eq!(response.status(), Status::BadRequest);
+        let body: Value = response.into_json().await.expect("valid problem json");
+        assert_eq!(body["detail"], "invalid query: password: unexpected");
+    }
+
+    #[rocket::async_test]
+    async fn test_delete_forbidden_for_viewer() {
+        let mut mock_user_use_case = MockUserUseCase::new();
+        mock_user_use_case.expect_delete().never();
+        let mut mock_auth_use_case = session_auth_use_case_for_test();
+        mock_auth_use_case
+            .expect_permissions()
+            .returning(|_, _, _| Ok(vec![]));
+
+        let mut app_state = create_app_for_test();
+        app_state.use_cases.user = Box::new(mock_user_use_case);
+        app_state.use_cases.auth = Box::new(mock_auth_use_case);
+
+        let rocket = rocket::build()
+            .manage(Arc::new(app_state))
+            .attach(Db::init())
+            .attach(AdHoc::config::<Config>())
+            .register("/", catchers())
+            .mount("/", routes![super::delete]);
+        let client = Client::tracked(rocket)
+            .await
+            .expect("valid rocket instance");
+        let response = client
+            .delete("/7")
+            .private_cookie(session_cookie_for_test(1))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Forbidden);
+        let body: Value = response.into_json().await.expect("valid problem json");
+        assert_eq!(body["detail"], "Permission 'users:write' is required");
     }
 }
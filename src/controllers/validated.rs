@@ -0,0 +1,116 @@
+use crate::controllers::json_body::JsonBody;
+use rocket::data::{self, Data, FromData};
+use rocket::http::Status;
+use rocket::outcome::Outcome;
+use rocket::serde::json;
+use rocket::Request;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Deref;
+use validator::{Validate, ValidationErrors, ValidationErrorsKind};
+
+// JSONボディをパースした後にValidateを実行するデータガード。失敗時は422になる
+#[derive(Debug)]
+pub struct Validated<T>(pub T);
+
+impl<T> Validated<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Validated<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+#[derive(Debug)]
+pub enum ValidatedError<'r> {
+    Json(json::Error<'r>),
+    Invalid(ValidationErrors),
+}
+
+impl<'r> fmt::Display for ValidatedError<'r> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidatedError::Json(e) => write!(f, "{}", e),
+            ValidatedError::Invalid(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+// フィールド名ごとのエラーメッセージ
+#[derive(Debug, Clone, Default)]
+pub struct FieldErrors(pub BTreeMap<String, Vec<String>>);
+
+impl FieldErrors {
+    // catcherから呼ばれ、同じリクエストで保存されたバリデーションエラーを返す
+    pub fn from_request(req: &Request<'_>) -> Option<FieldErrors> {
+        req.local_cache(|| None::<FieldErrors>).clone()
+    }
+}
+
+impl From<&ValidationErrors> for FieldErrors {
+    fn from(errors: &ValidationErrors) -> Self {
+        let mut fields = BTreeMap::new();
+        collect_field_errors(errors, "", &mut fields);
+        FieldErrors(fields)
+    }
+}
+
+// ネストした構造体や配列の要素のエラーは "items[0].quantity" のようなキーにする
+fn collect_field_errors(
+    errors: &ValidationErrors,
+    prefix: &str,
+    fields: &mut BTreeMap<String, Vec<String>>,
+) {
+    for (field, kind) in errors.errors() {
+        let path = format!("{}{}", prefix, field);
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                let messages = errors
+                    .iter()
+                    .map(|e| match &e.message {
+                        Some(message) => message.to_string(),
+                        None => e.code.to_string(),
+                    })
+                    .collect();
+                fields.insert(path, messages);
+            }
+            ValidationErrorsKind::Struct(errors) => {
+                collect_field_errors(errors, &format!("{}.", path), fields)
+            }
+            ValidationErrorsKind::List(items) => {
+                for (index, errors) in items {
+                    collect_field_errors(errors, &format!("{}[{}].", path, index), fields);
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<'r, T: Deserialize<'r> + Validate> FromData<'r> for Validated<T> {
+    type Error = ValidatedError<'r>;
+
+    async fn from_data(req: &'r Request<'_>, data: Data<'r>) -> data::Outcome<'r, Self> {
+        let value = match JsonBody::<T>::from_data(req, data).await {
+            Outcome::Success(value) => value.into_inner(),
+            Outcome::Failure((status, e)) => {
+                return Outcome::Failure((status, ValidatedError::Json(e)))
+            }
+            Outcome::Forward(data) => return Outcome::Forward(data),
+        };
+        match value.validate() {
+            Ok(()) => Outcome::Success(Validated(value)),
+            Err(errors) => {
+                req.local_cache(|| Some(FieldErrors::from(&errors)));
+                Outcome::Failure((Status::UnprocessableEntity, ValidatedError::Invalid(errors)))
+            }
+        }
+    }
+}
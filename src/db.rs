@@ -1,12 +1,50 @@
+use crate::metrics::metrics::Metrics;
+use rocket::request::{FromRequest, Outcome};
+use rocket::{Ignite, Request, Rocket, Sentinel};
 use rocket_db_pools::sqlx;
 use rocket_db_pools::Connection;
 use rocket_db_pools::Database;
-use sqlx::{pool::PoolConnection, Postgres};
+use sqlx::PgConnection;
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
 
-// pub type DbCon= Connection<Db>;
-pub type ConnectionDb = Connection<Db>;
-pub type DbCon = PoolConnection<Postgres>;
+// Connection<Db>と同じように使えるリクエストガード
+// コネクションを待っている間はdb_pool_waitersに数える(sqlxのプールは待ち数を公開していないため)
+pub struct ConnectionDb(Connection<Db>);
+// ユースケースが受け取るコネクション。プールのコネクションでもトランザクションでも渡せる
+pub type DbCon = PgConnection;
 
 #[derive(Database)]
 #[database("hoge")]
 pub struct Db(sqlx::PgPool);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ConnectionDb {
+    type Error = <Connection<Db> as FromRequest<'r>>::Error;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let metrics = req.rocket().state::<Arc<Metrics>>();
+        let _waiting = metrics.map(|metrics| metrics.wait_for_connection());
+        Connection::<Db>::from_request(req).await.map(ConnectionDb)
+    }
+}
+
+impl Sentinel for ConnectionDb {
+    fn abort(rocket: &Rocket<Ignite>) -> bool {
+        Connection::<Db>::abort(rocket)
+    }
+}
+
+impl Deref for ConnectionDb {
+    type Target = Connection<Db>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for ConnectionDb {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
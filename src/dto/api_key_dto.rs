@@ -0,0 +1,38 @@
+use crate::auth::authorized::API_KEY_SCOPES;
+use crate::dto::validators::not_blank;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use utoipa::ToSchema;
+use validator::{Validate, ValidationError};
+
+#[derive(Debug, Deserialize, Serialize, Validate, ToSchema, Clone)]
+pub struct ApiKeyForm {
+    #[validate(
+        custom = "not_blank",
+        length(max = 100, message = "must be at most 100 characters")
+    )]
+    #[schema(max_length = 100)]
+    pub name: String,
+    #[validate(
+        length(min = 1, message = "must not be empty"),
+        custom = "known_scopes"
+    )]
+    #[schema(example = json!(["products:write"]))]
+    pub scopes: Vec<String>,
+}
+
+fn known_scopes(scopes: &[String]) -> Result<(), ValidationError> {
+    if let Some(scope) = scopes
+        .iter()
+        .find(|scope| !API_KEY_SCOPES.contains(&scope.as_str()))
+    {
+        let mut error = ValidationError::new("unknown_scope");
+        error.message = Some(Cow::from(format!(
+            "unknown scope {}, expected one of {}",
+            scope,
+            API_KEY_SCOPES.join(", ")
+        )));
+        return Err(error);
+    }
+    Ok(())
+}
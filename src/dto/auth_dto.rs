@@ -0,0 +1,41 @@
+use crate::dto::validators::not_blank;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+// パスワードを含むため、ログに出ないようDebugは実装しない
+#[derive(Deserialize, Serialize, Validate, ToSchema, Clone)]
+pub struct RegisterForm {
+    #[validate(
+        custom = "not_blank",
+        length(max = 100, message = "must be at most 100 characters")
+    )]
+    #[schema(max_length = 100)]
+    pub name: String,
+    #[validate(range(min = 0, max = 32, message = "must be between 0 and 32"))]
+    #[schema(minimum = 0, maximum = 32)]
+    pub age: i32,
+    #[validate(email(message = "must be a valid email address"))]
+    #[schema(example = "taro@example.com")]
+    pub email: String,
+    #[validate(length(min = 8, max = 128, message = "must be between 8 and 128 characters"))]
+    #[schema(min_length = 8, max_length = 128)]
+    pub password: String,
+}
+
+#[derive(Deserialize, Serialize, Validate, ToSchema, Clone)]
+pub struct LoginForm {
+    #[validate(custom = "not_blank")]
+    pub email: String,
+    #[validate(custom = "not_blank")]
+    pub password: String,
+}
+
+#[derive(Deserialize, Serialize, Validate, ToSchema, Clone)]
+pub struct ChangePasswordForm {
+    #[validate(custom = "not_blank")]
+    pub current_password: String,
+    #[validate(length(min = 8, max = 128, message = "must be between 8 and 128 characters"))]
+    #[schema(min_length = 8, max_length = 128)]
+    pub new_password: String,
+}
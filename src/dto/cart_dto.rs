@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+// POST /carts/items の入力。既にカートにある商品なら数量を足す
+#[derive(Deserialize, Serialize, Validate, ToSchema, Debug, Clone, PartialEq, Eq)]
+pub struct CartItemForm {
+    pub product_id: i32,
+    #[validate(range(min = 1, max = 1000, message = "must be between 1 and 1000"))]
+    #[schema(minimum = 1, maximum = 1000)]
+    pub quantity: i32,
+}
+
+// PUT /carts/items/<product_id> の入力
+#[derive(Deserialize, Serialize, Validate, ToSchema, Debug, Clone, PartialEq, Eq)]
+pub struct CartQuantityForm {
+    #[validate(range(min = 1, max = 1000, message = "must be between 1 and 1000"))]
+    #[schema(minimum = 1, maximum = 1000)]
+    pub quantity: i32,
+}
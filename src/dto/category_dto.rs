@@ -0,0 +1,17 @@
+use crate::dto::validators::not_blank;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+// POST /categories の入力
+#[derive(Deserialize, Serialize, Validate, ToSchema, Debug, Clone, PartialEq, Eq)]
+pub struct CategoryInput {
+    #[validate(
+        custom = "not_blank",
+        length(max = 255, message = "must be at most 255 characters")
+    )]
+    #[schema(max_length = 255)]
+    pub name: String,
+    // 省略すると最上位のカテゴリになる
+    pub parent_id: Option<i32>,
+}
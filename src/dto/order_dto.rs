@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+// POST /orders の入力。同じ商品が複数回あれば数量を合計する
+#[derive(Deserialize, Serialize, Validate, ToSchema, Debug, Clone)]
+pub struct OrderForm {
+    #[validate(length(min = 1, max = 100, message = "must have 1 to 100 items"))]
+    #[validate]
+    #[schema(min_items = 1, max_items = 100)]
+    pub items: Vec<OrderItemInput>,
+}
+
+#[derive(Deserialize, Serialize, Validate, ToSchema, Debug, Clone, PartialEq, Eq)]
+pub struct OrderItemInput {
+    pub product_id: i32,
+    #[validate(range(min = 1, max = 1000, message = "must be between 1 and 1000"))]
+    #[schema(minimum = 1, maximum = 1000)]
+    pub quantity: i32,
+}
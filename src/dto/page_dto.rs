@@ -0,0 +1,243 @@
+use crate::app_err_bail;
+use crate::error::app_error::AppError;
+use crate::repositories::pagination::{ColumnType, Cursor, PageRequest, SortField, MAX_LIMIT};
+use rocket::form::{self, DataField, Errors, FromForm, Options, ValueField};
+use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
+
+// ?limit=20&offset=0&cursor=...&sort=name,-id&with_total=true
+#[derive(Deserialize, Serialize, FromForm, IntoParams, Debug, Default, Clone)]
+#[into_params(parameter_in = Query)]
+pub struct PageQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    pub cursor: Option<String>,
+    pub sort: Option<String>,
+    pub with_total: Option<bool>,
+}
+
+impl PageQuery {
+    pub const FIELDS: &'static [&'static str] =
+        &["limit", "offset", "cursor", "sort", "with_total"];
+
+    // sortableに含まれるカラムだけをソートに使えるようにする
+    pub fn to_page_request(
+        &self,
+        sortable: &[(&'static str, ColumnType)],
+    ) -> Result<PageRequest, AppError> {
+        let mut page = PageRequest::default();
+
+        if let Some(limit) = self.limit {
+            if !(1..=MAX_LIMIT).contains(&limit) {
+                app_err_bail!(400, &format!("limit must be between 1 and {}", MAX_LIMIT));
+            }
+            page.limit = limit;
+        }
+        if let Some(offset) = self.offset {
+            if offset < 0 {
+                app_err_bail!(400, "offset must not be negative");
+            }
+            page.offset = offset;
+        }
+        if let Some(sort) = &self.sort {
+            page.sort = parse_sort(sort, sortable)?;
+        }
+        page.with_total = self.with_total.unwrap_or(false);
+
+        if let Some(cursor) = &self.cursor {
+            if self.offset.is_some() {
+                app_err_bail!(400, "cursor and offset cannot be used together");
+            }
+            let cursor =
+                Cursor::decode(cursor).ok_or_else(|| AppError::new(400, "cursor is invalid"))?;
+            if cursor.sort != page.sort_key() || cursor.values.len() != page.sort.len() {
+                app_err_bail!(400, "cursor does not match sort");
+            }
+            // 書き換えられたカーソルの値をそのままSQLに渡すと、型の不一致でDBエラー(500)になる
+            let types_match = page.sort.iter().zip(&cursor.values).all(|(field, value)| {
+                sortable.iter().any(|(column, column_type)| {
+                    *column == field.column && *column_type == value.column_type()
+                })
+            });
+            if !types_match {
+                app_err_bail!(400, "cursor is invalid");
+            }
+            page.cursor = Some(cursor);
+        }
+        Ok(page)
+    }
+}
+
+// 一覧取得のクエリ。ページングのパラメータはPageQueryへ、それ以外は絞り込み条件Fへ渡す
+// Strict<ListQuery<F>> で受け取ると、どちらにも無いパラメータはエラーになる
+#[derive(Debug, Default, Clone)]
+pub struct ListQuery<F> {
+    pub page: PageQuery,
+    pub filter: F,
+}
+
+#[rocket::async_trait]
+impl<'v, F: FromForm<'v>> FromForm<'v> for ListQuery<F> {
+    type Context = (<PageQuery as FromForm<'v>>::Context, F::Context);
+
+    fn init(opts: Options) -> Self::Context {
+        (PageQuery::init(opts), F::init(opts))
+    }
+
+    fn push_value(ctxt: &mut Self::Context, field: ValueField<'v>) {
+        if PageQuery::FIELDS.contains(&field.name.key_lossy().as_str()) {
+            PageQuery::push_value(&mut ctxt.0, field);
+        } else {
+            F::push_value(&mut ctxt.1, field);
+        }
+    }
+
+    async fn push_data(ctxt: &mut Self::Context, field: DataField<'v, '_>) {
+        if PageQuery::FIELDS.contains(&field.name.key_lossy().as_str()) {
+            PageQuery::push_data(&mut ctxt.0, field).await;
+        } else {
+            F::push_data(&mut ctxt.1, field).await;
+        }
+    }
+
+    fn finalize(ctxt: Self::Context) -> form::Result<'v, Self> {
+        match (PageQuery::finalize(ctxt.0), F::finalize(ctxt.1)) {
+            (Ok(page), Ok(filter)) => Ok(ListQuery { page, filter }),
+            (Err(mut errors), Err(filter_errors)) => {
+                errors.extend(filter_errors);
+                Err(errors)
+            }
+            (Err(errors), _) | (_, Err(errors)) => Err(errors),
+        }
+    }
+}
+
+// クエリ文字列のパースエラーを400のAppErrorに変換する
+pub fn query_error(errors: Errors<'_>) -> AppError {
+    let message = errors
+        .iter()
+        .map(|e| match &e.name {
+            Some(name) => format!("{}: {}", name, e.kind),
+            None => e.kind.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    AppError::new(400, &format!("invalid query: {}", message))
+}
+
+// 並び順を一意にするため、idが含まれていなければ最後に追加する
+fn parse_sort(
+    sort: &str,
+    sortable: &[(&'static str, ColumnType)],
+) -> Result<Vec<SortField>, AppError> {
+    let mut fields: Vec<SortField> = vec![];
+    for field in sort.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+        let (name, descending) = match field.strip_prefix('-') {
+            Some(name) => (name, true),
+            None => (field, false),
+        };
+        let (column, _) = *sortable
+            .iter()
+            .find(|(c, _)| *c == name)
+            .ok_or_else(|| AppError::new(400, &format!("cannot sort by '{}'", name)))?;
+        if fields.iter().any(|f| f.column == column) {
+            app_err_bail!(400, &format!("'{}' appears more than once in sort", name));
+        }
+        fields.push(SortField { column, descending });
+    }
+    if !fields.iter().any(|f| f.column == "id") {
+        fields.push(SortField {
+            column: "id",
+            descending: false,
+        });
+    }
+    Ok(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repositories::pagination::CursorValue;
+
+    const SORTABLE: &[(&str, ColumnType)] = &[("id", ColumnType::Int), ("name", ColumnType::Text)];
+
+    #[test]
+    fn test_to_page_request_sort() {
+        let query = PageQuery {
+            sort: Some("-name".to_string()),
+            ..Default::default()
+        };
+        let page = query.to_page_request(SORTABLE).unwrap();
+        assert_eq!(page.sort_key(), "-name,id");
+    }
+
+    #[test]
+    fn test_to_page_request_rejects_unknown_sort() {
+        let query = PageQuery {
+            sort: Some("password".to_string()),
+            ..Default::default()
+        };
+        let result = query.to_page_request(SORTABLE);
+        assert_eq!(result.unwrap_err().status_code(), 400);
+    }
+
+    #[test]
+    fn test_to_page_request_rejects_cursor_for_other_sort() {
+        let cursor = Cursor {
+            sort: "id".to_string(),
+            values: vec![],
+        };
+        let query = PageQuery {
+            cursor: Some(cursor.encode()),
+            sort: Some("name".to_string()),
+            ..Default::default()
+        };
+        let result = query.to_page_request(SORTABLE);
+        assert_eq!(result.unwrap_err().status_code(), 400);
+    }
+
+    #[test]
+    fn test_to_page_request_rejects_cursor_value_of_wrong_type() {
+        let cursor = Cursor {
+            sort: "name,id".to_string(),
+            values: vec![
+                CursorValue::Text("taro".to_string()),
+                CursorValue::Text("1 OR 1=1".to_string()),
+            ],
+        };
+        let query = PageQuery {
+            cursor: Some(cursor.encode()),
+            sort: Some("name".to_string()),
+            ..Default::default()
+        };
+        let result = query.to_page_request(SORTABLE);
+        assert_eq!(result.unwrap_err().status_code(), 400);
+
+        let cursor = Cursor {
+            sort: "name,id".to_string(),
+            values: vec![CursorValue::Text("taro".to_string()), CursorValue::Int(1)],
+        };
+        let query = PageQuery {
+            cursor: Some(cursor.encode()),
+            sort: Some("name".to_string()),
+            ..Default::default()
+        };
+        assert!(query.to_page_request(SORTABLE).unwrap().cursor.is_some());
+    }
+
+    #[test]
+    fn test_list_query_rejects_unknown_field() {
+        use crate::dto::user_dto::UserFilterQuery;
+        use rocket::form::{Form, Strict};
+
+        let query =
+            Form::<Strict<ListQuery<UserFilterQuery>>>::parse("limit=5&age_gte=18").unwrap();
+        assert_eq!(query.page.limit, Some(5));
+        assert_eq!(query.filter.age_gte, Some(18));
+
+        let errors = Form::<Strict<ListQuery<UserFilterQuery>>>::parse("age_gte=18&password=x");
+        let error = query_error(errors.unwrap_err());
+        assert_eq!(error.status_code(), 400);
+        assert_eq!(error.to_string(), "invalid query: password: unexpected");
+    }
+}
@@ -1,6 +1,72 @@
+use crate::dto::validators::{currency_code, not_blank, sku, tags};
+use crate::models::product_model::ProductFilter;
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
 
-#[derive(Deserialize, Serialize, FromForm, Debug)]
-pub struct ProductName {
+// POST /products, PUT /products/<id> の入力
+#[derive(Deserialize, Serialize, FromForm, Validate, ToSchema, Debug, Clone)]
+pub struct ProductInput {
+    #[validate(
+        custom = "not_blank",
+        length(max = 255, message = "must be at most 255 characters")
+    )]
+    #[schema(max_length = 255)]
     pub name: String,
+    #[validate(
+        custom = "sku",
+        length(max = 64, message = "must be at most 64 characters")
+    )]
+    #[schema(max_length = 64, example = "APPLE-001")]
+    pub sku: String,
+    // 通貨の最小単位 (JPYなら円、USDならセント)
+    #[validate(range(min = 0, message = "must not be negative"))]
+    #[schema(minimum = 0)]
+    pub price: i64,
+    #[validate(custom = "currency_code")]
+    #[schema(min_length = 3, max_length = 3, example = "JPY")]
+    pub currency: String,
+    #[validate(range(min = 0, message = "must not be negative"))]
+    #[schema(minimum = 0)]
+    pub stock: i32,
+}
+
+// GET /products の絞り込み条件 (?name_like=app&category_id=1&tag=red)
+#[derive(Deserialize, Serialize, FromForm, IntoParams, Debug, Default, Clone, PartialEq)]
+#[into_params(parameter_in = Query)]
+pub struct ProductFilterQuery {
+    // 名前の部分一致
+    pub name_like: Option<String>,
+    // このカテゴリかその子孫のカテゴリに属する商品
+    pub category_id: Option<i32>,
+    // このタグが付いた商品 (大文字小文字は区別しない)
+    pub tag: Option<String>,
+}
+
+impl From<ProductFilterQuery> for ProductFilter {
+    fn from(query: ProductFilterQuery) -> Self {
+        Self {
+            name_like: query.name_like,
+            category_id: query.category_id,
+            tag: query.tag,
+        }
+    }
+}
+
+// PUT /products/<id>/categories の入力。商品のカテゴリをこの一覧で置き換える
+#[derive(Deserialize, Serialize, Validate, ToSchema, Debug, Clone, PartialEq, Eq)]
+pub struct ProductCategoriesForm {
+    #[validate(length(max = 20, message = "must have at most 20 categories"))]
+    pub category_ids: Vec<i32>,
+}
+
+// PUT /products/<id>/tags の入力。商品のタグをこの一覧で置き換える
+#[derive(Deserialize, Serialize, Validate, ToSchema, Debug, Clone, PartialEq, Eq)]
+pub struct ProductTagsForm {
+    #[validate(
+        custom = "tags",
+        length(max = 20, message = "must have at most 20 tags")
+    )]
+    #[schema(example = json!(["red", "organic"]))]
+    pub tags: Vec<String>,
 }
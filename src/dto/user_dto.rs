@@ -1,7 +1,44 @@
+use crate::dto::validators::not_blank;
+use crate::models::user_model::UserFilter;
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
 
-#[derive(Deserialize, Serialize, FromForm, Debug, Clone)]
+#[derive(Deserialize, Serialize, FromForm, Validate, ToSchema, Debug, Clone)]
 pub struct UserName {
+    #[validate(
+        custom = "not_blank",
+        length(max = 100, message = "must be at most 100 characters")
+    )]
+    #[schema(max_length = 100)]
     pub name: String,
+    #[validate(range(min = 0, max = 32, message = "must be between 0 and 32"))]
+    #[schema(minimum = 0, maximum = 32)]
     pub age: i32,
 }
+
+// GET /users の絞り込み条件 (?name_like=ta&age_gte=18&age_lt=30&age_is_null=false)
+#[derive(Deserialize, Serialize, FromForm, IntoParams, Debug, Default, Clone, PartialEq)]
+#[into_params(parameter_in = Query)]
+pub struct UserFilterQuery {
+    // 名前の前方一致
+    pub name_like: Option<String>,
+    pub age_gt: Option<i32>,
+    pub age_gte: Option<i32>,
+    pub age_lt: Option<i32>,
+    pub age_lte: Option<i32>,
+    pub age_is_null: Option<bool>,
+}
+
+impl From<UserFilterQuery> for UserFilter {
+    fn from(query: UserFilterQuery) -> Self {
+        Self {
+            name_like: query.name_like,
+            age_gt: query.age_gt,
+            age_gte: query.age_gte,
+            age_lt: query.age_lt,
+            age_lte: query.age_lte,
+            age_is_null: query.age_is_null,
+        }
+    }
+}
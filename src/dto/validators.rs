@@ -0,0 +1,48 @@
+use std::borrow::Cow;
+use validator::ValidationError;
+
+// 空白だけの文字列を拒否する (length(min = 1) では " " が通ってしまうため)
+pub fn not_blank(value: &str) -> Result<(), ValidationError> {
+    if value.trim().is_empty() {
+        let mut error = ValidationError::new("not_blank");
+        error.message = Some(Cow::from("must not be blank"));
+        return Err(error);
+    }
+    Ok(())
+}
+
+// ISO 4217の通貨コード (JPY, USDなど英大文字3文字)
+pub fn currency_code(value: &str) -> Result<(), ValidationError> {
+    if value.len() != 3 || !value.bytes().all(|b| b.is_ascii_uppercase()) {
+        let mut error = ValidationError::new("currency_code");
+        error.message = Some(Cow::from("must be an ISO 4217 currency code"));
+        return Err(error);
+    }
+    Ok(())
+}
+
+// SKUは英数字と-_のみ (URLやCSVでそのまま使えるように)
+pub fn sku(value: &str) -> Result<(), ValidationError> {
+    let valid = value
+        .bytes()
+        .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_');
+    if value.is_empty() || !valid {
+        let mut error = ValidationError::new("sku");
+        error.message = Some(Cow::from("must consist of letters, digits, '-' and '_'"));
+        return Err(error);
+    }
+    Ok(())
+}
+
+// タグは空白だけでなく、50文字以内
+pub fn tags(values: &[String]) -> Result<(), ValidationError> {
+    let valid = values
+        .iter()
+        .all(|tag| !tag.trim().is_empty() && tag.trim().chars().count() <= 50);
+    if !valid {
+        let mut error = ValidationError::new("tags");
+        error.message = Some(Cow::from("each tag must be 1 to 50 characters"));
+        return Err(error);
+    }
+    Ok(())
+}
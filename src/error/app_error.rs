@@ -1,13 +1,16 @@
+use crate::error::problem::{ErrorCode, Problem};
+use crate::metrics::metrics::Metrics;
 use crate::repositories::error::DbRepoError;
 use rocket::http::Status;
-use rocket::response::{Responder, Response};
+use rocket::response::Responder;
 use rocket::Request;
+use std::sync::Arc;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum AppError {
     #[error("Database Error")]
-    DbError(#[from] DbRepoError),
+    DbError(DbRepoError),
     #[error("Bad Request")]
     BadRequest,
     #[error("Unauthorized")]
@@ -16,6 +19,10 @@ pub enum AppError {
     Forbidden,
     #[error("Not Found")]
     NotFound,
+    #[error("Conflict")]
+    Conflict,
+    #[error("product {product_id} has only {available} in stock")]
+    OutOfStock { product_id: i32, available: i32 },
     #[error("Internal Server Error")]
     InternalServerError,
     #[error("{message}")]
@@ -37,20 +44,70 @@ impl AppError {
             AppError::Unauthorized => 401,
             AppError::Forbidden => 403,
             AppError::NotFound => 404,
+            AppError::Conflict => 409,
+            AppError::OutOfStock { .. } => 409,
             AppError::InternalServerError => 500,
             AppError::CustomError { status_code, .. } => *status_code,
         }
     }
+
+    // メトリクスのラベルに使うバリアント名
+    pub fn variant(&self) -> &'static str {
+        match self {
+            AppError::DbError(_) => "DbError",
+            AppError::BadRequest => "BadRequest",
+            AppError::Unauthorized => "Unauthorized",
+            AppError::Forbidden => "Forbidden",
+            AppError::NotFound => "NotFound",
+            AppError::Conflict => "Conflict",
+            AppError::OutOfStock { .. } => "OutOfStock",
+            AppError::InternalServerError => "InternalServerError",
+            AppError::CustomError { .. } => "CustomError",
+        }
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            AppError::DbError(_) => ErrorCode::DatabaseError,
+            _ => ErrorCode::from_status(self.status_code()),
+        }
+    }
+
+    pub fn to_problem(&self) -> Problem {
+        let status = Status::from_code(self.status_code()).unwrap_or(Status::InternalServerError);
+        let problem = Problem::new(status, self.code(), &self.to_string());
+        match self {
+            // クライアントが数量を直せるように、在庫数を拡張メンバーで返す
+            AppError::OutOfStock {
+                product_id,
+                available,
+            } => problem
+                .with_extension("product_id", product_id)
+                .with_extension("available", available),
+            _ => problem,
+        }
+    }
+}
+
+// 一意制約違反はリクエストの内容による競合なので、500ではなく409にする
+impl From<DbRepoError> for AppError {
+    fn from(e: DbRepoError) -> Self {
+        if e.is_unique_violation() {
+            AppError::Conflict
+        } else {
+            AppError::DbError(e)
+        }
+    }
 }
 
 impl<'r> Responder<'r, 'static> for AppError {
     fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
-        let status_code = self.status_code();
-        let status = Status::from_code(status_code).unwrap_or(Status::InternalServerError);
-        let error_message = self.to_string();
-        Response::build_from(error_message.respond_to(req)?)
-            .status(status)
-            .ok()
+        if let Some(metrics) = req.rocket().state::<Arc<Metrics>>() {
+            metrics.app_errors.with_label_values(&[self.variant()]).inc();
+        }
+        self.to_problem()
+            .with_instance(req.uri().path().as_str())
+            .respond_to(req)
     }
 }
 
@@ -107,3 +164,56 @@ macro_rules! app_err_ensure {
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_problem_custom_error() {
+        let problem = AppError::new(400, "age must be less than 32").to_problem();
+        assert_eq!(problem.status, 400);
+        assert_eq!(problem.title, "Bad Request");
+        assert_eq!(problem.detail, "age must be less than 32");
+        assert_eq!(problem.code, ErrorCode::BadRequest);
+    }
+
+    #[test]
+    fn test_to_problem_db_error() {
+        let problem = AppError::from(DbRepoError::DummyTestError).to_problem();
+        assert_eq!(problem.status, 500);
+        assert_eq!(problem.detail, "Database Error");
+        assert_eq!(problem.code, ErrorCode::DatabaseError);
+    }
+
+    #[test]
+    fn test_to_problem_out_of_stock() {
+        let problem = AppError::OutOfStock {
+            product_id: 3,
+            available: 1,
+        }
+        .to_problem();
+        assert_eq!(problem.status, 409);
+        assert_eq!(problem.code, ErrorCode::Conflict);
+        assert_eq!(problem.detail, "product 3 has only 1 in stock");
+        assert_eq!(problem.extensions["product_id"], 3);
+        assert_eq!(problem.extensions["available"], 1);
+    }
+
+    #[rocket::async_test]
+    async fn test_unique_violation_is_conflict() {
+        let mut tx = crate::test::db::create_tx_for_test().await.unwrap();
+        sqlx::query("CREATE TEMPORARY TABLE unique_test (id INTEGER UNIQUE)")
+            .execute(&mut tx)
+            .await
+            .unwrap();
+        let result = sqlx::query("INSERT INTO unique_test VALUES (1), (1)")
+            .execute(&mut tx)
+            .await;
+        let e = AppError::from(DbRepoError::from(result.unwrap_err()));
+        assert!(matches!(e, AppError::Conflict));
+        assert_eq!(e.to_problem().status, 409);
+        assert_eq!(e.code(), ErrorCode::Conflict);
+        tx.rollback().await.unwrap();
+    }
+}
@@ -0,0 +1,270 @@
+use crate::controllers::json_body::JsonBodyError;
+use crate::controllers::validated::FieldErrors;
+use crate::error::problem::{ErrorCode, Problem};
+use rocket::http::Status;
+use rocket::response::{self, Responder};
+use rocket::{Catcher, Request};
+
+// リクエストガードが失敗した理由。ガードのエラーはレスポンスに使われないので、catcherで読み出す
+// challengeがあれば401のレスポンスにWWW-Authenticateヘッダとして付ける
+#[derive(Debug, Clone)]
+pub struct GuardRejection {
+    pub detail: String,
+    pub challenge: Option<String>,
+}
+
+impl GuardRejection {
+    pub fn reject(req: &Request<'_>, detail: &str) {
+        req.local_cache(|| {
+            Some(GuardRejection {
+                detail: detail.to_string(),
+                challenge: None,
+            })
+        });
+    }
+
+    pub fn reject_with_challenge(req: &Request<'_>, detail: &str, challenge: &str) {
+        req.local_cache(|| {
+            Some(GuardRejection {
+                detail: detail.to_string(),
+                challenge: Some(challenge.to_string()),
+            })
+        });
+    }
+
+    pub fn from_request(req: &Request<'_>) -> Option<GuardRejection> {
+        req.local_cache(|| None::<GuardRejection>).clone()
+    }
+}
+
+// WWW-Authenticateヘッダ付きのproblem+json
+pub struct Challenge {
+    problem: Problem,
+    challenge: Option<String>,
+}
+
+impl<'r> Responder<'r, 'static> for Challenge {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        let mut response = self.problem.respond_to(req)?;
+        if let Some(challenge) = self.challenge {
+            response.set_raw_header("WWW-Authenticate", challenge);
+        }
+        Ok(response)
+    }
+}
+
+// AppErrorと同じproblem+json形式でRocketのエラーを返す
+fn problem(status: Status, req: &Request<'_>, detail: &str) -> Problem {
+    Problem::new(status, ErrorCode::from_status(status.code), detail)
+        .with_instance(req.uri().path().as_str())
+}
+
+// JSONのパースエラーがあれば、その内容と位置をレスポンスに含める
+fn json_problem(status: Status, req: &Request<'_>, fallback: &str) -> Problem {
+    match JsonBodyError::from_request(req) {
+        Some(error) => problem(
+            status,
+            req,
+            &format!("Invalid JSON body: {}", error.message),
+        )
+        .with_extension("line", error.line)
+        .with_extension("column", error.column),
+        None => problem(status, req, fallback),
+    }
+}
+
+#[catch(400)]
+fn bad_request(req: &Request) -> Problem {
+    json_problem(
+        Status::BadRequest,
+        req,
+        "The request could not be understood",
+    )
+}
+
+// ガードが理由を残していればdetailに使う
+fn rejection_problem(status: Status, req: &Request<'_>, fallback: &str) -> Problem {
+    match GuardRejection::from_request(req) {
+        Some(rejection) => problem(status, req, &rejection.detail),
+        None => problem(status, req, fallback),
+    }
+}
+
+#[catch(401)]
+fn unauthorized(req: &Request) -> Challenge {
+    let challenge = GuardRejection::from_request(req).and_then(|rejection| rejection.challenge);
+    Challenge {
+        problem: rejection_problem(Status::Unauthorized, req, "Authentication is required"),
+        challenge,
+    }
+}
+
+#[catch(403)]
+fn forbidden(req: &Request) -> Problem {
+    rejection_problem(
+        Status::Forbidden,
+        req,
+        "You do not have permission to perform this action",
+    )
+}
+
+#[catch(404)]
+fn not_found(req: &Request) -> Problem {
+    let detail = format!("No resource matches {} {}", req.method(), req.uri());
+    problem(Status::NotFound, req, &detail)
+}
+
+#[catch(405)]
+fn method_not_allowed(req: &Request) -> Problem {
+    let detail = format!("Method {} is not allowed for {}", req.method(), req.uri());
+    problem(Status::MethodNotAllowed, req, &detail)
+}
+
+#[catch(415)]
+fn unsupported_media_type(req: &Request) -> Problem {
+    let detail = match req.content_type() {
+        Some(content_type) => format!("Content-Type {} is not supported", content_type),
+        None => "Content-Type is missing".to_string(),
+    };
+    problem(Status::UnsupportedMediaType, req, &detail)
+}
+
+#[catch(422)]
+fn unprocessable_entity(req: &Request) -> Problem {
+    match FieldErrors::from_request(req) {
+        Some(errors) => problem(Status::UnprocessableEntity, req, "Validation failed")
+            .with_extension("errors", errors.0),
+        None => json_problem(
+            Status::UnprocessableEntity,
+            req,
+            "The request body could not be processed",
+        ),
+    }
+}
+
+#[catch(500)]
+fn internal_server_error(req: &Request) -> Problem {
+    problem(Status::InternalServerError, req, "Internal Server Error")
+}
+
+#[catch(default)]
+fn default(status: Status, req: &Request) -> Problem {
+    problem(status, req, status.reason_lossy())
+}
+
+pub fn catchers() -> Vec<Catcher> {
+    catchers![
+        bad_request,
+        unauthorized,
+        forbidden,
+        not_found,
+        method_not_allowed,
+        unsupported_media_type,
+        unprocessable_entity,
+        internal_server_error,
+        default
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::controllers::json_body::JsonBody;
+    use crate::controllers::validated::Validated;
+    use crate::dto::user_dto::UserName;
+    use rocket::http::{ContentType, Status};
+    use rocket::local::asynchronous::Client;
+    use rocket::serde::json::Value;
+
+    #[post("/", data = "<user_json>")]
+    fn add(user_json: JsonBody<UserName>) -> String {
+        user_json.into_inner().name
+    }
+
+    #[put("/", data = "<user_json>")]
+    fn update(user_json: Validated<UserName>) -> String {
+        user_json.into_inner().name
+    }
+
+    #[get("/<id>")]
+    fn show(id: i32) -> String {
+        id.to_string()
+    }
+
+    async fn client() -> Client {
+        let rocket = rocket::build()
+            .register("/", super::catchers())
+            .mount("/", routes![add, update, show]);
+        Client::tracked(rocket)
+            .await
+            .expect("valid rocket instance")
+    }
+
+    #[rocket::async_test]
+    async fn test_not_found_for_non_integer_id() {
+        let client = client().await;
+        let response = client.get("/abc").dispatch().await;
+
+        assert_eq!(response.status(), Status::NotFound);
+        assert_eq!(
+            response.content_type(),
+            Some(ContentType::new("application", "problem+json"))
+        );
+        let body: Value = response.into_json().await.expect("valid problem json");
+        assert_eq!(body["code"], "NOT_FOUND");
+        assert_eq!(body["instance"], "/abc");
+    }
+
+    #[rocket::async_test]
+    async fn test_bad_request_for_malformed_json() {
+        let client = client().await;
+        let response = client
+            .post("/")
+            .header(ContentType::JSON)
+            .body("{\n  \"name\": \"taro\",")
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::BadRequest);
+        let body: Value = response.into_json().await.expect("valid problem json");
+        assert_eq!(body["code"], "BAD_REQUEST");
+        assert_eq!(body["line"], 2);
+        assert!(body["detail"]
+            .as_str()
+            .unwrap()
+            .starts_with("Invalid JSON body"));
+    }
+
+    #[rocket::async_test]
+    async fn test_unprocessable_entity_for_wrong_field_type() {
+        let client = client().await;
+        let response = client
+            .post("/")
+            .header(ContentType::JSON)
+            .body(r#"{"name": "taro", "age": "twenty"}"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+        let body: Value = response.into_json().await.expect("valid problem json");
+        assert_eq!(body["code"], "UNPROCESSABLE_ENTITY");
+        assert_eq!(body["line"], 1);
+        assert!(body["column"].as_u64().is_some());
+    }
+
+    #[rocket::async_test]
+    async fn test_unprocessable_entity_for_invalid_fields() {
+        let client = client().await;
+        let response = client
+            .put("/")
+            .header(ContentType::JSON)
+            .body(r#"{"name": "  ", "age": 40}"#)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+        let body: Value = response.into_json().await.expect("valid problem json");
+        assert_eq!(body["detail"], "Validation failed");
+        assert_eq!(body["errors"]["name"][0], "must not be blank");
+        assert_eq!(body["errors"]["age"][0], "must be between 0 and 32");
+    }
+}
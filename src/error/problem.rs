@@ -0,0 +1,107 @@
+use crate::request_id::RequestId;
+use rocket::http::{ContentType, Status};
+use rocket::response::{Responder, Response};
+use rocket::Request;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::io::Cursor;
+use utoipa::{ToResponse, ToSchema};
+
+// クライアントが機械的に判別できる安定したエラーコード
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    BadRequest,
+    Unauthorized,
+    Forbidden,
+    NotFound,
+    MethodNotAllowed,
+    Conflict,
+    UnsupportedMediaType,
+    UnprocessableEntity,
+    DatabaseError,
+    InternalServerError,
+    ClientError,
+    ServerError,
+}
+
+impl ErrorCode {
+    pub fn from_status(status_code: u16) -> Self {
+        match status_code {
+            400 => ErrorCode::BadRequest,
+            401 => ErrorCode::Unauthorized,
+            403 => ErrorCode::Forbidden,
+            404 => ErrorCode::NotFound,
+            405 => ErrorCode::MethodNotAllowed,
+            409 => ErrorCode::Conflict,
+            415 => ErrorCode::UnsupportedMediaType,
+            422 => ErrorCode::UnprocessableEntity,
+            500 => ErrorCode::InternalServerError,
+            code if (400..500).contains(&code) => ErrorCode::ClientError,
+            _ => ErrorCode::ServerError,
+        }
+    }
+}
+
+// RFC 7807 (application/problem+json) のレスポンスボディ
+#[derive(Debug, Serialize, ToSchema, ToResponse)]
+#[response(
+    description = "RFC 7807 problem details",
+    content_type = "application/problem+json"
+)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    pub type_uri: String,
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    pub code: ErrorCode,
+    #[serde(flatten)]
+    pub extensions: Map<String, Value>,
+}
+
+impl Problem {
+    pub fn new(status: Status, code: ErrorCode, detail: &str) -> Self {
+        Self {
+            type_uri: "about:blank".to_string(),
+            title: status.reason_lossy().to_string(),
+            status: status.code,
+            detail: detail.to_string(),
+            instance: None,
+            code,
+            extensions: Map::new(),
+        }
+    }
+
+    pub fn with_instance(mut self, instance: &str) -> Self {
+        self.instance = Some(instance.to_string());
+        self
+    }
+
+    // RFC 7807 の拡張メンバーを追加する
+    pub fn with_extension<V: Serialize>(mut self, key: &str, value: V) -> Self {
+        if let Ok(value) = serde_json::to_value(value) {
+            self.extensions.insert(key.to_string(), value);
+        }
+        self
+    }
+}
+
+// ログと突き合わせられるように、リクエストIDを拡張メンバーとして返す
+impl<'r> Responder<'r, 'static> for Problem {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let problem = match RequestId::get(req) {
+            Some(id) => self.with_extension("request_id", id),
+            None => self,
+        };
+        let status = Status::from_code(problem.status).unwrap_or(Status::InternalServerError);
+        let body = serde_json::to_string(&problem).map_err(|_| Status::InternalServerError)?;
+        Response::build()
+            .status(status)
+            .header(ContentType::new("application", "problem+json"))
+            .sized_body(body.len(), Cursor::new(body))
+            .ok()
+    }
+}
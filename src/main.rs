@@ -4,43 +4,124 @@ extern crate rocket;
 pub mod app;
 pub mod config;
 pub mod db;
+pub mod migration;
+pub mod request_id;
+
+mod cli {
+    pub mod cli;
+    pub mod config_command;
+    pub mod migrate_command;
+    pub mod product_command;
+    pub mod user_command;
+}
+
+mod metrics {
+    pub mod fairing;
+    pub mod metrics;
+    pub mod span_layer;
+}
+
+mod telemetry {
+    pub mod json;
+    pub mod logging;
+    pub mod otlp;
+    pub mod redact;
+}
+
+mod auth {
+    pub mod api_key;
+    pub mod authenticated_user;
+    pub mod authorized;
+    pub mod cart_session;
+    pub mod jwt;
+    pub mod password;
+}
 
 mod error {
     pub mod app_error;
+    pub mod catchers;
     pub mod logging;
+    pub mod problem;
 }
 
 mod controllers {
+    pub mod api_key_controller;
+    pub mod auth_controller;
+    pub mod cart_controller;
+    pub mod category_controller;
+    pub mod docs_controller;
+    pub mod health_controller;
+    pub mod json_body;
+    pub mod order_controller;
     pub mod product_controller;
     pub mod user_controller;
+    pub mod validated;
 }
 mod use_cases {
+    pub mod api_key_use_case;
+    pub mod auth_use_case;
+    pub mod cart_use_case;
+    pub mod category_use_case;
+    pub mod health_use_case;
+    pub mod order_use_case;
     pub mod product_use_case;
+    pub mod unit_of_work;
     pub mod use_cases;
     pub mod user_use_case;
 }
 mod repositories {
+    pub mod api_key_repo;
+    pub mod cache;
+    pub mod cached_product_repo;
+    pub mod cached_user_repo;
+    pub mod cart_repo;
+    pub mod category_repo;
+    pub mod credential_repo;
     pub mod error;
+    pub mod filter;
+    pub mod health_repo;
+    pub mod order_repo;
+    pub mod pagination;
     pub mod product_repo;
     pub mod repositories;
+    pub mod role_repo;
     pub mod user_repo;
 }
 
 mod models {
+    pub mod api_key_model;
+    pub mod cart_model;
+    pub mod category_model;
+    pub mod credential_model;
+    pub mod health_model;
+    pub mod order_model;
+    pub mod page_model;
     pub mod product_model;
     pub mod user_model;
 }
 
 mod dto {
+    pub mod api_key_dto;
+    pub mod auth_dto;
+    pub mod cart_dto;
+    pub mod category_dto;
+    pub mod order_dto;
+    pub mod page_dto;
     pub mod product_dto;
     pub mod user_dto;
+    pub mod validators;
 }
 
 #[cfg(test)]
 mod test {
     pub mod app;
+    pub mod auth;
+    pub mod collector;
     pub mod db;
+    pub mod log;
     pub mod fixture {
+        pub mod cart;
+        pub mod order;
         pub mod product;
         pub mod user;
     }
@@ -54,22 +135,70 @@ mod test {
 }
 
 use crate::app::create_app;
+use crate::auth::api_key::UsageFlusher;
+use crate::cli::cli::{Cli, Command};
 use crate::config::Config;
-use crate::controllers::{product_controller, user_controller};
+use crate::controllers::{
+    api_key_controller, auth_controller, cart_controller, category_controller, docs_controller,
+    health_controller, order_controller, product_controller, user_controller,
+};
 use crate::db::Db;
+use crate::error::catchers;
+use crate::metrics::{fairing::MetricsFairing, metrics::Metrics};
+use crate::migration::MigrationFairing;
+use crate::request_id::{traced, RequestIdFairing};
+use crate::telemetry::logging::init_logging;
+use clap::Parser;
 use dotenv::dotenv;
 use rocket::fairing::AdHoc;
+use rocket::figment::Figment;
+use rocket::{Build, Rocket};
 use rocket_db_pools::Database;
+use std::sync::Arc;
 
-#[launch]
-async fn rocket() -> _ {
+#[rocket::main]
+async fn main() {
     dotenv().ok();
-    tracing_subscriber::fmt::init();
+    let cli = Cli::parse();
+    let figment = rocket::Config::figment();
+
+    match cli.command {
+        None | Some(Command::Serve) => {
+            let config: Config = figment.extract().expect("valid configuration");
+            let _ = rocket(figment, config).launch().await;
+        }
+        Some(command) => {
+            if let Err(e) = cli::cli::run(command, &figment).await {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+fn rocket(figment: Figment, config: Config) -> Rocket<Build> {
+    // rocket::build()はlogクレートのロガーを登録するので、先にtracingを初期化する
+    let metrics = Arc::new(Metrics::new());
+    let log_guard = init_logging(&config.logging.clone().unwrap_or_default(), metrics.clone())
+        .expect("valid logging configuration");
 
-    rocket::build()
+    rocket::custom(figment)
         .attach(Db::init())
         .attach(AdHoc::config::<Config>())
-        .manage(create_app())
-        .mount("/users", user_controller::routes())
-        .mount("/products", product_controller::routes())
+        .attach(MigrationFairing)
+        .attach(UsageFlusher)
+        .attach(MetricsFairing::new(metrics))
+        .attach(RequestIdFairing)
+        .attach(log_guard)
+        .manage(create_app(&config))
+        .register("/", catchers::catchers())
+        .mount("/auth", traced(auth_controller::routes()))
+        .mount("/users", traced(user_controller::routes()))
+        .mount("/products", traced(product_controller::routes()))
+        .mount("/orders", traced(order_controller::routes()))
+        .mount("/carts", traced(cart_controller::routes()))
+        .mount("/categories", traced(category_controller::routes()))
+        .mount("/api-keys", traced(api_key_controller::routes()))
+        .mount("/health", traced(health_controller::routes()))
+        .mount("/", traced(docs_controller::routes()))
 }
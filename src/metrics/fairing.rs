@@ -0,0 +1,130 @@
+use crate::db::Db;
+use crate::metrics::metrics::Metrics;
+use rocket::fairing::{self, Fairing, Info, Kind};
+use rocket::http::ContentType;
+use rocket::request::{FromRequest, Outcome};
+use rocket::{Build, Data, Request, Response, Rocket, State};
+use rocket_db_pools::Database;
+use std::sync::Arc;
+use std::time::Instant;
+
+// リクエスト数とレイテンシをルートとステータスごとに記録し、/metrics を追加する
+pub struct MetricsFairing {
+    metrics: Arc<Metrics>,
+}
+
+impl MetricsFairing {
+    pub fn new(metrics: Arc<Metrics>) -> Self {
+        Self { metrics }
+    }
+}
+
+struct RequestStart(Instant);
+
+#[rocket::async_trait]
+impl Fairing for MetricsFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Prometheus metrics",
+            kind: Kind::Ignite | Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> fairing::Result {
+        Ok(rocket
+            .manage(self.metrics.clone())
+            .mount("/", routes![metrics]))
+    }
+
+    async fn on_request(&self, req: &mut Request<'_>, _data: &mut Data<'_>) {
+        req.local_cache(|| RequestStart(Instant::now()));
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        let RequestStart(started) = req.local_cache(|| RequestStart(Instant::now()));
+        // ラベルの種類が増えすぎないように、実際のパスではなくルートのパターンを使う
+        let route = req.route().map_or("unmatched", |route| route.uri.path());
+        let status = res.status().code.to_string();
+        let labels = [req.method().as_str(), route, status.as_str()];
+        self.metrics.http_requests.with_label_values(&labels).inc();
+        self.metrics
+            .http_request_duration
+            .with_label_values(&labels)
+            .observe(started.elapsed().as_secs_f64());
+    }
+}
+
+// Dbがattachされていない場合はNone (&DbをガードにするとSentinelで起動できなくなる)
+struct Pool<'r>(Option<&'r Db>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Pool<'r> {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(Pool(Db::fetch(req.rocket())))
+    }
+}
+
+#[get("/metrics")]
+fn metrics(metrics: &State<Arc<Metrics>>, pool: Pool<'_>) -> (ContentType, String) {
+    if let Some(db) = pool.0 {
+        metrics.observe_pool(db);
+    }
+    let content_type = ContentType::new("text", "plain").with_params(("version", "0.0.4"));
+    (content_type, metrics.render())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::App;
+    use crate::error::app_error::AppError;
+    use crate::error::catchers::catchers;
+    use crate::test::app::create_app_for_test;
+    use rocket::http::Status;
+    use rocket::local::asynchronous::Client;
+
+    #[get("/items/<id>")]
+    fn item(id: i32) -> Result<String, AppError> {
+        if id == 0 {
+            return Err(AppError::NotFound);
+        }
+        Ok(id.to_string())
+    }
+
+    #[rocket::async_test]
+    async fn test_metrics() {
+        let app_state: App = create_app_for_test();
+        let rocket = rocket::build()
+            .manage(Arc::new(app_state))
+            .attach(MetricsFairing::new(Arc::new(Metrics::new())))
+            .register("/", catchers())
+            .mount("/", routes![item]);
+        let client = Client::tracked(rocket)
+            .await
+            .expect("valid rocket instance");
+        client.get("/items/1").dispatch().await;
+        client.get("/items/2").dispatch().await;
+        client.get("/items/0").dispatch().await;
+        client.get("/unknown").dispatch().await;
+
+        let response = client.get("/metrics").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(
+            response.headers().get_one("Content-Type"),
+            Some("text/plain; version=0.0.4")
+        );
+        let body = response.into_string().await.unwrap();
+        assert!(body.contains(
+            "http_requests_total{method=\"GET\",route=\"/items/<id>\",status=\"200\"} 2"
+        ));
+        assert!(body.contains(
+            "http_requests_total{method=\"GET\",route=\"/items/<id>\",status=\"404\"} 1"
+        ));
+        assert!(body
+            .contains("http_requests_total{method=\"GET\",route=\"unmatched\",status=\"404\"} 1"));
+        assert!(body.contains("http_request_duration_seconds_count{method=\"GET\",route=\"/items/<id>\",status=\"200\"} 2"));
+        assert!(body.contains("app_errors_total{variant=\"NotFound\"} 1"));
+    }
+}
@@ -0,0 +1,143 @@
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, Opts, Registry, TextEncoder,
+};
+use sqlx::PgPool;
+
+// Prometheusのメトリクス。プロセスに1つ作り、fairingとtracingのレイヤーで共有する
+pub struct Metrics {
+    registry: Registry,
+    pub http_requests: IntCounterVec,
+    pub http_request_duration: HistogramVec,
+    pub app_errors: IntCounterVec,
+    pub repo_query_duration: HistogramVec,
+    pub db_pool_size: IntGauge,
+    pub db_pool_idle: IntGauge,
+    pub db_pool_waiters: IntGauge,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let registry = Registry::new();
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "Number of HTTP requests"),
+            &["method", "route", "status"],
+        )
+        .expect("valid metric");
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "HTTP request latency in seconds",
+            ),
+            &["method", "route", "status"],
+        )
+        .expect("valid metric");
+        let app_errors = IntCounterVec::new(
+            Opts::new("app_errors_total", "Number of AppError responses"),
+            &["variant"],
+        )
+        .expect("valid metric");
+        let repo_query_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "repo_query_duration_seconds",
+                "Duration of repository calls in seconds",
+            ),
+            &["span"],
+        )
+        .expect("valid metric");
+        let db_pool_size = IntGauge::new("db_pool_size", "Connections in the database pool")
+            .expect("valid metric");
+        let db_pool_idle = IntGauge::new("db_pool_idle", "Idle connections in the database pool")
+            .expect("valid metric");
+        let db_pool_waiters = IntGauge::new(
+            "db_pool_waiters",
+            "Requests waiting for a database connection",
+        )
+        .expect("valid metric");
+
+        registry
+            .register(Box::new(http_requests.clone()))
+            .expect("unique metric");
+        registry
+            .register(Box::new(http_request_duration.clone()))
+            .expect("unique metric");
+        registry
+            .register(Box::new(app_errors.clone()))
+            .expect("unique metric");
+        registry
+            .register(Box::new(repo_query_duration.clone()))
+            .expect("unique metric");
+        registry
+            .register(Box::new(db_pool_size.clone()))
+            .expect("unique metric");
+        registry
+            .register(Box::new(db_pool_idle.clone()))
+            .expect("unique metric");
+        registry
+            .register(Box::new(db_pool_waiters.clone()))
+            .expect("unique metric");
+
+        Self {
+            registry,
+            http_requests,
+            http_request_duration,
+            app_errors,
+            repo_query_duration,
+            db_pool_size,
+            db_pool_idle,
+            db_pool_waiters,
+        }
+    }
+
+    // プールの状態はスクレイプの時点の値を記録する
+    pub fn observe_pool(&self, pool: &PgPool) {
+        self.db_pool_size.set(pool.size() as i64);
+        self.db_pool_idle.set(pool.num_idle() as i64);
+    }
+
+    // 戻り値がdropされるまでdb_pool_waitersに数える
+    pub fn wait_for_connection(&self) -> Waiting<'_> {
+        self.db_pool_waiters.inc();
+        Waiting(&self.db_pool_waiters)
+    }
+
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buffer) {
+            tracing::error!("{} ({}:{})", e, file!(), line!());
+        }
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct Waiting<'a>(&'a IntGauge);
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.0.dec();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let metrics = Metrics::new();
+        metrics.app_errors.with_label_values(&["NotFound"]).inc();
+        {
+            let _waiting = metrics.wait_for_connection();
+            assert_eq!(metrics.db_pool_waiters.get(), 1);
+        }
+        assert_eq!(metrics.db_pool_waiters.get(), 0);
+        let text = metrics.render();
+        assert!(text.contains("app_errors_total{variant=\"NotFound\"} 1"));
+        assert!(text.contains("# TYPE db_pool_waiters gauge"));
+    }
+}
@@ -0,0 +1,80 @@
+use crate::metrics::metrics::Metrics;
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::span::{Attributes, Id};
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+// #[instrument(name = "user_repo/find_all")] のようなリポジトリのspanが
+// 作られてから閉じるまでの時間をrepo_query_duration_secondsに記録する
+pub struct RepoSpanLayer {
+    metrics: Arc<Metrics>,
+}
+
+impl RepoSpanLayer {
+    pub fn new(metrics: Arc<Metrics>) -> Self {
+        Self { metrics }
+    }
+}
+
+struct Started(Instant);
+
+pub fn is_repo_span(name: &str) -> bool {
+    match name.split_once('/') {
+        Some((module, _)) => module.ends_with("_repo"),
+        None => false,
+    }
+}
+
+impl<S> Layer<S> for RepoSpanLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if !is_repo_span(attrs.metadata().name()) {
+            return;
+        }
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(Started(Instant::now()));
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let span = match ctx.span(&id) {
+            Some(span) => span,
+            None => return,
+        };
+        let extensions = span.extensions();
+        if let Some(Started(started)) = extensions.get::<Started>() {
+            self.metrics
+                .repo_query_duration
+                .with_label_values(&[span.name()])
+                .observe(started.elapsed().as_secs_f64());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_records_repo_spans() {
+        let metrics = Arc::new(Metrics::new());
+        let subscriber = tracing_subscriber::registry().with(RepoSpanLayer::new(metrics.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info_span!("user_repo/find_all").in_scope(|| {});
+            tracing::info_span!("user_use_case/find_all").in_scope(|| {});
+        });
+        let histogram = &metrics.repo_query_duration;
+        assert_eq!(
+            histogram
+                .with_label_values(&["user_repo/find_all"])
+                .get_sample_count(),
+            1
+        );
+        assert!(!metrics.render().contains("user_use_case"));
+    }
+}
@@ -0,0 +1,178 @@
+use crate::config::Config;
+use crate::db::Db;
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::{Build, Rocket};
+use rocket_db_pools::Database;
+use sqlx::migrate::{Migrate, MigrateError, Migrator};
+use sqlx::{PgConnection, PgPool};
+
+// migrationsディレクトリのマイグレーション。バイナリに埋め込まれる
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub applied: bool,
+}
+
+// 埋め込まれたマイグレーションごとに適用済みかどうか
+pub async fn status(con: &mut PgConnection) -> Result<Vec<MigrationStatus>, MigrateError> {
+    con.ensure_migrations_table().await?;
+    let applied: Vec<i64> = con
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|migration| migration.version)
+        .collect();
+    let status = MIGRATOR
+        .iter()
+        .filter(|migration| !migration.migration_type.is_down_migration())
+        .map(|migration| MigrationStatus {
+            version: migration.version,
+            description: migration.description.to_string(),
+            applied: applied.contains(&migration.version),
+        })
+        .collect();
+    Ok(status)
+}
+
+// 未適用のマイグレーションをすべて適用し、適用したバージョンを返す
+pub async fn up(pool: &PgPool) -> Result<Vec<i64>, MigrateError> {
+    let pending = pending(&mut *pool.acquire().await?).await?;
+    MIGRATOR.run(pool).await?;
+    Ok(pending)
+}
+
+// targetより新しいマイグレーションを戻す。targetが無ければ最後の1つだけ戻す
+pub async fn down(pool: &PgPool, target: Option<i64>) -> Result<Vec<i64>, MigrateError> {
+    let mut applied: Vec<i64> = status(&mut *pool.acquire().await?)
+        .await?
+        .into_iter()
+        .filter(|migration| migration.applied)
+        .map(|migration| migration.version)
+        .collect();
+    let target = match target {
+        Some(target) => target,
+        None => applied.iter().rev().nth(1).copied().unwrap_or(0),
+    };
+    MIGRATOR.undo(pool, target).await?;
+    applied.retain(|version| *version > target);
+    applied.reverse();
+    Ok(applied)
+}
+
+async fn pending(con: &mut PgConnection) -> Result<Vec<i64>, MigrateError> {
+    Ok(status(con)
+        .await?
+        .into_iter()
+        .filter(|migration| !migration.applied)
+        .map(|migration| migration.version)
+        .collect())
+}
+
+// migrate_on_startが有効なら、起動時に未適用のマイグレーションを適用する
+// 失敗した場合は起動しない。Db::init()とAdHoc::config::<Config>()の後にattachする
+pub struct MigrationFairing;
+
+#[rocket::async_trait]
+impl Fairing for MigrationFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Migrations",
+            kind: Kind::Ignite,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> rocket::fairing::Result {
+        let enabled = rocket
+            .state::<Config>()
+            .and_then(|config| config.databases.get(Db::NAME))
+            .and_then(|database| database.migrate_on_start)
+            .unwrap_or(false);
+        if !enabled {
+            return Ok(rocket);
+        }
+        let db = match Db::fetch(&rocket) {
+            Some(db) => db,
+            None => return Err(rocket),
+        };
+        match up(db).await {
+            Ok(applied) => {
+                for version in applied {
+                    tracing::info!("applied migration {}", version);
+                }
+                Ok(rocket)
+            }
+            Err(e) => {
+                tracing::error!("{} ({}:{})", e, file!(), line!());
+                Err(rocket)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::db::create_database_for_test;
+    use rocket::fairing::AdHoc;
+    use rocket::figment::Figment;
+    use rocket::local::asynchronous::Client;
+
+    fn applied(status: &[MigrationStatus]) -> Vec<i64> {
+        status
+            .iter()
+            .filter(|migration| migration.applied)
+            .map(|migration| migration.version)
+            .collect()
+    }
+
+    #[rocket::async_test]
+    async fn test_up_and_down() {
+        let database = create_database_for_test().await.unwrap();
+        let pool = database.pool().await.unwrap();
+        let versions: Vec<i64> = status(&mut pool.acquire().await.unwrap())
+            .await
+            .unwrap()
+            .iter()
+            .map(|migration| migration.version)
+            .collect();
+        let latest = *versions.last().unwrap();
+
+        assert_eq!(up(&pool).await.unwrap(), versions);
+        assert!(up(&pool).await.unwrap().is_empty());
+
+        assert_eq!(down(&pool, None).await.unwrap(), vec![latest]);
+        let status = status(&mut pool.acquire().await.unwrap()).await.unwrap();
+        assert_eq!(applied(&status), versions[..versions.len() - 1]);
+        assert_eq!(up(&pool).await.unwrap(), vec![latest]);
+
+        let mut reverted = versions.clone();
+        reverted.reverse();
+        assert_eq!(down(&pool, Some(0)).await.unwrap(), reverted);
+        assert!(down(&pool, Some(0)).await.unwrap().is_empty());
+
+        pool.close().await;
+        database.drop().await.unwrap();
+    }
+
+    #[rocket::async_test]
+    async fn test_fairing_applies_pending_migrations() {
+        let database = create_database_for_test().await.unwrap();
+        let figment = Figment::from(rocket::Config::debug_default())
+            .merge(("databases.hoge.url", &database.url))
+            .merge(("databases.hoge.migrate_on_start", true));
+        let rocket = rocket::custom(figment)
+            .attach(Db::init())
+            .attach(AdHoc::config::<Config>())
+            .attach(MigrationFairing);
+        let client = Client::tracked(rocket).await.expect("migrations applied");
+        let db = Db::fetch(client.rocket()).unwrap();
+        let status = status(&mut db.acquire().await.unwrap()).await.unwrap();
+        assert!(status.iter().all(|migration| migration.applied));
+
+        db.close().await;
+        drop(client);
+        database.drop().await.unwrap();
+    }
+}
@@ -0,0 +1,42 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+// key_hashは照合にだけ使い、レスポンスには含めない
+#[derive(Debug, Clone, PartialEq, Eq, FromRow, Serialize, ToSchema)]
+pub struct ApiKey {
+    pub id: i32,
+    pub name: String,
+    pub prefix: String,
+    #[serde(skip_serializing)]
+    pub key_hash: String,
+    pub scopes: Vec<String>,
+    pub created_by: Option<i32>,
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub use_count: i64,
+}
+
+impl ApiKey {
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.revoked_at.is_none() && self.scopes.iter().any(|s| s == scope)
+    }
+}
+
+// 発行直後のレスポンス。keyはこの時しか返さない
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MintedApiKey {
+    #[serde(flatten)]
+    pub api_key: ApiKey,
+    pub key: String,
+}
+
+// まとめて書き込むまでメモリに溜めておく利用回数と最終利用日時
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiKeyUsage {
+    pub id: i32,
+    pub count: i64,
+    pub last_used_at: DateTime<Utc>,
+}
@@ -0,0 +1,40 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+// カートの持ち主。ログイン中はユーザー、未ログインならcookieのトークン
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CartOwner {
+    User(i32),
+    Anonymous(Uuid),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct Cart {
+    pub id: i32,
+    pub user_id: Option<i32>,
+    pub token: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+// カートの明細。価格は注文時と違って常に現在の商品の価格を使う
+#[derive(Debug, Clone, PartialEq, Eq, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct CartLine {
+    pub product_id: i32,
+    pub name: String,
+    pub sku: String,
+    pub unit_price: i64,
+    pub currency: String,
+    pub quantity: i32,
+    pub subtotal: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct CartView {
+    pub items: Vec<CartLine>,
+    // 空のカートではnull
+    pub currency: Option<String>,
+    pub total: i64,
+}
@@ -0,0 +1,11 @@
+use sqlx::FromRow;
+
+// パスワードハッシュを含むため、レスポンスには使わない
+#[derive(Debug, FromRow)]
+pub struct Credential {
+    pub user_id: i32,
+    pub email: String,
+    pub password_hash: String,
+    // ログインのcookieに入れる世代。ログアウトとパスワードの変更で進める
+    pub session_epoch: i32,
+}
@@ -0,0 +1,60 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Up,
+    Down,
+}
+
+// 依存先ごとのチェック結果。requiredでないチェックが失敗してもreadyのままにする
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct HealthCheck {
+    pub status: HealthStatus,
+    pub required: bool,
+    pub latency_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl HealthCheck {
+    pub fn new(required: bool, latency: Duration, result: Result<(), String>) -> Self {
+        let (status, error) = match result {
+            Ok(()) => (HealthStatus::Up, None),
+            Err(e) => (HealthStatus::Down, Some(e)),
+        };
+        Self {
+            status,
+            required,
+            latency_ms: latency.as_secs_f64() * 1000.0,
+            error,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    pub checks: BTreeMap<String, HealthCheck>,
+}
+
+impl HealthReport {
+    pub fn new(checks: BTreeMap<String, HealthCheck>) -> Self {
+        let down = checks
+            .values()
+            .any(|check| check.required && check.status == HealthStatus::Down);
+        let status = if down {
+            HealthStatus::Down
+        } else {
+            HealthStatus::Up
+        };
+        Self { status, checks }
+    }
+
+    pub fn is_up(&self) -> bool {
+        self.status == HealthStatus::Up
+    }
+}
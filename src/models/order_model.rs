@@ -0,0 +1,45 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, PartialEq, Eq, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct Order {
+    pub id: i32,
+    pub user_id: i32,
+    pub currency: String,
+    // 明細のsubtotalの合計 (通貨の最小単位)
+    pub total: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+// 注文時点の商品の名前・SKU・単価。商品が削除されるとproduct_idはnullになる
+#[derive(Debug, Clone, PartialEq, Eq, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct OrderItem {
+    pub id: i32,
+    pub order_id: i32,
+    pub product_id: Option<i32>,
+    pub name: String,
+    pub sku: String,
+    pub unit_price: i64,
+    pub quantity: i32,
+    pub subtotal: i64,
+}
+
+// order_itemsに書き込む前の明細
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewOrderItem {
+    pub product_id: i32,
+    pub name: String,
+    pub sku: String,
+    pub unit_price: i64,
+    pub quantity: i32,
+    pub subtotal: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct OrderDetail {
+    #[serde(flatten)]
+    pub order: Order,
+    pub items: Vec<OrderItem>,
+}
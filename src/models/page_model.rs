@@ -0,0 +1,14 @@
+use crate::models::product_model::Product;
+use crate::models::user_model::User;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+// OpenAPIのスキーマはジェネリクスを扱えないので、使う型ごとに別名を付ける
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[aliases(UserPage = Page<User>, ProductPage = Page<Product>)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<i64>,
+}
@@ -1,8 +1,49 @@
+use crate::repositories::pagination::ColumnType;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use utoipa::ToSchema;
 
-#[derive(Debug, FromRow, Serialize, Deserialize)]
+#[derive(Debug, FromRow, Serialize, Deserialize, ToSchema)]
 pub struct Product {
     pub id: i32,
     pub name: String,
+    pub sku: String,
+    // 通貨の最小単位 (JPYなら円、USDならセント)
+    pub price: i64,
+    // ISO 4217の通貨コード
+    pub currency: String,
+    pub stock: i32,
+}
+
+// 在庫の引き当ての結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StockReservation {
+    Reserved { remaining: i32 },
+    Insufficient { available: i32 },
+}
+
+impl Product {
+    pub const SORTABLE_COLUMNS: &'static [(&'static str, ColumnType)] = &[
+        ("id", ColumnType::Int),
+        ("name", ColumnType::Text),
+        ("sku", ColumnType::Text),
+        ("price", ColumnType::Int),
+        ("stock", ColumnType::Int),
+    ];
+}
+
+// 一覧の絞り込み条件。値はすべてバインドパラメータとしてSQLに渡す
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct ProductFilter {
+    // 名前の部分一致
+    pub name_like: Option<String>,
+    // このカテゴリかその子孫のカテゴリに属する商品
+    pub category_id: Option<i32>,
+    // このタグが付いた商品 (大文字小文字は区別しない)
+    pub tag: Option<String>,
+}
+
+// タグは前後の空白を除いて小文字で保存・検索する
+pub fn normalize_tag(tag: &str) -> String {
+    tag.trim().to_lowercase()
 }
@@ -1,9 +1,28 @@
+use crate::repositories::pagination::ColumnType;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use utoipa::ToSchema;
 
-#[derive(Debug, FromRow, Serialize, Deserialize)]
+#[derive(Debug, FromRow, Serialize, Deserialize, ToSchema)]
 pub struct User {
     pub id: i32,
     pub name: String,
     pub age: Option<i32>,
 }
+
+impl User {
+    pub const SORTABLE_COLUMNS: &'static [(&'static str, ColumnType)] =
+        &[("id", ColumnType::Int), ("name", ColumnType::Text)];
+}
+
+// 一覧の絞り込み条件。値はすべてバインドパラメータとしてSQLに渡す
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct UserFilter {
+    // 名前の前方一致
+    pub name_like: Option<String>,
+    pub age_gt: Option<i32>,
+    pub age_gte: Option<i32>,
+    pub age_lt: Option<i32>,
+    pub age_lte: Option<i32>,
+    pub age_is_null: Option<bool>,
+}
@@ -0,0 +1,177 @@
+use crate::log_into;
+use crate::models::api_key_model::{ApiKey, ApiKeyUsage};
+use crate::repositories::error::DbRepoError;
+use mockall::automock;
+use sqlx::{query, query_as, PgConnection};
+use tracing::instrument;
+
+pub struct ApiKeyRepoImpl {}
+
+impl ApiKeyRepoImpl {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+#[automock]
+#[async_trait]
+pub trait ApiKeyRepo: Send + Sync {
+    async fn create(
+        &self,
+        con: &mut PgConnection,
+        name: &str,
+        prefix: &str,
+        key_hash: &str,
+        scopes: &[String],
+        created_by: Option<i32>,
+    ) -> Result<ApiKey, DbRepoError>;
+
+    async fn find_all(&self, con: &mut PgConnection) -> Result<Vec<ApiKey>, DbRepoError>;
+
+    async fn find_by_hash(
+        &self,
+        con: &mut PgConnection,
+        key_hash: &str,
+    ) -> Result<Option<ApiKey>, DbRepoError>;
+
+    async fn revoke(&self, con: &mut PgConnection, id: i32) -> Result<ApiKey, DbRepoError>;
+
+    async fn record_usage(
+        &self,
+        con: &mut PgConnection,
+        usage: &ApiKeyUsage,
+    ) -> Result<(), DbRepoError>;
+}
+
+#[async_trait]
+impl ApiKeyRepo for ApiKeyRepoImpl {
+    #[instrument(name = "api_key_repo/create", skip_all, fields(prefix = %prefix))]
+    async fn create(
+        &self,
+        con: &mut PgConnection,
+        name: &str,
+        prefix: &str,
+        key_hash: &str,
+        scopes: &[String],
+        created_by: Option<i32>,
+    ) -> Result<ApiKey, DbRepoError> {
+        query_as!(
+            ApiKey,
+            "INSERT INTO api_keys (name, prefix, key_hash, scopes, created_by) \
+             VALUES ($1, $2, $3, $4, $5) RETURNING *",
+            name,
+            prefix,
+            key_hash,
+            scopes,
+            created_by
+        )
+        .fetch_one(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+
+    #[instrument(name = "api_key_repo/find_all", skip_all)]
+    async fn find_all(&self, con: &mut PgConnection) -> Result<Vec<ApiKey>, DbRepoError> {
+        query_as!(ApiKey, "SELECT * FROM api_keys ORDER BY id")
+            .fetch_all(&mut *con)
+            .await
+            .map_err(|e| log_into!(e, DbRepoError))
+    }
+
+    #[instrument(name = "api_key_repo/find_by_hash", skip_all)]
+    async fn find_by_hash(
+        &self,
+        con: &mut PgConnection,
+        key_hash: &str,
+    ) -> Result<Option<ApiKey>, DbRepoError> {
+        query_as!(
+            ApiKey,
+            "SELECT * FROM api_keys WHERE key_hash = $1",
+            key_hash
+        )
+        .fetch_optional(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+
+    // 取り消し済みの場合は最初に取り消した日時のままにする
+    #[instrument(name = "api_key_repo/revoke", skip_all, fields(id = %id))]
+    async fn revoke(&self, con: &mut PgConnection, id: i32) -> Result<ApiKey, DbRepoError> {
+        query_as!(
+            ApiKey,
+            "UPDATE api_keys SET revoked_at = COALESCE(revoked_at, now()) WHERE id = $1 RETURNING *",
+            id
+        )
+        .fetch_one(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+
+    #[instrument(name = "api_key_repo/record_usage", skip_all, fields(id = %usage.id))]
+    async fn record_usage(
+        &self,
+        con: &mut PgConnection,
+        usage: &ApiKeyUsage,
+    ) -> Result<(), DbRepoError> {
+        query!(
+            "UPDATE api_keys SET use_count = use_count + $2, \
+             last_used_at = GREATEST(last_used_at, $3) WHERE id = $1",
+            usage.id,
+            usage.count,
+            usage.last_used_at
+        )
+        .execute(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::api_key_model::ApiKeyUsage;
+    use crate::repositories::api_key_repo::{ApiKeyRepo, ApiKeyRepoImpl};
+    use crate::test::db::create_db_con_for_test;
+    use chrono::Utc;
+    use sqlx::Connection;
+
+    #[tokio::test]
+    async fn test_create_revoke_and_record_usage() {
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+        let repo = ApiKeyRepoImpl::new();
+        let scopes = vec!["products:write".to_string()];
+        let created = repo
+            .create(
+                &mut tx,
+                "partner",
+                "rk_abcdefgh",
+                "repo_test_hash",
+                &scopes,
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(created.scopes, scopes);
+        assert_eq!(created.use_count, 0);
+
+        let usage = ApiKeyUsage {
+            id: created.id,
+            count: 3,
+            last_used_at: Utc::now(),
+        };
+        repo.record_usage(&mut tx, &usage).await.unwrap();
+        let found = repo
+            .find_by_hash(&mut tx, "repo_test_hash")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.use_count, 3);
+        assert!(found.last_used_at.is_some());
+
+        let revoked = repo.revoke(&mut tx, created.id).await.unwrap();
+        assert!(revoked.revoked_at.is_some());
+        assert!(!revoked.has_scope("products:write"));
+        tx.rollback().await.unwrap();
+    }
+}
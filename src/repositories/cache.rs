@@ -0,0 +1,300 @@
+use rocket_db_pools::deadpool_redis::{self, redis, Runtime};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sqlx::pool::PoolConnection;
+use sqlx::{PgConnection, Postgres, Transaction};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum CacheError {
+    #[error("[CacheError::Redis] {0}")]
+    Redis(#[from] redis::RedisError),
+    #[error("[CacheError::Pool] {0}")]
+    Pool(#[from] deadpool_redis::PoolError),
+    #[error("[CacheError::CreatePool] {0}")]
+    CreatePool(#[from] deadpool_redis::CreatePoolError),
+}
+
+#[async_trait]
+pub trait CacheStore: Send + Sync {
+    async fn get(&self, key: &str) -> Result<Option<String>, CacheError>;
+    async fn set(&self, key: &str, value: &str, ttl: Duration) -> Result<(), CacheError>;
+    async fn delete(&self, key: &str) -> Result<(), CacheError>;
+    async fn incr(&self, key: &str) -> Result<i64, CacheError>;
+    async fn ping(&self) -> Result<(), CacheError>;
+}
+
+pub struct RedisCache {
+    pool: deadpool_redis::Pool,
+}
+
+impl RedisCache {
+    // 接続は最初に使われた時に作られる
+    pub fn new(url: &str) -> Result<Self, CacheError> {
+        let pool = deadpool_redis::Config::from_url(url).create_pool(Some(Runtime::Tokio1))?;
+        Ok(Self { pool })
+    }
+}
+
+#[async_trait]
+impl CacheStore for RedisCache {
+    async fn get(&self, key: &str) -> Result<Option<String>, CacheError> {
+        let mut con = self.pool.get().await?;
+        let value = redis::cmd("GET").arg(key).query_async(&mut con).await?;
+        Ok(value)
+    }
+
+    async fn set(&self, key: &str, value: &str, ttl: Duration) -> Result<(), CacheError> {
+        let mut con = self.pool.get().await?;
+        redis::cmd("SET")
+            .arg(key)
+            .arg(value)
+            .arg("EX")
+            .arg(ttl.as_secs().max(1))
+            .query_async::<_, ()>(&mut con)
+            .await?;
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), CacheError> {
+        let mut con = self.pool.get().await?;
+        redis::cmd("DEL").arg(key).query_async::<_, ()>(&mut con).await?;
+        Ok(())
+    }
+
+    async fn incr(&self, key: &str) -> Result<i64, CacheError> {
+        let mut con = self.pool.get().await?;
+        let value = redis::cmd("INCR").arg(key).query_async(&mut con).await?;
+        Ok(value)
+    }
+
+    async fn ping(&self) -> Result<(), CacheError> {
+        let mut con = self.pool.get().await?;
+        redis::cmd("PING").query_async::<_, ()>(&mut con).await?;
+        Ok(())
+    }
+}
+
+// Redisが無い環境(テストなど)で使うプロセス内キャッシュ
+#[derive(Default)]
+pub struct MemoryCache {
+    entries: Mutex<HashMap<String, (String, Option<Instant>)>>,
+}
+
+impl MemoryCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl CacheStore for MemoryCache {
+    async fn get(&self, key: &str) -> Result<Option<String>, CacheError> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(key) {
+            Some((_, Some(expires_at))) if *expires_at <= Instant::now() => {
+                entries.remove(key);
+                Ok(None)
+            }
+            Some((value, _)) => Ok(Some(value.clone())),
+            None => Ok(None),
+        }
+    }
+
+    async fn set(&self, key: &str, value: &str, ttl: Duration) -> Result<(), CacheError> {
+        let expires_at = Instant::now() + ttl;
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), (value.to_string(), Some(expires_at)));
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), CacheError> {
+        self.entries.lock().unwrap().remove(key);
+        Ok(())
+    }
+
+    async fn incr(&self, key: &str) -> Result<i64, CacheError> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries
+            .entry(key.to_string())
+            .or_insert_with(|| ("0".to_string(), None));
+        let value = entry.0.parse::<i64>().unwrap_or(0) + 1;
+        entry.0 = value.to_string();
+        Ok(value)
+    }
+
+    async fn ping(&self) -> Result<(), CacheError> {
+        Ok(())
+    }
+}
+
+// リポジトリのデコレータが使うキャッシュ操作
+// キャッシュのエラーはログに出してキャッシュミスとして扱い、リクエストは失敗させない
+// 一覧はバージョン番号をキーに含め、書き込み時にバージョンを上げて古いページを無効にする
+pub struct RepoCache {
+    store: Arc<dyn CacheStore>,
+    prefix: &'static str,
+    ttl: Duration,
+}
+
+impl RepoCache {
+    pub fn new(store: Arc<dyn CacheStore>, prefix: &'static str, ttl: Duration) -> Self {
+        Self { store, prefix, ttl }
+    }
+
+    pub fn item_key(&self, id: i32) -> String {
+        item_key(self.prefix, id)
+    }
+
+    pub async fn page_key<Q: Serialize>(&self, query: &Q) -> String {
+        let version_key = format!("{}:version", self.prefix);
+        let version = match self.store.get(&version_key).await {
+            Ok(version) => version.unwrap_or_else(|| "0".to_string()),
+            Err(e) => {
+                tracing::warn!("{} ({}:{})", e, file!(), line!());
+                "0".to_string()
+            }
+        };
+        let query = serde_json::to_string(query).unwrap_or_default();
+        format!("{}:page:{}:{}", self.prefix, version, query)
+    }
+
+    pub async fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        match self.store.get(key).await {
+            Ok(Some(value)) => serde_json::from_str(&value).ok(),
+            Ok(None) => None,
+            Err(e) => {
+                tracing::warn!("{} ({}:{})", e, file!(), line!());
+                None
+            }
+        }
+    }
+
+    pub async fn put<T: Serialize>(&self, key: &str, value: &T) {
+        let value = match serde_json::to_string(value) {
+            Ok(value) => value,
+            Err(_) => return,
+        };
+        if let Err(e) = self.store.set(key, &value, self.ttl).await {
+            tracing::warn!("{} ({}:{})", e, file!(), line!());
+        }
+    }
+
+    // UnitOfWorkの中ではキャッシュを読み書きしない
+    // コミット前の値をキャッシュしたり、同じトランザクションで書いた値より古い値を返したりしないため
+    pub fn bypass(&self, con: &mut dyn CacheScope) -> bool {
+        con.pending().is_some()
+    }
+
+    // idのキャッシュを消し、一覧のキャッシュをすべて無効にする
+    // UnitOfWorkの中ではコミットされるまで保留する
+    pub async fn invalidate(&self, con: &mut dyn CacheScope, id: Option<i32>) {
+        let invalidation = Invalidation {
+            store: self.store.clone(),
+            prefix: self.prefix,
+            id,
+        };
+        match con.pending() {
+            Some(pending) => pending.push(invalidation),
+            None => invalidation.run().await,
+        }
+    }
+}
+
+fn item_key(prefix: &str, id: i32) -> String {
+    format!("{}:id:{}", prefix, id)
+}
+
+pub struct Invalidation {
+    store: Arc<dyn CacheStore>,
+    prefix: &'static str,
+    id: Option<i32>,
+}
+
+impl Invalidation {
+    pub async fn run(self) {
+        if let Some(id) = self.id {
+            if let Err(e) = self.store.delete(&item_key(self.prefix, id)).await {
+                tracing::warn!("{} ({}:{})", e, file!(), line!());
+            }
+        }
+        let version_key = format!("{}:version", self.prefix);
+        if let Err(e) = self.store.incr(&version_key).await {
+            tracing::warn!("{} ({}:{})", e, file!(), line!());
+        }
+    }
+}
+
+// キャッシュするリポジトリに渡すコネクション
+// UnitOfWorkのコネクションはpendingで無効化の保留先を返し、それ以外はNoneを返す
+pub trait CacheScope: Send {
+    fn con(&mut self) -> &mut PgConnection;
+    fn pending(&mut self) -> Option<&mut Vec<Invalidation>>;
+}
+
+// リポジトリのトレイトの引数に使う。mockallが省略されたdynのライフタイムを扱えないので、ライフタイムを明示する
+pub type ScopedCon<'a> = dyn CacheScope + 'a;
+
+impl CacheScope for PgConnection {
+    fn con(&mut self) -> &mut PgConnection {
+        self
+    }
+
+    fn pending(&mut self) -> Option<&mut Vec<Invalidation>> {
+        None
+    }
+}
+
+// テストなどでUnitOfWorkを使わずにトランザクションやプールのコネクションを直接渡す場合
+impl CacheScope for Transaction<'_, Postgres> {
+    fn con(&mut self) -> &mut PgConnection {
+        self
+    }
+
+    fn pending(&mut self) -> Option<&mut Vec<Invalidation>> {
+        None
+    }
+}
+
+impl CacheScope for PoolConnection<Postgres> {
+    fn con(&mut self) -> &mut PgConnection {
+        self
+    }
+
+    fn pending(&mut self) -> Option<&mut Vec<Invalidation>> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::db::create_db_con_for_test;
+
+    #[tokio::test]
+    async fn test_memory_cache_expires() {
+        let cache = MemoryCache::new();
+        cache.set("key", "value", Duration::ZERO).await.unwrap();
+        assert_eq!(cache.get("key").await.unwrap(), None);
+        cache
+            .set("key", "value", Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert_eq!(cache.get("key").await.unwrap(), Some("value".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_changes_page_key() {
+        let store = Arc::new(MemoryCache::new());
+        let cache = RepoCache::new(store, "user", Duration::from_secs(60));
+        let before = cache.page_key(&"query").await;
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        cache.invalidate(&mut db_con, None).await;
+        assert_ne!(before, cache.page_key(&"query").await);
+    }
+}
@@ -0,0 +1,259 @@
+use crate::dto::product_dto::ProductInput;
+use crate::models::category_model::Category;
+use crate::models::page_model::Page;
+use crate::models::product_model::{Product, ProductFilter, StockReservation};
+use crate::repositories::cache::{CacheStore, RepoCache, ScopedCon};
+use crate::repositories::error::DbRepoError;
+use crate::repositories::pagination::PageRequest;
+use crate::repositories::product_repo::ProductRepo;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::instrument;
+
+// ProductRepoの結果をキャッシュするデコレータ。find_by_idと一覧のページをキャッシュし、書き込み時に無効にする
+pub struct CachedProductRepo {
+    inner: Box<dyn ProductRepo>,
+    cache: RepoCache,
+}
+
+impl CachedProductRepo {
+    pub fn new(inner: Box<dyn ProductRepo>, store: Arc<dyn CacheStore>, ttl: Duration) -> Self {
+        Self {
+            inner,
+            cache: RepoCache::new(store, "product", ttl),
+        }
+    }
+}
+
+#[async_trait]
+impl ProductRepo for CachedProductRepo {
+    #[instrument(name = "cached_product_repo/create", skip_all)]
+    async fn create<'a>(
+        &self,
+        con: &mut ScopedCon<'a>,
+        input: &ProductInput,
+    ) -> Result<Product, DbRepoError> {
+        let product = self.inner.create(con, input).await?;
+        self.cache.invalidate(con, None).await;
+        Ok(product)
+    }
+
+    async fn find_all<'a>(&self, con: &mut ScopedCon<'a>) -> Result<Vec<Product>, DbRepoError> {
+        self.inner.find_all(con).await
+    }
+
+    #[instrument(name = "cached_product_repo/find_page", skip_all)]
+    async fn find_page<'a>(
+        &self,
+        con: &mut ScopedCon<'a>,
+        filter: &ProductFilter,
+        page: &PageRequest,
+    ) -> Result<Page<Product>, DbRepoError> {
+        if self.cache.bypass(con) {
+            return self.inner.find_page(con, filter, page).await;
+        }
+        let key = self.cache.page_key(&(filter, page)).await;
+        if let Some(cached) = self.cache.get(&key).await {
+            return Ok(cached);
+        }
+        let products = self.inner.find_page(con, filter, page).await?;
+        self.cache.put(&key, &products).await;
+        Ok(products)
+    }
+
+    #[instrument(name = "cached_product_repo/find_by_id", skip_all, fields(id = %id))]
+    async fn find_by_id<'a>(
+        &self,
+        con: &mut ScopedCon<'a>,
+        id: i32,
+    ) -> Result<Option<Product>, DbRepoError> {
+        if self.cache.bypass(con) {
+            return self.inner.find_by_id(con, id).await;
+        }
+        let key = self.cache.item_key(id);
+        if let Some(cached) = self.cache.get(&key).await {
+            return Ok(Some(cached));
+        }
+        // 存在しないidはキャッシュしない (後から作られたときに古い結果を返さないため)
+        let product = self.inner.find_by_id(con, id).await?;
+        if let Some(product) = &product {
+            self.cache.put(&key, product).await;
+        }
+        Ok(product)
+    }
+
+    // 注文などで使う。複数のidをまとめて読むのでキャッシュしない
+    async fn find_by_ids<'a>(
+        &self,
+        con: &mut ScopedCon<'a>,
+        ids: &[i32],
+    ) -> Result<Vec<Product>, DbRepoError> {
+        self.inner.find_by_ids(con, ids).await
+    }
+
+    #[instrument(name = "cached_product_repo/update", skip_all, fields(id = %id))]
+    async fn update<'a>(
+        &self,
+        con: &mut ScopedCon<'a>,
+        id: i32,
+        input: &ProductInput,
+    ) -> Result<Product, DbRepoError> {
+        let result = self.inner.update(con, id, input).await;
+        self.cache.invalidate(con, Some(id)).await;
+        result
+    }
+
+    #[instrument(name = "cached_product_repo/delete", skip_all, fields(id = %id))]
+    async fn delete<'a>(&self, con: &mut ScopedCon<'a>, id: i32) -> Result<(), DbRepoError> {
+        let result = self.inner.delete(con, id).await;
+        self.cache.invalidate(con, Some(id)).await;
+        result
+    }
+
+    #[instrument(name = "cached_product_repo/reserve_stock", skip_all, fields(id = %id))]
+    async fn reserve_stock<'a>(
+        &self,
+        con: &mut ScopedCon<'a>,
+        id: i32,
+        quantity: i32,
+    ) -> Result<StockReservation, DbRepoError> {
+        let result = self.inner.reserve_stock(con, id, quantity).await;
+        self.cache.invalidate(con, Some(id)).await;
+        result
+    }
+
+    // カテゴリやタグで絞り込んだ一覧がキャッシュにあるので、一覧のキャッシュを無効にする
+    #[instrument(name = "cached_product_repo/set_categories", skip_all, fields(id = %id))]
+    async fn set_categories<'a>(
+        &self,
+        con: &mut ScopedCon<'a>,
+        id: i32,
+        category_ids: &[i32],
+    ) -> Result<(), DbRepoError> {
+        let result = self.inner.set_categories(con, id, category_ids).await;
+        self.cache.invalidate(con, None).await;
+        result
+    }
+
+    async fn find_categories<'a>(
+        &self,
+        con: &mut ScopedCon<'a>,
+        id: i32,
+    ) -> Result<Vec<Category>, DbRepoError> {
+        self.inner.find_categories(con, id).await
+    }
+
+    #[instrument(name = "cached_product_repo/set_tags", skip_all, fields(id = %id))]
+    async fn set_tags<'a>(
+        &self,
+        con: &mut ScopedCon<'a>,
+        id: i32,
+        tags: &[String],
+    ) -> Result<(), DbRepoError> {
+        let result = self.inner.set_tags(con, id, tags).await;
+        self.cache.invalidate(con, None).await;
+        result
+    }
+
+    async fn find_tags<'a>(
+        &self,
+        con: &mut ScopedCon<'a>,
+        id: i32,
+    ) -> Result<Vec<String>, DbRepoError> {
+        self.inner.find_tags(con, id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repositories::cache::MemoryCache;
+    use crate::repositories::product_repo::MockProductRepo;
+    use crate::test::db::create_db_con_for_test;
+    use crate::test::fixture::product::{product_fixture, product_input_fixture};
+    use crate::use_cases::unit_of_work::UnitOfWork;
+    use sqlx::Connection;
+
+    #[tokio::test]
+    async fn test_find_by_id_is_cached_until_delete() {
+        let mut mock_product_repo = MockProductRepo::new();
+        mock_product_repo
+            .expect_find_by_id()
+            .times(2)
+            .returning(|_, id| Ok(Some(product_fixture(id as usize))));
+        mock_product_repo
+            .expect_delete()
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let store = Arc::new(MemoryCache::new());
+        let repo =
+            CachedProductRepo::new(Box::new(mock_product_repo), store, Duration::from_secs(60));
+        let mut db_con = create_db_con_for_test().await.unwrap();
+
+        repo.find_by_id(&mut db_con, 1).await.unwrap();
+        repo.find_by_id(&mut db_con, 1).await.unwrap();
+        repo.delete(&mut db_con, 1).await.unwrap();
+        repo.find_by_id(&mut db_con, 1).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rollback_does_not_repopulate_cache() {
+        let mut mock_product_repo = MockProductRepo::new();
+        mock_product_repo
+            .expect_update()
+            .times(1)
+            .returning(|_, id, _| Ok(product_fixture(id as usize)));
+        mock_product_repo
+            .expect_find_by_id()
+            .times(1)
+            .returning(|_, id| Ok(Some(product_fixture(id as usize))));
+        let store = Arc::new(MemoryCache::new());
+        let repo = CachedProductRepo::new(
+            Box::new(mock_product_repo),
+            store.clone(),
+            Duration::from_secs(60),
+        );
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+        let input = product_input_fixture();
+
+        let mut uow = UnitOfWork::begin(&mut tx).await.unwrap();
+        repo.update(uow.con(), 1, &input).await.unwrap();
+        repo.find_by_id(uow.con(), 1).await.unwrap();
+        uow.rollback().await.unwrap();
+
+        assert_eq!(store.get("product:id:1").await.unwrap(), None);
+        assert_eq!(store.get("product:version").await.unwrap(), None);
+        tx.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_invalidation_waits_for_commit() {
+        let mut mock_product_repo = MockProductRepo::new();
+        mock_product_repo
+            .expect_find_by_id()
+            .times(1)
+            .returning(|_, id| Ok(Some(product_fixture(id as usize))));
+        mock_product_repo
+            .expect_reserve_stock()
+            .times(1)
+            .returning(|_, _, _| Ok(StockReservation::Reserved { remaining: 0 }));
+        let store = Arc::new(MemoryCache::new());
+        let repo = CachedProductRepo::new(
+            Box::new(mock_product_repo),
+            store.clone(),
+            Duration::from_secs(60),
+        );
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+        repo.find_by_id(&mut tx, 1).await.unwrap();
+
+        let mut uow = UnitOfWork::begin(&mut tx).await.unwrap();
+        repo.reserve_stock(uow.con(), 1, 1).await.unwrap();
+        assert!(store.get("product:id:1").await.unwrap().is_some());
+        uow.commit().await.unwrap();
+
+        assert_eq!(store.get("product:id:1").await.unwrap(), None);
+        tx.rollback().await.unwrap();
+    }
+}
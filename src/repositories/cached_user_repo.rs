@@ -0,0 +1,167 @@
+use crate::models::page_model::Page;
+use crate::models::user_model::{User, UserFilter};
+use crate::repositories::cache::{CacheStore, RepoCache, ScopedCon};
+use crate::repositories::error::DbRepoError;
+use crate::repositories::pagination::PageRequest;
+use crate::repositories::user_repo::UserRepo;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::instrument;
+
+// UserRepoの結果をキャッシュするデコレータ。find_by_idと一覧のページをキャッシュし、書き込み時に無効にする
+pub struct CachedUserRepo {
+    inner: Box<dyn UserRepo>,
+    cache: RepoCache,
+}
+
+impl CachedUserRepo {
+    pub fn new(inner: Box<dyn UserRepo>, store: Arc<dyn CacheStore>, ttl: Duration) -> Self {
+        Self {
+            inner,
+            cache: RepoCache::new(store, "user", ttl),
+        }
+    }
+}
+
+#[async_trait]
+impl UserRepo for CachedUserRepo {
+    #[instrument(name = "cached_user_repo/create", skip_all)]
+    async fn create<'a>(
+        &self,
+        con: &mut ScopedCon<'a>,
+        name: &String,
+        age: i32,
+    ) -> Result<User, DbRepoError> {
+        let user = self.inner.create(con, name, age).await?;
+        self.cache.invalidate(con, None).await;
+        Ok(user)
+    }
+
+    async fn find_all<'a>(&self, con: &mut ScopedCon<'a>) -> Result<Vec<User>, DbRepoError> {
+        self.inner.find_all(con).await
+    }
+
+    #[instrument(name = "cached_user_repo/find_page", skip_all)]
+    async fn find_page<'a>(
+        &self,
+        con: &mut ScopedCon<'a>,
+        filter: &UserFilter,
+        page: &PageRequest,
+    ) -> Result<Page<User>, DbRepoError> {
+        if self.cache.bypass(con) {
+            return self.inner.find_page(con, filter, page).await;
+        }
+        let key = self.cache.page_key(&(filter, page)).await;
+        if let Some(cached) = self.cache.get(&key).await {
+            return Ok(cached);
+        }
+        let users = self.inner.find_page(con, filter, page).await?;
+        self.cache.put(&key, &users).await;
+        Ok(users)
+    }
+
+    #[instrument(name = "cached_user_repo/find_by_id", skip_all, fields(id = %id))]
+    async fn find_by_id<'a>(
+        &self,
+        con: &mut ScopedCon<'a>,
+        id: i32,
+    ) -> Result<Option<User>, DbRepoError> {
+        if self.cache.bypass(con) {
+            return self.inner.find_by_id(con, id).await;
+        }
+        let key = self.cache.item_key(id);
+        if let Some(cached) = self.cache.get(&key).await {
+            return Ok(Some(cached));
+        }
+        // 存在しないidはキャッシュしない (後から作られたときに古い結果を返さないため)
+        let user = self.inner.find_by_id(con, id).await?;
+        if let Some(user) = &user {
+            self.cache.put(&key, user).await;
+        }
+        Ok(user)
+    }
+
+    #[instrument(name = "cached_user_repo/update", skip_all, fields(id = %id))]
+    async fn update<'a>(
+        &self,
+        con: &mut ScopedCon<'a>,
+        id: i32,
+        name: &String,
+        age: i32,
+    ) -> Result<User, DbRepoError> {
+        let result = self.inner.update(con, id, name, age).await;
+        self.cache.invalidate(con, Some(id)).await;
+        result
+    }
+
+    #[instrument(name = "cached_user_repo/delete", skip_all, fields(id = %id))]
+    async fn delete<'a>(&self, con: &mut ScopedCon<'a>, id: i32) -> Result<(), DbRepoError> {
+        let result = self.inner.delete(con, id).await;
+        self.cache.invalidate(con, Some(id)).await;
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repositories::cache::MemoryCache;
+    use crate::repositories::user_repo::MockUserRepo;
+    use crate::test::db::create_db_con_for_test;
+    use crate::test::fixture::user::user_fixture;
+
+    #[tokio::test]
+    async fn test_find_by_id_is_cached_until_update() {
+        let mut mock_user_repo = MockUserRepo::new();
+        mock_user_repo
+            .expect_find_by_id()
+            .times(2)
+            .returning(|_, id| Ok(Some(user_fixture(id as usize))));
+        mock_user_repo
+            .expect_update()
+            .times(1)
+            .returning(|_, id, _, _| Ok(user_fixture(id as usize)));
+        let store = Arc::new(MemoryCache::new());
+        let repo = CachedUserRepo::new(Box::new(mock_user_repo), store, Duration::from_secs(60));
+        let mut db_con = create_db_con_for_test().await.unwrap();
+
+        repo.find_by_id(&mut db_con, 1).await.unwrap();
+        let cached = repo.find_by_id(&mut db_con, 1).await.unwrap();
+        assert_eq!(cached.unwrap().id, 1);
+
+        let name = "jiro".to_string();
+        repo.update(&mut db_con, 1, &name, 20).await.unwrap();
+        repo.find_by_id(&mut db_con, 1).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_find_page_is_invalidated_by_create() {
+        let mut mock_user_repo = MockUserRepo::new();
+        mock_user_repo
+            .expect_find_page()
+            .times(2)
+            .returning(|_, _, _| {
+                Ok(Page {
+                    items: vec![user_fixture(1)],
+                    next_cursor: None,
+                    total: None,
+                })
+            });
+        mock_user_repo
+            .expect_create()
+            .times(1)
+            .returning(|_, _, _| Ok(user_fixture(2)));
+        let store = Arc::new(MemoryCache::new());
+        let repo = CachedUserRepo::new(Box::new(mock_user_repo), store, Duration::from_secs(60));
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let filter = UserFilter::default();
+        let page = PageRequest::default();
+
+        repo.find_page(&mut db_con, &filter, &page).await.unwrap();
+        repo.find_page(&mut db_con, &filter, &page).await.unwrap();
+
+        let name = "jiro".to_string();
+        repo.create(&mut db_con, &name, 20).await.unwrap();
+        repo.find_page(&mut db_con, &filter, &page).await.unwrap();
+    }
+}
@@ -0,0 +1,335 @@
+use crate::log_into;
+use crate::models::cart_model::{Cart, CartLine, CartOwner};
+use crate::repositories::error::DbRepoError;
+use mockall::automock;
+use sqlx::{query, query_as, PgConnection};
+use tracing::instrument;
+
+pub struct CartRepoImpl {}
+
+impl CartRepoImpl {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+#[automock]
+#[async_trait]
+pub trait CartRepo: Send + Sync {
+    async fn find(
+        &self,
+        con: &mut PgConnection,
+        owner: &CartOwner,
+    ) -> Result<Option<Cart>, DbRepoError>;
+
+    async fn find_or_create(
+        &self,
+        con: &mut PgConnection,
+        owner: &CartOwner,
+    ) -> Result<Cart, DbRepoError>;
+
+    async fn find_lines(
+        &self,
+        con: &mut PgConnection,
+        cart_id: i32,
+    ) -> Result<Vec<CartLine>, DbRepoError>;
+
+    async fn add_item(
+        &self,
+        con: &mut PgConnection,
+        cart_id: i32,
+        product_id: i32,
+        quantity: i32,
+    ) -> Result<(), DbRepoError>;
+
+    async fn set_quantity(
+        &self,
+        con: &mut PgConnection,
+        cart_id: i32,
+        product_id: i32,
+        quantity: i32,
+    ) -> Result<(), DbRepoError>;
+
+    async fn remove_item(
+        &self,
+        con: &mut PgConnection,
+        cart_id: i32,
+        product_id: i32,
+    ) -> Result<(), DbRepoError>;
+
+    async fn merge(
+        &self,
+        con: &mut PgConnection,
+        from_cart_id: i32,
+        into_cart_id: i32,
+    ) -> Result<(), DbRepoError>;
+
+    async fn delete(&self, con: &mut PgConnection, cart_id: i32) -> Result<(), DbRepoError>;
+}
+
+#[async_trait]
+impl CartRepo for CartRepoImpl {
+    #[instrument(name = "cart_repo/find", skip_all)]
+    async fn find(
+        &self,
+        con: &mut PgConnection,
+        owner: &CartOwner,
+    ) -> Result<Option<Cart>, DbRepoError> {
+        let result = match owner {
+            CartOwner::User(user_id) => {
+                query_as!(Cart, "SELECT * FROM carts WHERE user_id = $1", user_id)
+                    .fetch_optional(&mut *con)
+                    .await
+            }
+            CartOwner::Anonymous(token) => {
+                query_as!(Cart, "SELECT * FROM carts WHERE token = $1", token)
+                    .fetch_optional(&mut *con)
+                    .await
+            }
+        };
+        result.map_err(|e| log_into!(e, DbRepoError))
+    }
+
+    // 同時に作ろうとしても一意制約で1つにまとまるように、ON CONFLICTで既存の行を返す
+    #[instrument(name = "cart_repo/find_or_create", skip_all)]
+    async fn find_or_create(
+        &self,
+        con: &mut PgConnection,
+        owner: &CartOwner,
+    ) -> Result<Cart, DbRepoError> {
+        let result = match owner {
+            CartOwner::User(user_id) => {
+                query_as!(
+                    Cart,
+                    "INSERT INTO carts (user_id) VALUES ($1) \
+                     ON CONFLICT (user_id) DO UPDATE SET user_id = EXCLUDED.user_id RETURNING *",
+                    user_id
+                )
+                .fetch_one(&mut *con)
+                .await
+            }
+            CartOwner::Anonymous(token) => {
+                query_as!(
+                    Cart,
+                    "INSERT INTO carts (token) VALUES ($1) \
+                     ON CONFLICT (token) DO UPDATE SET token = EXCLUDED.token RETURNING *",
+                    token
+                )
+                .fetch_one(&mut *con)
+                .await
+            }
+        };
+        result.map_err(|e| log_into!(e, DbRepoError))
+    }
+
+    #[instrument(name = "cart_repo/find_lines", skip_all, fields(cart_id = %cart_id))]
+    async fn find_lines(
+        &self,
+        con: &mut PgConnection,
+        cart_id: i32,
+    ) -> Result<Vec<CartLine>, DbRepoError> {
+        query_as!(
+            CartLine,
+            r#"SELECT p.id AS product_id, p.name, p.sku, p.price AS unit_price, p.currency,
+                      c.quantity, p.price * c.quantity AS "subtotal!"
+               FROM cart_items c JOIN products p ON p.id = c.product_id
+               WHERE c.cart_id = $1 ORDER BY p.id"#,
+            cart_id
+        )
+        .fetch_all(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+
+    #[instrument(name = "cart_repo/add_item", skip_all, fields(cart_id = %cart_id, product_id = %product_id))]
+    async fn add_item(
+        &self,
+        con: &mut PgConnection,
+        cart_id: i32,
+        product_id: i32,
+        quantity: i32,
+    ) -> Result<(), DbRepoError> {
+        query!(
+            "INSERT INTO cart_items (cart_id, product_id, quantity) VALUES ($1, $2, $3) \
+             ON CONFLICT (cart_id, product_id) DO UPDATE SET quantity = cart_items.quantity + EXCLUDED.quantity",
+            cart_id,
+            product_id,
+            quantity
+        )
+        .execute(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))?;
+        Ok(())
+    }
+
+    #[instrument(name = "cart_repo/set_quantity", skip_all, fields(cart_id = %cart_id, product_id = %product_id))]
+    async fn set_quantity(
+        &self,
+        con: &mut PgConnection,
+        cart_id: i32,
+        product_id: i32,
+        quantity: i32,
+    ) -> Result<(), DbRepoError> {
+        let result = query!(
+            "UPDATE cart_items SET quantity = $3 WHERE cart_id = $1 AND product_id = $2",
+            cart_id,
+            product_id,
+            quantity
+        )
+        .execute(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))?;
+        if result.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound.into());
+        }
+        Ok(())
+    }
+
+    #[instrument(name = "cart_repo/remove_item", skip_all, fields(cart_id = %cart_id, product_id = %product_id))]
+    async fn remove_item(
+        &self,
+        con: &mut PgConnection,
+        cart_id: i32,
+        product_id: i32,
+    ) -> Result<(), DbRepoError> {
+        let result = query!(
+            "DELETE FROM cart_items WHERE cart_id = $1 AND product_id = $2",
+            cart_id,
+            product_id
+        )
+        .execute(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))?;
+        if result.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound.into());
+        }
+        Ok(())
+    }
+
+    // 明細を移し(同じ商品は数量を足す)、移し元のカートを削除する
+    #[instrument(name = "cart_repo/merge", skip_all, fields(from = %from_cart_id, into = %into_cart_id))]
+    async fn merge(
+        &self,
+        con: &mut PgConnection,
+        from_cart_id: i32,
+        into_cart_id: i32,
+    ) -> Result<(), DbRepoError> {
+        query!(
+            "INSERT INTO cart_items (cart_id, product_id, quantity) \
+             SELECT $2, product_id, quantity FROM cart_items WHERE cart_id = $1 \
+             ON CONFLICT (cart_id, product_id) DO UPDATE SET quantity = cart_items.quantity + EXCLUDED.quantity",
+            from_cart_id,
+            into_cart_id
+        )
+        .execute(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))?;
+        self.delete(con, from_cart_id).await
+    }
+
+    #[instrument(name = "cart_repo/delete", skip_all, fields(cart_id = %cart_id))]
+    async fn delete(&self, con: &mut PgConnection, cart_id: i32) -> Result<(), DbRepoError> {
+        query!("DELETE FROM carts WHERE id = $1", cart_id)
+            .execute(&mut *con)
+            .await
+            .map_err(|e| log_into!(e, DbRepoError))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::db::create_tx_for_test;
+    use crate::test::repositories::prepare::product::create_product;
+    use crate::test::repositories::prepare::user::create_user;
+    use uuid::Uuid;
+
+    #[rocket::async_test]
+    async fn test_find_or_create_returns_the_same_cart() {
+        let mut tx = create_tx_for_test().await.unwrap();
+        let user = create_user(&mut tx).await.unwrap();
+        let repo = CartRepoImpl::new();
+        let owner = CartOwner::User(user.id);
+        assert_eq!(repo.find(&mut tx, &owner).await.unwrap(), None);
+        let cart = repo.find_or_create(&mut tx, &owner).await.unwrap();
+        assert_eq!(cart.user_id, Some(user.id));
+        assert_eq!(repo.find_or_create(&mut tx, &owner).await.unwrap(), cart);
+        assert_eq!(
+            repo.find(&mut tx, &owner).await.unwrap(),
+            Some(cart.clone())
+        );
+
+        let owner = CartOwner::Anonymous(Uuid::new_v4());
+        let cart = repo.find_or_create(&mut tx, &owner).await.unwrap();
+        assert_eq!(repo.find(&mut tx, &owner).await.unwrap(), Some(cart));
+        tx.rollback().await.unwrap();
+    }
+
+    #[rocket::async_test]
+    async fn test_items() {
+        let mut tx = create_tx_for_test().await.unwrap();
+        let product = create_product(&mut tx).await.unwrap();
+        let repo = CartRepoImpl::new();
+        let cart = repo
+            .find_or_create(&mut tx, &CartOwner::Anonymous(Uuid::new_v4()))
+            .await
+            .unwrap();
+        repo.add_item(&mut tx, cart.id, product.id, 2)
+            .await
+            .unwrap();
+        repo.add_item(&mut tx, cart.id, product.id, 1)
+            .await
+            .unwrap();
+        let lines = repo.find_lines(&mut tx, cart.id).await.unwrap();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].quantity, 3);
+        assert_eq!(lines[0].subtotal, product.price * 3);
+
+        repo.set_quantity(&mut tx, cart.id, product.id, 5)
+            .await
+            .unwrap();
+        let lines = repo.find_lines(&mut tx, cart.id).await.unwrap();
+        assert_eq!(lines[0].quantity, 5);
+
+        repo.remove_item(&mut tx, cart.id, product.id)
+            .await
+            .unwrap();
+        assert!(repo.find_lines(&mut tx, cart.id).await.unwrap().is_empty());
+        let result = repo.remove_item(&mut tx, cart.id, product.id).await;
+        assert!(result.unwrap_err().is_row_not_found());
+        let result = repo.set_quantity(&mut tx, cart.id, product.id, 1).await;
+        assert!(result.unwrap_err().is_row_not_found());
+        tx.rollback().await.unwrap();
+    }
+
+    #[rocket::async_test]
+    async fn test_merge() {
+        let mut tx = create_tx_for_test().await.unwrap();
+        let user = create_user(&mut tx).await.unwrap();
+        let apple = create_product(&mut tx).await.unwrap();
+        let orange = create_product(&mut tx).await.unwrap();
+        let repo = CartRepoImpl::new();
+        let anonymous = CartOwner::Anonymous(Uuid::new_v4());
+        let from = repo.find_or_create(&mut tx, &anonymous).await.unwrap();
+        repo.add_item(&mut tx, from.id, apple.id, 1).await.unwrap();
+        repo.add_item(&mut tx, from.id, orange.id, 2).await.unwrap();
+        let into = repo
+            .find_or_create(&mut tx, &CartOwner::User(user.id))
+            .await
+            .unwrap();
+        repo.add_item(&mut tx, into.id, apple.id, 3).await.unwrap();
+
+        repo.merge(&mut tx, from.id, into.id).await.unwrap();
+        let quantities: Vec<(i32, i32)> = repo
+            .find_lines(&mut tx, into.id)
+            .await
+            .unwrap()
+            .iter()
+            .map(|line| (line.product_id, line.quantity))
+            .collect();
+        assert_eq!(quantities, vec![(apple.id, 4), (orange.id, 2)]);
+        assert_eq!(repo.find(&mut tx, &anonymous).await.unwrap(), None);
+        tx.rollback().await.unwrap();
+    }
+}
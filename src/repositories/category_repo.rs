@@ -0,0 +1,136 @@
+use crate::dto::category_dto::CategoryInput;
+use crate::log_into;
+use crate::models::category_model::Category;
+use crate::repositories::error::DbRepoError;
+use mockall::automock;
+use sqlx::{query_as, PgConnection};
+use tracing::instrument;
+
+pub struct CategoryRepoImpl {}
+
+impl CategoryRepoImpl {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+#[automock]
+#[async_trait]
+pub trait CategoryRepo: Send + Sync {
+    async fn create(
+        &self,
+        con: &mut PgConnection,
+        input: &CategoryInput,
+    ) -> Result<Category, DbRepoError>;
+
+    async fn find_all(&self, con: &mut PgConnection) -> Result<Vec<Category>, DbRepoError>;
+
+    async fn find_by_id(
+        &self,
+        con: &mut PgConnection,
+        id: i32,
+    ) -> Result<Option<Category>, DbRepoError>;
+
+    async fn find_by_ids(
+        &self,
+        con: &mut PgConnection,
+        ids: &[i32],
+    ) -> Result<Vec<Category>, DbRepoError>;
+}
+
+#[async_trait]
+impl CategoryRepo for CategoryRepoImpl {
+    #[instrument(name = "category_repo/create", skip_all)]
+    async fn create(
+        &self,
+        con: &mut PgConnection,
+        input: &CategoryInput,
+    ) -> Result<Category, DbRepoError> {
+        query_as!(
+            Category,
+            "INSERT INTO categories (name, parent_id) VALUES ($1, $2) RETURNING *",
+            input.name,
+            input.parent_id
+        )
+        .fetch_one(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+
+    #[instrument(name = "category_repo/find_all", skip_all)]
+    async fn find_all(&self, con: &mut PgConnection) -> Result<Vec<Category>, DbRepoError> {
+        query_as!(Category, "SELECT * FROM categories ORDER BY id")
+            .fetch_all(&mut *con)
+            .await
+            .map_err(|e| log_into!(e, DbRepoError))
+    }
+
+    #[instrument(name = "category_repo/find_by_id", skip_all, fields(id = %id))]
+    async fn find_by_id(
+        &self,
+        con: &mut PgConnection,
+        id: i32,
+    ) -> Result<Option<Category>, DbRepoError> {
+        query_as!(Category, "SELECT * FROM categories WHERE id = $1", id)
+            .fetch_optional(&mut *con)
+            .await
+            .map_err(|e| log_into!(e, DbRepoError))
+    }
+
+    #[instrument(name = "category_repo/find_by_ids", skip_all, fields(ids = ids.len()))]
+    async fn find_by_ids(
+        &self,
+        con: &mut PgConnection,
+        ids: &[i32],
+    ) -> Result<Vec<Category>, DbRepoError> {
+        query_as!(
+            Category,
+            "SELECT * FROM categories WHERE id = ANY($1) ORDER BY id",
+            ids
+        )
+        .fetch_all(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::db::create_tx_for_test;
+
+    #[rocket::async_test]
+    async fn test_create_and_find() {
+        let mut tx = create_tx_for_test().await.unwrap();
+        let repo = CategoryRepoImpl::new();
+        let fruit = repo
+            .create(
+                &mut tx,
+                &CategoryInput {
+                    name: "fruit".to_string(),
+                    parent_id: None,
+                },
+            )
+            .await
+            .unwrap();
+        let citrus = repo
+            .create(
+                &mut tx,
+                &CategoryInput {
+                    name: "citrus".to_string(),
+                    parent_id: Some(fruit.id),
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(citrus.parent_id, Some(fruit.id));
+        let found = repo.find_by_id(&mut tx, citrus.id).await.unwrap();
+        assert_eq!(found, Some(citrus.clone()));
+        let found = repo
+            .find_by_ids(&mut tx, &[citrus.id, fruit.id, -1])
+            .await
+            .unwrap();
+        assert_eq!(found, vec![fruit, citrus]);
+        tx.rollback().await.unwrap();
+    }
+}
@@ -0,0 +1,228 @@
+use crate::log_into;
+use crate::models::credential_model::Credential;
+use crate::repositories::error::DbRepoError;
+use mockall::automock;
+use sqlx::{query_as, query_scalar, PgConnection};
+use tracing::instrument;
+
+pub struct CredentialRepoImpl {}
+
+impl CredentialRepoImpl {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+#[automock]
+#[async_trait]
+pub trait CredentialRepo: Send + Sync {
+    async fn create(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+        email: &str,
+        password_hash: &str,
+    ) -> Result<Credential, DbRepoError>;
+
+    // 大文字小文字を区別せずに探す
+    async fn find_by_email(
+        &self,
+        con: &mut PgConnection,
+        email: &str,
+    ) -> Result<Option<Credential>, DbRepoError>;
+
+    async fn find_by_user_id(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+    ) -> Result<Option<Credential>, DbRepoError>;
+
+    // セッションの世代を進め、新しい世代を返す。資格情報の無いユーザーはNone
+    async fn bump_session_epoch(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+    ) -> Result<Option<i32>, DbRepoError>;
+
+    // パスワードを変えるときはセッションの世代も進め、新しい世代を返す
+    async fn update_password(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+        password_hash: &str,
+    ) -> Result<Option<i32>, DbRepoError>;
+}
+
+#[async_trait]
+impl CredentialRepo for CredentialRepoImpl {
+    #[instrument(name = "credential_repo/create", skip_all, fields(user_id = %user_id))]
+    async fn create(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+        email: &str,
+        password_hash: &str,
+    ) -> Result<Credential, DbRepoError> {
+        query_as!(
+            Credential,
+            "INSERT INTO user_credentials (user_id, email, password_hash) VALUES ($1, $2, $3) RETURNING *",
+            user_id,
+            email,
+            password_hash
+        )
+        .fetch_one(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+
+    #[instrument(name = "credential_repo/find_by_email", skip_all)]
+    async fn find_by_email(
+        &self,
+        con: &mut PgConnection,
+        email: &str,
+    ) -> Result<Option<Credential>, DbRepoError> {
+        query_as!(
+            Credential,
+            "SELECT * FROM user_credentials WHERE lower(email) = lower($1)",
+            email
+        )
+        .fetch_optional(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+
+    #[instrument(name = "credential_repo/find_by_user_id", skip_all, fields(user_id = %user_id))]
+    async fn find_by_user_id(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+    ) -> Result<Option<Credential>, DbRepoError> {
+        query_as!(
+            Credential,
+            "SELECT * FROM user_credentials WHERE user_id = $1",
+            user_id
+        )
+        .fetch_optional(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+
+    #[instrument(name = "credential_repo/bump_session_epoch", skip_all, fields(user_id = %user_id))]
+    async fn bump_session_epoch(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+    ) -> Result<Option<i32>, DbRepoError> {
+        query_scalar!(
+            "UPDATE user_credentials SET session_epoch = session_epoch + 1 WHERE user_id = $1 RETURNING session_epoch",
+            user_id
+        )
+        .fetch_optional(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+
+    #[instrument(name = "credential_repo/update_password", skip_all, fields(user_id = %user_id))]
+    async fn update_password(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+        password_hash: &str,
+    ) -> Result<Option<i32>, DbRepoError> {
+        query_scalar!(
+            "UPDATE user_credentials SET password_hash = $2, session_epoch = session_epoch + 1 WHERE user_id = $1 RETURNING session_epoch",
+            user_id,
+            password_hash
+        )
+        .fetch_optional(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::repositories::credential_repo::{CredentialRepo, CredentialRepoImpl};
+    use crate::test::db::create_db_con_for_test;
+    use crate::test::repositories::prepare::user::create_user;
+    use sqlx::Connection;
+
+    #[tokio::test]
+    async fn test_create_and_find_by_email() {
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+        let user = create_user(&mut tx).await.unwrap();
+        let repo = CredentialRepoImpl::new();
+        repo.create(&mut tx, user.id, "fer@example.com", "hash")
+            .await
+            .unwrap();
+        let result = repo.find_by_email(&mut tx, "fer@example.com").await;
+        assert_eq!(result.unwrap().unwrap().user_id, user.id);
+        tx.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_create_duplicate_email() {
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+        let first = create_user(&mut tx).await.unwrap();
+        let second = create_user(&mut tx).await.unwrap();
+        let repo = CredentialRepoImpl::new();
+        repo.create(&mut tx, first.id, "dup@example.com", "hash")
+            .await
+            .unwrap();
+        // 大文字小文字だけが違うメールアドレスも重複になる
+        // 失敗したトランザクションは続けて使えないので、それぞれセーブポイントの中で試す
+        for email in ["dup@example.com", "Dup@Example.com"] {
+            let mut savepoint = tx.begin().await.unwrap();
+            let result = repo.create(&mut savepoint, second.id, email, "hash").await;
+            assert!(result.unwrap_err().is_unique_violation());
+            savepoint.rollback().await.unwrap();
+        }
+        tx.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_find_by_email_ignores_case() {
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+        let user = create_user(&mut tx).await.unwrap();
+        let repo = CredentialRepoImpl::new();
+        repo.create(&mut tx, user.id, "Case@Example.com", "hash")
+            .await
+            .unwrap();
+        let result = repo.find_by_email(&mut tx, "case@example.COM").await;
+        assert_eq!(result.unwrap().unwrap().user_id, user.id);
+        tx.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_session_epoch() {
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+        let user = create_user(&mut tx).await.unwrap();
+        let repo = CredentialRepoImpl::new();
+        let credential = repo
+            .create(&mut tx, user.id, "epoch@example.com", "hash")
+            .await
+            .unwrap();
+        assert_eq!(credential.session_epoch, 0);
+        let epoch = repo.bump_session_epoch(&mut tx, user.id).await.unwrap();
+        assert_eq!(epoch, Some(1));
+        let epoch = repo
+            .update_password(&mut tx, user.id, "new hash")
+            .await
+            .unwrap();
+        assert_eq!(epoch, Some(2));
+        let credential = repo
+            .find_by_user_id(&mut tx, user.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(credential.password_hash, "new hash");
+        assert_eq!(credential.session_epoch, 2);
+        let epoch = repo.bump_session_epoch(&mut tx, -1).await.unwrap();
+        assert_eq!(epoch, None);
+        tx.rollback().await.unwrap();
+    }
+}
@@ -11,3 +11,29 @@ pub enum DbRepoError {
     #[error("Dummy error for testing")]
     DummyTestError,
 }
+
+impl DbRepoError {
+    pub fn is_row_not_found(&self) -> bool {
+        matches!(self, DbRepoError::SqlxError(sqlx::Error::RowNotFound))
+    }
+
+    // 23505: unique_violation
+    pub fn is_unique_violation(&self) -> bool {
+        match self {
+            DbRepoError::SqlxError(sqlx::Error::Database(e)) => {
+                e.code().as_deref() == Some("23505")
+            }
+            _ => false,
+        }
+    }
+
+    // 23503: foreign_key_violation
+    pub fn is_foreign_key_violation(&self) -> bool {
+        match self {
+            DbRepoError::SqlxError(sqlx::Error::Database(e)) => {
+                e.code().as_deref() == Some("23503")
+            }
+            _ => false,
+        }
+    }
+}
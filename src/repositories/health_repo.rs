@@ -0,0 +1,102 @@
+use crate::log_into;
+use crate::migration::MIGRATOR;
+use crate::repositories::cache::{CacheError, CacheStore};
+use crate::repositories::error::DbRepoError;
+use mockall::automock;
+use sqlx::{query_scalar, PgConnection};
+use std::sync::Arc;
+use tracing::instrument;
+
+pub struct HealthRepoImpl {
+    redis: Option<Arc<dyn CacheStore>>,
+}
+
+impl HealthRepoImpl {
+    // redisはRedisを使うように設定されている場合だけ渡す
+    pub fn new(redis: Option<Arc<dyn CacheStore>>) -> Self {
+        Self { redis }
+    }
+}
+
+#[automock]
+#[async_trait]
+pub trait HealthRepo: Send + Sync {
+    async fn ping(&self, con: &mut PgConnection) -> Result<(), DbRepoError>;
+
+    // バイナリに埋め込まれたマイグレーションのうち、DBに適用されていないもののversion
+    async fn pending_migrations(&self, con: &mut PgConnection) -> Result<Vec<i64>, DbRepoError>;
+
+    // Redisを使っていない場合はNone
+    async fn ping_redis(&self) -> Option<Result<(), CacheError>>;
+}
+
+#[async_trait]
+impl HealthRepo for HealthRepoImpl {
+    #[instrument(name = "health_repo/ping", skip_all)]
+    async fn ping(&self, con: &mut PgConnection) -> Result<(), DbRepoError> {
+        query_scalar!("SELECT 1")
+            .fetch_one(&mut *con)
+            .await
+            .map_err(|e| log_into!(e, DbRepoError))?;
+        Ok(())
+    }
+
+    #[instrument(name = "health_repo/pending_migrations", skip_all)]
+    async fn pending_migrations(&self, con: &mut PgConnection) -> Result<Vec<i64>, DbRepoError> {
+        let applied = query_scalar!("SELECT version FROM _sqlx_migrations WHERE success")
+            .fetch_all(&mut *con)
+            .await
+            .map_err(|e| log_into!(e, DbRepoError))?;
+        let pending = MIGRATOR
+            .iter()
+            .filter(|migration| !migration.migration_type.is_down_migration())
+            .map(|migration| migration.version)
+            .filter(|version| !applied.contains(version))
+            .collect();
+        Ok(pending)
+    }
+
+    #[instrument(name = "health_repo/ping_redis", skip_all)]
+    async fn ping_redis(&self) -> Option<Result<(), CacheError>> {
+        let redis = self.redis.as_ref()?;
+        Some(redis.ping().await.map_err(|e| {
+            tracing::warn!("{} ({}:{})", e, file!(), line!());
+            e
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::repositories::cache::MemoryCache;
+    use crate::repositories::health_repo::{HealthRepo, HealthRepoImpl};
+    use crate::test::db::create_db_con_for_test;
+    use sqlx::{query, Connection};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_ping_and_pending_migrations() {
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+        let repo = HealthRepoImpl::new(None);
+        repo.ping(&mut tx).await.unwrap();
+        assert!(repo.pending_migrations(&mut tx).await.unwrap().is_empty());
+
+        query!("UPDATE _sqlx_migrations SET success = FALSE WHERE version = 20231208000000")
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+        assert_eq!(
+            repo.pending_migrations(&mut tx).await.unwrap(),
+            vec![20231208000000]
+        );
+        tx.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_ping_redis() {
+        assert!(HealthRepoImpl::new(None).ping_redis().await.is_none());
+        let repo = HealthRepoImpl::new(Some(Arc::new(MemoryCache::new())));
+        assert!(matches!(repo.ping_redis().await, Some(Ok(()))));
+    }
+}
@@ -0,0 +1,214 @@
+use crate::log_into;
+use crate::models::order_model::{NewOrderItem, Order, OrderItem};
+use crate::repositories::error::DbRepoError;
+use mockall::automock;
+use sqlx::{query_as, PgConnection};
+use tracing::instrument;
+
+pub struct OrderRepoImpl {}
+
+impl OrderRepoImpl {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+#[automock]
+#[async_trait]
+pub trait OrderRepo: Send + Sync {
+    async fn create(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+        currency: &str,
+        total: i64,
+    ) -> Result<Order, DbRepoError>;
+
+    async fn create_items(
+        &self,
+        con: &mut PgConnection,
+        order_id: i32,
+        items: &[NewOrderItem],
+    ) -> Result<Vec<OrderItem>, DbRepoError>;
+
+    async fn find_by_user(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+    ) -> Result<Vec<Order>, DbRepoError>;
+
+    async fn find_by_id(
+        &self,
+        con: &mut PgConnection,
+        id: i32,
+    ) -> Result<Option<Order>, DbRepoError>;
+
+    async fn find_items(
+        &self,
+        con: &mut PgConnection,
+        order_id: i32,
+    ) -> Result<Vec<OrderItem>, DbRepoError>;
+}
+
+#[async_trait]
+impl OrderRepo for OrderRepoImpl {
+    #[instrument(name = "order_repo/create", skip_all, fields(user_id = %user_id))]
+    async fn create(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+        currency: &str,
+        total: i64,
+    ) -> Result<Order, DbRepoError> {
+        query_as!(
+            Order,
+            "INSERT INTO orders (user_id, currency, total) VALUES ($1, $2, $3) RETURNING *",
+            user_id,
+            currency,
+            total
+        )
+        .fetch_one(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+
+    // 明細は配列にしてUNNESTで1回のINSERTにまとめる
+    #[instrument(name = "order_repo/create_items", skip_all, fields(order_id = %order_id))]
+    async fn create_items(
+        &self,
+        con: &mut PgConnection,
+        order_id: i32,
+        items: &[NewOrderItem],
+    ) -> Result<Vec<OrderItem>, DbRepoError> {
+        let product_ids: Vec<i32> = items.iter().map(|item| item.product_id).collect();
+        let names: Vec<String> = items.iter().map(|item| item.name.clone()).collect();
+        let skus: Vec<String> = items.iter().map(|item| item.sku.clone()).collect();
+        let unit_prices: Vec<i64> = items.iter().map(|item| item.unit_price).collect();
+        let quantities: Vec<i32> = items.iter().map(|item| item.quantity).collect();
+        let subtotals: Vec<i64> = items.iter().map(|item| item.subtotal).collect();
+        query_as!(
+            OrderItem,
+            "INSERT INTO order_items (order_id, product_id, name, sku, unit_price, quantity, subtotal) \
+             SELECT $1, * FROM UNNEST($2::int4[], $3::varchar[], $4::varchar[], $5::int8[], $6::int4[], $7::int8[]) \
+             RETURNING *",
+            order_id,
+            &product_ids,
+            &names,
+            &skus,
+            &unit_prices,
+            &quantities,
+            &subtotals
+        )
+        .fetch_all(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+
+    #[instrument(name = "order_repo/find_by_user", skip_all, fields(user_id = %user_id))]
+    async fn find_by_user(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+    ) -> Result<Vec<Order>, DbRepoError> {
+        query_as!(
+            Order,
+            "SELECT * FROM orders WHERE user_id = $1 ORDER BY id DESC",
+            user_id
+        )
+        .fetch_all(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+
+    #[instrument(name = "order_repo/find_by_id", skip_all, fields(id = %id))]
+    async fn find_by_id(
+        &self,
+        con: &mut PgConnection,
+        id: i32,
+    ) -> Result<Option<Order>, DbRepoError> {
+        query_as!(Order, "SELECT * FROM orders WHERE id = $1", id)
+            .fetch_optional(&mut *con)
+            .await
+            .map_err(|e| log_into!(e, DbRepoError))
+    }
+
+    #[instrument(name = "order_repo/find_items", skip_all, fields(order_id = %order_id))]
+    async fn find_items(
+        &self,
+        con: &mut PgConnection,
+        order_id: i32,
+    ) -> Result<Vec<OrderItem>, DbRepoError> {
+        query_as!(
+            OrderItem,
+            "SELECT * FROM order_items WHERE order_id = $1 ORDER BY id",
+            order_id
+        )
+        .fetch_all(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::db::create_tx_for_test;
+    use crate::test::repositories::prepare::product::create_product;
+    use crate::test::repositories::prepare::user::create_user;
+
+    #[rocket::async_test]
+    async fn test_create_order_with_items() {
+        let mut tx = create_tx_for_test().await.unwrap();
+        let user = create_user(&mut tx).await.unwrap();
+        let product = create_product(&mut tx).await.unwrap();
+        let repo = OrderRepoImpl::new();
+        let order = repo.create(&mut tx, user.id, "JPY", 360).await.unwrap();
+        let items = vec![NewOrderItem {
+            product_id: product.id,
+            name: product.name.clone(),
+            sku: product.sku.clone(),
+            unit_price: 120,
+            quantity: 3,
+            subtotal: 360,
+        }];
+        let created = repo.create_items(&mut tx, order.id, &items).await.unwrap();
+        assert_eq!(created.len(), 1);
+        assert_eq!(created[0].order_id, order.id);
+        assert_eq!(created[0].product_id, Some(product.id));
+        assert_eq!(created[0].subtotal, 360);
+
+        let found = repo.find_by_id(&mut tx, order.id).await.unwrap();
+        assert_eq!(found, Some(order.clone()));
+        let orders = repo.find_by_user(&mut tx, user.id).await.unwrap();
+        assert_eq!(orders, vec![order.clone()]);
+        let found_items = repo.find_items(&mut tx, order.id).await.unwrap();
+        assert_eq!(found_items, created);
+        tx.rollback().await.unwrap();
+    }
+
+    #[rocket::async_test]
+    async fn test_deleted_product_keeps_order_item() {
+        let mut tx = create_tx_for_test().await.unwrap();
+        let user = create_user(&mut tx).await.unwrap();
+        let product = create_product(&mut tx).await.unwrap();
+        let repo = OrderRepoImpl::new();
+        let order = repo.create(&mut tx, user.id, "JPY", 120).await.unwrap();
+        let items = vec![NewOrderItem {
+            product_id: product.id,
+            name: product.name.clone(),
+            sku: product.sku.clone(),
+            unit_price: 120,
+            quantity: 1,
+            subtotal: 120,
+        }];
+        repo.create_items(&mut tx, order.id, &items).await.unwrap();
+        sqlx::query!("DELETE FROM products WHERE id = $1", product.id)
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+        let found_items = repo.find_items(&mut tx, order.id).await.unwrap();
+        assert_eq!(found_items[0].product_id, None);
+        assert_eq!(found_items[0].sku, product.sku);
+        tx.rollback().await.unwrap();
+    }
+}
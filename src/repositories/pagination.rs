@@ -0,0 +1,223 @@
+use crate::models::page_model::Page;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use sqlx::{Postgres, QueryBuilder};
+
+pub const DEFAULT_LIMIT: i64 = 20;
+pub const MAX_LIMIT: i64 = 100;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SortField {
+    pub column: &'static str,
+    pub descending: bool,
+}
+
+// ソートできるカラムの型。カーソルの値がカラムの型と合っているかの確認に使う
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Int,
+    Text,
+}
+
+// カーソルに入れるソートキーの値 (整数カラムと文字列カラムのみ対応)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CursorValue {
+    Int(i64),
+    Text(String),
+}
+
+impl CursorValue {
+    pub fn column_type(&self) -> ColumnType {
+        match self {
+            CursorValue::Int(_) => ColumnType::Int,
+            CursorValue::Text(_) => ColumnType::Text,
+        }
+    }
+}
+
+// 前のページの最後の行のソートキー。クライアントにはbase64の文字列として渡す
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cursor {
+    pub sort: String,
+    pub values: Vec<CursorValue>,
+}
+
+impl Cursor {
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_vec(self).unwrap_or_default();
+        URL_SAFE_NO_PAD.encode(json)
+    }
+
+    pub fn decode(cursor: &str) -> Option<Cursor> {
+        let json = URL_SAFE_NO_PAD.decode(cursor).ok()?;
+        serde_json::from_slice(&json).ok()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PageRequest {
+    pub limit: i64,
+    pub offset: i64,
+    pub sort: Vec<SortField>,
+    pub cursor: Option<Cursor>,
+    pub with_total: bool,
+}
+
+impl Default for PageRequest {
+    fn default() -> Self {
+        Self {
+            limit: DEFAULT_LIMIT,
+            offset: 0,
+            sort: vec![SortField {
+                column: "id",
+                descending: false,
+            }],
+            cursor: None,
+            with_total: false,
+        }
+    }
+}
+
+impl PageRequest {
+    // "name,-id" の形式。カーソルが同じ並び順で作られたかの確認に使う
+    pub fn sort_key(&self) -> String {
+        self.sort
+            .iter()
+            .map(|f| {
+                if f.descending {
+                    format!("-{}", f.column)
+                } else {
+                    f.column.to_string()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    // カーソルより後ろの行だけに絞り込む条件を追加する
+    // (a > $1) OR (a = $1 AND b > $2) OR ...
+    pub fn push_keyset(&self, qb: &mut QueryBuilder<'_, Postgres>) {
+        let cursor = match &self.cursor {
+            Some(cursor) => cursor,
+            None => return,
+        };
+        qb.push(" AND (");
+        for i in 0..self.sort.len() {
+            if i > 0 {
+                qb.push(" OR ");
+            }
+            qb.push("(");
+            for (field, value) in self.sort.iter().zip(&cursor.values).take(i) {
+                qb.push(field.column).push(" = ");
+                push_cursor_value(qb, value);
+                qb.push(" AND ");
+            }
+            let field = &self.sort[i];
+            qb.push(field.column)
+                .push(if field.descending { " < " } else { " > " });
+            push_cursor_value(qb, &cursor.values[i]);
+            qb.push(")");
+        }
+        qb.push(")");
+    }
+
+    // 次のページがあるか判定するために1件多く取得する
+    pub fn push_order_and_limit(&self, qb: &mut QueryBuilder<'_, Postgres>) {
+        qb.push(" ORDER BY ");
+        for (i, field) in self.sort.iter().enumerate() {
+            if i > 0 {
+                qb.push(", ");
+            }
+            qb.push(field.column)
+                .push(if field.descending { " DESC" } else { " ASC" });
+        }
+        qb.push(" LIMIT ").push_bind(self.limit + 1);
+        qb.push(" OFFSET ").push_bind(self.offset);
+    }
+
+    pub fn to_page<T: Serialize>(&self, mut rows: Vec<T>, total: Option<i64>) -> Page<T> {
+        let has_next = rows.len() as i64 > self.limit;
+        rows.truncate(self.limit as usize);
+        let next_cursor = if has_next {
+            rows.last().map(|row| self.cursor_for(row).encode())
+        } else {
+            None
+        };
+        Page {
+            items: rows,
+            next_cursor,
+            total,
+        }
+    }
+
+    fn cursor_for<T: Serialize>(&self, row: &T) -> Cursor {
+        let row = serde_json::to_value(row).unwrap_or_default();
+        let values = self
+            .sort
+            .iter()
+            .map(|field| match &row[field.column] {
+                serde_json::Value::Number(n) => CursorValue::Int(n.as_i64().unwrap_or_default()),
+                serde_json::Value::String(s) => CursorValue::Text(s.clone()),
+                other => CursorValue::Text(other.to_string()),
+            })
+            .collect();
+        Cursor {
+            sort: self.sort_key(),
+            values,
+        }
+    }
+}
+
+fn push_cursor_value(qb: &mut QueryBuilder<'_, Postgres>, value: &CursorValue) {
+    match value {
+        CursorValue::Int(v) => qb.push_bind(*v),
+        CursorValue::Text(v) => qb.push_bind(v.clone()),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn name_desc_id() -> PageRequest {
+        PageRequest {
+            sort: vec![
+                SortField {
+                    column: "name",
+                    descending: true,
+                },
+                SortField {
+                    column: "id",
+                    descending: false,
+                },
+            ],
+            cursor: Some(Cursor {
+                sort: "-name,id".to_string(),
+                values: vec![CursorValue::Text("taro".to_string()), CursorValue::Int(3)],
+            }),
+            ..PageRequest::default()
+        }
+    }
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = name_desc_id().cursor.unwrap();
+        assert_eq!(Cursor::decode(&cursor.encode()), Some(cursor));
+        assert_eq!(Cursor::decode("not a cursor"), None);
+    }
+
+    #[test]
+    fn test_push_keyset_and_order() {
+        let page = name_desc_id();
+        let mut qb = QueryBuilder::<Postgres>::new("SELECT * FROM users WHERE TRUE");
+        page.push_keyset(&mut qb);
+        page.push_order_and_limit(&mut qb);
+        assert_eq!(
+            qb.sql(),
+            "SELECT * FROM users WHERE TRUE AND ((name < $1) OR (name = $2 AND id > $3)) \
+             ORDER BY name DESC, id ASC LIMIT $4 OFFSET $5"
+        );
+    }
+}
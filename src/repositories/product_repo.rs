@@ -1,8 +1,14 @@
+use crate::dto::product_dto::ProductInput;
 use crate::log_into;
-use crate::models::product_model::Product;
+use crate::models::category_model::Category;
+use crate::models::page_model::Page;
+use crate::models::product_model::{normalize_tag, Product, ProductFilter, StockReservation};
+use crate::repositories::cache::ScopedCon;
 use crate::repositories::error::DbRepoError;
+use crate::repositories::filter::escape_like;
+use crate::repositories::pagination::PageRequest;
 use mockall::automock;
-use sqlx::{query, query_as, PgConnection};
+use sqlx::{query, query_as, query_scalar, Postgres, QueryBuilder};
 use tracing::instrument;
 
 pub struct ProductRepoImpl {}
@@ -15,30 +21,107 @@ impl ProductRepoImpl {
 #[automock]
 #[async_trait]
 pub trait ProductRepo: Send + Sync {
-    async fn create(&self, con: &mut PgConnection, name: &String) -> Result<Product, DbRepoError>;
-    async fn find_all(&self, con: &mut PgConnection) -> Result<Vec<Product>, DbRepoError>;
-    async fn find_by_id(
+    async fn create<'a>(
         &self,
-        con: &mut PgConnection,
+        con: &mut ScopedCon<'a>,
+        input: &ProductInput,
+    ) -> Result<Product, DbRepoError>;
+    async fn find_all<'a>(&self, con: &mut ScopedCon<'a>) -> Result<Vec<Product>, DbRepoError>;
+    async fn find_page<'a>(
+        &self,
+        con: &mut ScopedCon<'a>,
+        filter: &ProductFilter,
+        page: &PageRequest,
+    ) -> Result<Page<Product>, DbRepoError>;
+    async fn find_by_id<'a>(
+        &self,
+        con: &mut ScopedCon<'a>,
         id: i32,
     ) -> Result<Option<Product>, DbRepoError>;
-    async fn update(
+    async fn find_by_ids<'a>(
+        &self,
+        con: &mut ScopedCon<'a>,
+        ids: &[i32],
+    ) -> Result<Vec<Product>, DbRepoError>;
+    async fn update<'a>(
         &self,
-        con: &mut PgConnection,
+        con: &mut ScopedCon<'a>,
         id: i32,
-        name: &String,
+        input: &ProductInput,
     ) -> Result<Product, DbRepoError>;
-    async fn delete(&self, con: &mut PgConnection, id: i32) -> Result<(), DbRepoError>;
+    async fn delete<'a>(&self, con: &mut ScopedCon<'a>, id: i32) -> Result<(), DbRepoError>;
+    async fn reserve_stock<'a>(
+        &self,
+        con: &mut ScopedCon<'a>,
+        id: i32,
+        quantity: i32,
+    ) -> Result<StockReservation, DbRepoError>;
+    async fn set_categories<'a>(
+        &self,
+        con: &mut ScopedCon<'a>,
+        id: i32,
+        category_ids: &[i32],
+    ) -> Result<(), DbRepoError>;
+    async fn find_categories<'a>(
+        &self,
+        con: &mut ScopedCon<'a>,
+        id: i32,
+    ) -> Result<Vec<Category>, DbRepoError>;
+    async fn set_tags<'a>(
+        &self,
+        con: &mut ScopedCon<'a>,
+        id: i32,
+        tags: &[String],
+    ) -> Result<(), DbRepoError>;
+    async fn find_tags<'a>(
+        &self,
+        con: &mut ScopedCon<'a>,
+        id: i32,
+    ) -> Result<Vec<String>, DbRepoError>;
+}
+
+// 絞り込み条件をWHERE句に追加する。値はすべてバインドパラメータで渡す
+fn push_filter(qb: &mut QueryBuilder<'_, Postgres>, filter: &ProductFilter) {
+    if let Some(name_like) = &filter.name_like {
+        qb.push(" AND name LIKE ")
+            .push_bind(format!("%{}%", escape_like(name_like)));
+    }
+    // 子孫のカテゴリは再帰CTEでたどる
+    if let Some(category_id) = filter.category_id {
+        qb.push(
+            " AND id IN (SELECT product_id FROM product_categories WHERE category_id IN (\
+             WITH RECURSIVE subtree AS (SELECT id FROM categories WHERE id = ",
+        )
+        .push_bind(category_id)
+        .push(
+            " UNION SELECT c.id FROM categories c JOIN subtree s ON c.parent_id = s.id) \
+             SELECT id FROM subtree))",
+        );
+    }
+    if let Some(tag) = &filter.tag {
+        qb.push(" AND id IN (SELECT product_id FROM product_tags WHERE tag = ")
+            .push_bind(normalize_tag(tag))
+            .push(")");
+    }
 }
 
 #[async_trait]
 impl ProductRepo for ProductRepoImpl {
     #[instrument(name = "product_repo/create", skip_all)]
-    async fn create(&self, con: &mut PgConnection, name: &String) -> Result<Product, DbRepoError> {
+    async fn create<'a>(
+        &self,
+        con: &mut ScopedCon<'a>,
+        input: &ProductInput,
+    ) -> Result<Product, DbRepoError> {
+        let con = con.con();
         query_as!(
             Product,
-            "INSERT INTO products (name) VALUES ($1) RETURNING *",
-            name,
+            "INSERT INTO products (name, sku, price, currency, stock) VALUES ($1, $2, $3, $4, $5) RETURNING *",
+            input.name,
+            input.sku,
+            input.price,
+            input.currency,
+            input.stock,
         )
         .fetch_one(&mut *con)
         .await
@@ -46,7 +129,8 @@ impl ProductRepo for ProductRepoImpl {
     }
 
     #[instrument(name = "product_repo/find_all", skip_all)]
-    async fn find_all(&self, con: &mut PgConnection) -> Result<Vec<Product>, DbRepoError> {
+    async fn find_all<'a>(&self, con: &mut ScopedCon<'a>) -> Result<Vec<Product>, DbRepoError> {
+        let con = con.con();
         let products = query_as!(Product, "SELECT * FROM products")
             .fetch_all(&mut *con)
             .await
@@ -54,52 +138,228 @@ impl ProductRepo for ProductRepoImpl {
         Ok(products)
     }
 
+    #[instrument(name = "product_repo/find_page", skip_all)]
+    async fn find_page<'a>(
+        &self,
+        con: &mut ScopedCon<'a>,
+        filter: &ProductFilter,
+        page: &PageRequest,
+    ) -> Result<Page<Product>, DbRepoError> {
+        let con = con.con();
+        let mut qb = QueryBuilder::new("SELECT * FROM products WHERE TRUE");
+        push_filter(&mut qb, filter);
+        page.push_keyset(&mut qb);
+        page.push_order_and_limit(&mut qb);
+        let products = qb
+            .build_query_as::<Product>()
+            .fetch_all(&mut *con)
+            .await
+            .map_err(|e| log_into!(e, DbRepoError))?;
+        let total = if page.with_total {
+            let mut qb = QueryBuilder::new("SELECT COUNT(*) FROM products WHERE TRUE");
+            push_filter(&mut qb, filter);
+            let (count,) = qb
+                .build_query_as::<(i64,)>()
+                .fetch_one(&mut *con)
+                .await
+                .map_err(|e| log_into!(e, DbRepoError))?;
+            Some(count)
+        } else {
+            None
+        };
+        Ok(page.to_page(products, total))
+    }
+
     #[instrument(name = "product_repo/find_by_id", skip_all, fields(id = %id))]
-    async fn find_by_id(
+    async fn find_by_id<'a>(
         &self,
-        con: &mut PgConnection,
+        con: &mut ScopedCon<'a>,
         id: i32,
     ) -> Result<Option<Product>, DbRepoError> {
+        let con = con.con();
         query_as!(Product, "SELECT * FROM products WHERE id = $1", id)
             .fetch_optional(&mut *con)
             .await
             .map_err(|e| log_into!(e, DbRepoError))
     }
 
+    #[instrument(name = "product_repo/find_by_ids", skip_all, fields(ids = ids.len()))]
+    async fn find_by_ids<'a>(
+        &self,
+        con: &mut ScopedCon<'a>,
+        ids: &[i32],
+    ) -> Result<Vec<Product>, DbRepoError> {
+        let con = con.con();
+        query_as!(
+            Product,
+            "SELECT * FROM products WHERE id = ANY($1) ORDER BY id",
+            ids
+        )
+        .fetch_all(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+
     #[instrument(name = "product_repo/update", skip_all, fields(id = %id))]
-    async fn update(
+    async fn update<'a>(
         &self,
-        con: &mut PgConnection,
+        con: &mut ScopedCon<'a>,
         id: i32,
-        name: &String,
+        input: &ProductInput,
     ) -> Result<Product, DbRepoError> {
+        let con = con.con();
         query_as!(
             Product,
-            "UPDATE products SET name = $1 WHERE id = $2 RETURNING *",
-            name,
+            "UPDATE products SET name = $1, sku = $2, price = $3, currency = $4, stock = $5 WHERE id = $6 RETURNING *",
+            input.name,
+            input.sku,
+            input.price,
+            input.currency,
+            input.stock,
             id
         )
-        .fetch_one(&mut *con)
+        .fetch_optional(&mut *con)
         .await
-        .map_err(|e| log_into!(e, DbRepoError))
+        .map_err(|e| log_into!(e, DbRepoError))?
+        .ok_or_else(|| sqlx::Error::RowNotFound.into())
     }
 
     #[instrument(name = "product_repo/delete", skip_all, fields(id = %id))]
-    async fn delete(&self, con: &mut PgConnection, id: i32) -> Result<(), DbRepoError> {
-        query!("DELETE FROM products WHERE id = $1", id)
+    async fn delete<'a>(&self, con: &mut ScopedCon<'a>, id: i32) -> Result<(), DbRepoError> {
+        let con = con.con();
+        let result = query!("DELETE FROM products WHERE id = $1", id)
             .execute(&mut *con)
             .await
             .map_err(|e| log_into!(e, DbRepoError))?;
+        // 存在しないidは呼び出し側で404にするので、エラーとしてログに出さない
+        if result.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound.into());
+        }
         Ok(())
     }
+
+    // 条件付きのUPDATEで在庫を減らす。同時に更新しようとした側は行ロックを待ってから
+    // WHEREを評価し直すので、最後の1個を2つのリクエストが同時に引き当てることはない
+    #[instrument(name = "product_repo/reserve_stock", skip_all, fields(id = %id, quantity = %quantity))]
+    async fn reserve_stock<'a>(
+        &self,
+        con: &mut ScopedCon<'a>,
+        id: i32,
+        quantity: i32,
+    ) -> Result<StockReservation, DbRepoError> {
+        let con = con.con();
+        let remaining = query_scalar!(
+            "UPDATE products SET stock = stock - $2 WHERE id = $1 AND stock >= $2 RETURNING stock",
+            id,
+            quantity
+        )
+        .fetch_optional(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))?;
+        if let Some(remaining) = remaining {
+            return Ok(StockReservation::Reserved { remaining });
+        }
+        let available = query_scalar!("SELECT stock FROM products WHERE id = $1", id)
+            .fetch_one(&mut *con)
+            .await
+            .map_err(|e| log_into!(e, DbRepoError))?;
+        Ok(StockReservation::Insufficient { available })
+    }
+
+    // 既存の紐付けを消してから入れ直す
+    #[instrument(name = "product_repo/set_categories", skip_all, fields(id = %id))]
+    async fn set_categories<'a>(
+        &self,
+        con: &mut ScopedCon<'a>,
+        id: i32,
+        category_ids: &[i32],
+    ) -> Result<(), DbRepoError> {
+        let con = con.con();
+        query!("DELETE FROM product_categories WHERE product_id = $1", id)
+            .execute(&mut *con)
+            .await
+            .map_err(|e| log_into!(e, DbRepoError))?;
+        query!(
+            "INSERT INTO product_categories (product_id, category_id) \
+             SELECT $1, * FROM UNNEST($2::int4[]) ON CONFLICT DO NOTHING",
+            id,
+            category_ids
+        )
+        .execute(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))?;
+        Ok(())
+    }
+
+    #[instrument(name = "product_repo/find_categories", skip_all, fields(id = %id))]
+    async fn find_categories<'a>(
+        &self,
+        con: &mut ScopedCon<'a>,
+        id: i32,
+    ) -> Result<Vec<Category>, DbRepoError> {
+        let con = con.con();
+        query_as!(
+            Category,
+            "SELECT c.* FROM categories c JOIN product_categories pc ON pc.category_id = c.id \
+             WHERE pc.product_id = $1 ORDER BY c.id",
+            id
+        )
+        .fetch_all(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+
+    #[instrument(name = "product_repo/set_tags", skip_all, fields(id = %id))]
+    async fn set_tags<'a>(
+        &self,
+        con: &mut ScopedCon<'a>,
+        id: i32,
+        tags: &[String],
+    ) -> Result<(), DbRepoError> {
+        let con = con.con();
+        query!("DELETE FROM product_tags WHERE product_id = $1", id)
+            .execute(&mut *con)
+            .await
+            .map_err(|e| log_into!(e, DbRepoError))?;
+        query!(
+            "INSERT INTO product_tags (product_id, tag) \
+             SELECT $1, * FROM UNNEST($2::varchar[]) ON CONFLICT DO NOTHING",
+            id,
+            tags
+        )
+        .execute(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))?;
+        Ok(())
+    }
+
+    #[instrument(name = "product_repo/find_tags", skip_all, fields(id = %id))]
+    async fn find_tags<'a>(
+        &self,
+        con: &mut ScopedCon<'a>,
+        id: i32,
+    ) -> Result<Vec<String>, DbRepoError> {
+        let con = con.con();
+        query_scalar!(
+            "SELECT tag FROM product_tags WHERE product_id = $1 ORDER BY tag",
+            id
+        )
+        .fetch_all(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::models::product_model::StockReservation;
     use crate::repositories::product_repo::{ProductRepo, ProductRepoImpl};
     use crate::test::db::create_db_con_for_test;
+    use crate::test::fixture::product::product_input_fixture;
+    use crate::test::log::LogBuffer;
     use crate::test::repositories::prepare::product::create_product;
     use sqlx::Connection;
+    use tracing_subscriber::layer::SubscriberExt;
 
     #[tokio::test]
     async fn test_create_product() {
@@ -121,15 +381,48 @@ mod tests {
         tx.rollback().await.unwrap();
     }
 
+    #[tokio::test]
+    async fn test_find_products_by_ids() {
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+        let first = create_product(&mut tx).await.unwrap();
+        let second = create_product(&mut tx).await.unwrap();
+        let repo = ProductRepoImpl::new();
+        let result = repo
+            .find_by_ids(&mut tx, &[second.id, first.id, -1])
+            .await
+            .unwrap();
+        let ids: Vec<i32> = result.iter().map(|product| product.id).collect();
+        assert_eq!(ids, vec![first.id, second.id]);
+        tx.rollback().await.unwrap();
+    }
+
     #[tokio::test]
     async fn test_update_product() {
         let mut db_con = create_db_con_for_test().await.unwrap();
         let mut tx = db_con.begin().await.unwrap();
         let product = create_product(&mut tx).await.unwrap();
         let repo = ProductRepoImpl::new();
-        let new_name = "new_name".to_string();
-        let result = repo.update(&mut tx, product.id, &new_name).await;
-        assert!(result.is_ok());
+        let mut input = product_input_fixture();
+        input.name = "new_name".to_string();
+        input.price = 250;
+        let result = repo.update(&mut tx, product.id, &input).await.unwrap();
+        assert_eq!(result.name, "new_name");
+        assert_eq!(result.sku, input.sku);
+        assert_eq!(result.price, 250);
+        tx.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_create_product_duplicate_sku() {
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+        let product = create_product(&mut tx).await.unwrap();
+        let repo = ProductRepoImpl::new();
+        let mut input = product_input_fixture();
+        input.sku = product.sku;
+        let result = repo.create(&mut tx, &input).await;
+        assert!(result.unwrap_err().is_unique_violation());
         tx.rollback().await.unwrap();
     }
 
@@ -143,4 +436,53 @@ mod tests {
         assert!(result.is_ok());
         tx.rollback().await.unwrap();
     }
+
+    #[tokio::test]
+    async fn test_reserve_stock() {
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+        let product = create_product(&mut tx).await.unwrap();
+        let repo = ProductRepoImpl::new();
+        let result = repo.reserve_stock(&mut tx, product.id, 7).await.unwrap();
+        assert_eq!(result, StockReservation::Reserved { remaining: 3 });
+        let result = repo.reserve_stock(&mut tx, product.id, 4).await.unwrap();
+        assert_eq!(result, StockReservation::Insufficient { available: 3 });
+        let result = repo.reserve_stock(&mut tx, -1, 1).await;
+        assert!(result.unwrap_err().is_row_not_found());
+        tx.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_delete_product_not_found() {
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+        let product = create_product(&mut tx).await.unwrap();
+        let repo = ProductRepoImpl::new();
+        repo.delete(&mut tx, product.id).await.unwrap();
+        let result = repo.delete(&mut tx, product.id).await;
+        assert!(result.unwrap_err().is_row_not_found());
+        tx.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_missing_product_is_not_logged_as_error() {
+        let buffer = LogBuffer::default();
+        let writer = buffer.clone();
+        let layer = tracing_subscriber::fmt::layer().with_writer(move || writer.clone());
+        let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(layer));
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+        let repo = ProductRepoImpl::new();
+
+        let result = repo.update(&mut tx, -1, &product_input_fixture()).await;
+        assert!(result.unwrap_err().is_row_not_found());
+        let result = repo.delete(&mut tx, -1).await;
+        assert!(result.unwrap_err().is_row_not_found());
+        assert!(
+            !buffer.contents().contains("ERROR"),
+            "{}",
+            buffer.contents()
+        );
+        tx.rollback().await.unwrap();
+    }
 }
@@ -1,15 +1,85 @@
+use crate::config::CacheConfig;
 use crate::repositories::{
+    api_key_repo::{ApiKeyRepo, ApiKeyRepoImpl},
+    cache::{CacheStore, MemoryCache, RedisCache},
+    cached_product_repo::CachedProductRepo,
+    cached_user_repo::CachedUserRepo,
+    cart_repo::{CartRepo, CartRepoImpl},
+    category_repo::{CategoryRepo, CategoryRepoImpl},
+    credential_repo::{CredentialRepo, CredentialRepoImpl},
+    health_repo::{HealthRepo, HealthRepoImpl},
+    order_repo::{OrderRepo, OrderRepoImpl},
     product_repo::{ProductRepo, ProductRepoImpl},
+    role_repo::{RoleRepo, RoleRepoImpl},
     user_repo::{UserRepo, UserRepoImpl},
 };
+use std::sync::Arc;
+use std::time::Duration;
+
+const DEFAULT_CACHE_TTL: u64 = 60;
 
 pub struct Repos {
     pub user: Box<dyn UserRepo>,
     pub product: Box<dyn ProductRepo>,
+    pub category: Box<dyn CategoryRepo>,
+    pub credential: Box<dyn CredentialRepo>,
+    pub role: Box<dyn RoleRepo>,
+    pub api_key: Box<dyn ApiKeyRepo>,
+    pub order: Box<dyn OrderRepo>,
+    pub cart: Box<dyn CartRepo>,
+    pub health: Box<dyn HealthRepo>,
+}
+
+// cacheの設定があればリポジトリをキャッシュのデコレータで包む
+pub fn create_repos(cache: Option<&CacheConfig>) -> Repos {
+    let user: Box<dyn UserRepo> = Box::new(UserRepoImpl::new());
+    let product: Box<dyn ProductRepo> = Box::new(ProductRepoImpl::new());
+    let category = Box::new(CategoryRepoImpl::new());
+    let credential = Box::new(CredentialRepoImpl::new());
+    let role = Box::new(RoleRepoImpl::new());
+    let api_key = Box::new(ApiKeyRepoImpl::new());
+    let order = Box::new(OrderRepoImpl::new());
+    let cart = Box::new(CartRepoImpl::new());
+    let redis = cache.and_then(create_redis_cache);
+    let health = Box::new(HealthRepoImpl::new(redis.clone()));
+    match cache {
+        Some(config) => {
+            let store = redis.unwrap_or_else(|| Arc::new(MemoryCache::new()));
+            let ttl = Duration::from_secs(config.ttl.unwrap_or(DEFAULT_CACHE_TTL));
+            Repos {
+                user: Box::new(CachedUserRepo::new(user, store.clone(), ttl)),
+                product: Box::new(CachedProductRepo::new(product, store, ttl)),
+                category,
+                credential,
+                role,
+                api_key,
+                order,
+                cart,
+                health,
+            }
+        }
+        None => Repos {
+            user,
+            product,
+            category,
+            credential,
+            role,
+            api_key,
+            order,
+            cart,
+            health,
+        },
+    }
 }
 
-pub fn create_repos() -> Repos {
-    let user = Box::new(UserRepoImpl::new());
-    let product = Box::new(ProductRepoImpl::new());
-    Repos { user, product }
+// redis_urlが無いか不正な場合はNoneを返し、プロセス内キャッシュを使う
+fn create_redis_cache(config: &CacheConfig) -> Option<Arc<dyn CacheStore>> {
+    let url = config.redis_url.as_ref()?;
+    match RedisCache::new(url) {
+        Ok(cache) => Some(Arc::new(cache)),
+        Err(e) => {
+            tracing::error!("{} ({}:{})", e, file!(), line!());
+            None
+        }
+    }
 }
@@ -0,0 +1,177 @@
+use crate::log_into;
+use crate::repositories::error::DbRepoError;
+use mockall::automock;
+use sqlx::{query, query_scalar, PgConnection};
+use tracing::instrument;
+
+pub struct RoleRepoImpl {}
+
+impl RoleRepoImpl {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+#[automock]
+#[async_trait]
+pub trait RoleRepo: Send + Sync {
+    async fn assign(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+        role: &str,
+    ) -> Result<(), DbRepoError>;
+
+    async fn find_roles(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+    ) -> Result<Vec<String>, DbRepoError>;
+
+    async fn find_permissions(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+    ) -> Result<Vec<String>, DbRepoError>;
+
+    async fn find_role_permissions(
+        &self,
+        con: &mut PgConnection,
+        roles: &[String],
+    ) -> Result<Vec<String>, DbRepoError>;
+}
+
+#[async_trait]
+impl RoleRepo for RoleRepoImpl {
+    #[instrument(name = "role_repo/assign", skip_all, fields(user_id = %user_id, role = %role))]
+    async fn assign(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+        role: &str,
+    ) -> Result<(), DbRepoError> {
+        query!(
+            "INSERT INTO user_roles (user_id, role) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+            user_id,
+            role
+        )
+        .execute(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))?;
+        Ok(())
+    }
+
+    #[instrument(name = "role_repo/find_roles", skip_all, fields(user_id = %user_id))]
+    async fn find_roles(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+    ) -> Result<Vec<String>, DbRepoError> {
+        query_scalar!(
+            "SELECT role FROM user_roles WHERE user_id = $1 ORDER BY role",
+            user_id
+        )
+        .fetch_all(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+
+    // ユーザーに付与されたすべてのロールの権限をまとめて返す
+    #[instrument(name = "role_repo/find_permissions", skip_all, fields(user_id = %user_id))]
+    async fn find_permissions(
+        &self,
+        con: &mut PgConnection,
+        user_id: i32,
+    ) -> Result<Vec<String>, DbRepoError> {
+        query_scalar!(
+            "SELECT DISTINCT rp.permission FROM user_roles ur \
+             JOIN role_permissions rp ON rp.role = ur.role \
+             WHERE ur.user_id = $1 ORDER BY rp.permission",
+            user_id
+        )
+        .fetch_all(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+
+    // Bearerトークンのrolesなど、ユーザーに紐付かないロールの権限をまとめて返す。未知のロールは無視する
+    #[instrument(name = "role_repo/find_role_permissions", skip_all)]
+    async fn find_role_permissions(
+        &self,
+        con: &mut PgConnection,
+        roles: &[String],
+    ) -> Result<Vec<String>, DbRepoError> {
+        query_scalar!(
+            "SELECT DISTINCT permission FROM role_permissions \
+             WHERE role = ANY($1) ORDER BY permission",
+            roles
+        )
+        .fetch_all(&mut *con)
+        .await
+        .map_err(|e| log_into!(e, DbRepoError))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::repositories::role_repo::{RoleRepo, RoleRepoImpl};
+    use crate::test::db::create_db_con_for_test;
+    use crate::test::repositories::prepare::user::create_user;
+    use sqlx::Connection;
+
+    #[tokio::test]
+    async fn test_assign_and_find_permissions() {
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+        let user = create_user(&mut tx).await.unwrap();
+        let repo = RoleRepoImpl::new();
+        assert!(repo
+            .find_permissions(&mut tx, user.id)
+            .await
+            .unwrap()
+            .is_empty());
+
+        repo.assign(&mut tx, user.id, "editor").await.unwrap();
+        repo.assign(&mut tx, user.id, "admin").await.unwrap();
+        repo.assign(&mut tx, user.id, "admin").await.unwrap();
+
+        assert_eq!(
+            repo.find_roles(&mut tx, user.id).await.unwrap(),
+            vec!["admin", "editor"]
+        );
+        assert_eq!(
+            repo.find_permissions(&mut tx, user.id).await.unwrap(),
+            vec!["api_keys:manage", "products:write", "users:write"]
+        );
+        tx.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_find_role_permissions() {
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let repo = RoleRepoImpl::new();
+        let roles = vec!["editor".to_string(), "unknown".to_string()];
+        assert_eq!(
+            repo.find_role_permissions(&mut db_con, &roles)
+                .await
+                .unwrap(),
+            vec!["products:write"]
+        );
+        assert!(repo
+            .find_role_permissions(&mut db_con, &[])
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_assign_unknown_role() {
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+        let user = create_user(&mut tx).await.unwrap();
+        let repo = RoleRepoImpl::new();
+        let result = repo.assign(&mut tx, user.id, "owner").await;
+        assert!(result.is_err());
+        tx.rollback().await.unwrap();
+    }
+}
@@ -1,8 +1,12 @@
 use crate::log_into;
-use crate::models::user_model::User;
+use crate::models::page_model::Page;
+use crate::models::user_model::{User, UserFilter};
+use crate::repositories::cache::ScopedCon;
 use crate::repositories::error::DbRepoError;
+use crate::repositories::filter::escape_like;
+use crate::repositories::pagination::PageRequest;
 use mockall::automock;
-use sqlx::{query, query_as, PgConnection};
+use sqlx::{query, query_as, Postgres, QueryBuilder};
 use tracing::instrument;
 
 pub struct UserRepoImpl {}
@@ -16,44 +20,77 @@ impl UserRepoImpl {
 #[automock]
 #[async_trait]
 pub trait UserRepo: Send + Sync {
-    async fn create(
+    async fn create<'a>(
         &self, 
-        con: &mut PgConnection, 
+        con: &mut ScopedCon<'a>, 
         name: &String,
         age: i32
     ) -> Result<User, DbRepoError>;
 
-    async fn find_all(
+    async fn find_all<'a>(
         &self, 
-        con: &mut PgConnection) -> Result<Vec<User>, DbRepoError>;
+        con: &mut ScopedCon<'a>) -> Result<Vec<User>, DbRepoError>;
 
-    async fn find_by_id(
+    async fn find_page<'a>(
         &self,
-        con: &mut PgConnection,
+        con: &mut ScopedCon<'a>,
+        filter: &UserFilter,
+        page: &PageRequest,
+    ) -> Result<Page<User>, DbRepoError>;
+
+    async fn find_by_id<'a>(
+        &self,
+        con: &mut ScopedCon<'a>,
         id: i32,
     ) -> Result<Option<User>, DbRepoError>;
 
-    async fn update(
+    async fn update<'a>(
         &self,
-        con: &mut PgConnection,
+        con: &mut ScopedCon<'a>,
         id: i32,
         name: &String,
         age: i32
     ) -> Result<User, DbRepoError>;
 
-    async fn delete(
+    async fn delete<'a>(
         &self, 
-        con: &mut PgConnection, 
+        con: &mut ScopedCon<'a>, 
         id: i32) -> Result<(), DbRepoError>;
 }
 
+// 絞り込み条件をWHERE句に追加する。値はすべてバインドパラメータで渡す
+fn push_filter(qb: &mut QueryBuilder<'_, Postgres>, filter: &UserFilter) {
+    if let Some(name_like) = &filter.name_like {
+        qb.push(" AND name LIKE ")
+            .push_bind(format!("{}%", escape_like(name_like)));
+    }
+    if let Some(age) = filter.age_gt {
+        qb.push(" AND age > ").push_bind(age);
+    }
+    if let Some(age) = filter.age_gte {
+        qb.push(" AND age >= ").push_bind(age);
+    }
+    if let Some(age) = filter.age_lt {
+        qb.push(" AND age < ").push_bind(age);
+    }
+    if let Some(age) = filter.age_lte {
+        qb.push(" AND age <= ").push_bind(age);
+    }
+    match filter.age_is_null {
+        Some(true) => qb.push(" AND age IS NULL"),
+        Some(false) => qb.push(" AND age IS NOT NULL"),
+        None => qb,
+    };
+}
+
 #[async_trait]
 impl UserRepo for UserRepoImpl {
     #[instrument(name = "user_repo/create", skip_all)]
-    async fn create(&self, 
-        con: &mut PgConnection, 
+    async fn create<'a>(&self, 
+        con: &mut ScopedCon<'a>, 
         name: &String,
         age: i32) -> Result<User, DbRepoError> {
+        let con = con.con();
 
         // create unwrap age
         let x = age.to_string().parse::<i32>().unwrap();
@@ -69,7 +106,8 @@ impl UserRepo for UserRepoImpl {
     }
 
     #[instrument(name = "user_repo/find_all", skip_all)]
-    async fn find_all(&self, con: &mut PgConnection) -> Result<Vec<User>, DbRepoError> {
+    async fn find_all<'a>(&self, con: &mut ScopedCon<'a>) -> Result<Vec<User>, DbRepoError> {
+        let con = con.con();
         let users = query_as!(User, "SELECT * FROM users")
             .fetch_all(&mut *con)
             .await
@@ -77,12 +115,45 @@ impl UserRepo for UserRepoImpl {
         Ok(users)
     }
 
+    #[instrument(name = "user_repo/find_page", skip_all)]
+    async fn find_page<'a>(
+        &self,
+        con: &mut ScopedCon<'a>,
+        filter: &UserFilter,
+        page: &PageRequest,
+    ) -> Result<Page<User>, DbRepoError> {
+        let con = con.con();
+        let mut qb = QueryBuilder::new("SELECT * FROM users WHERE TRUE");
+        push_filter(&mut qb, filter);
+        page.push_keyset(&mut qb);
+        page.push_order_and_limit(&mut qb);
+        let users = qb
+            .build_query_as::<User>()
+            .fetch_all(&mut *con)
+            .await
+            .map_err(|e| log_into!(e, DbRepoError))?;
+        let total = if page.with_total {
+            let mut qb = QueryBuilder::new("SELECT COUNT(*) FROM users WHERE TRUE");
+            push_filter(&mut qb, filter);
+            let (count,) = qb
+                .build_query_as::<(i64,)>()
+                .fetch_one(&mut *con)
+                .await
+                .map_err(|e| log_into!(e, DbRepoError))?;
+            Some(count)
+        } else {
+            None
+        };
+        Ok(page.to_page(users, total))
+    }
+
     #[instrument(name = "user_repo/find_by_id", skip_all, fields(id = %id))]
-    async fn find_by_id(
+    async fn find_by_id<'a>(
         &self,
-        con: &mut PgConnection,
+        con: &mut ScopedCon<'a>,
         id: i32,
     ) -> Result<Option<User>, DbRepoError> {
+        let con = con.con();
         query_as!(User, "SELECT * FROM users WHERE id = $1", id)
             .fetch_optional(&mut *con)
             .await
@@ -90,13 +161,14 @@ impl UserRepo for UserRepoImpl {
     }
 
     #[instrument(name = "user_repo/update", skip_all, fields(id = %id))]
-    async fn update(
+    async fn update<'a>(
         &self,
-        con: &mut PgConnection,
+        con: &mut ScopedCon<'a>,
         id: i32,
         name: &String,
         age: i32,
     ) -> Result<User, DbRepoError> {
+        let con = con.con();
         query_as!(
             User,
             "UPDATE users SET name = $1, age = $2 WHERE id = $3 RETURNING *",
@@ -110,7 +182,8 @@ impl UserRepo for UserRepoImpl {
     }
 
     #[instrument(name = "user_repo/delete", skip_all, fields(id = %id))]
-    async fn delete(&self, con: &mut PgConnection, id: i32) -> Result<(), DbRepoError> {
+    async fn delete<'a>(&self, con: &mut ScopedCon<'a>, id: i32) -> Result<(), DbRepoError> {
+        let con = con.con();
         query!("DELETE FROM users WHERE id = $1", id)
             .execute(&mut *con)
             .await
@@ -121,6 +194,8 @@ impl UserRepo for UserRepoImpl {
 
 #[cfg(test)]
 mod tests {
+    use crate::models::user_model::UserFilter;
+    use crate::repositories::pagination::{Cursor, PageRequest, SortField};
     use crate::repositories::user_repo::{UserRepo, UserRepoImpl};
     use crate::test::db::create_db_con_for_test;
     use crate::test::repositories::prepare::user::create_user;
@@ -169,4 +244,60 @@ mod tests {
         assert!(result.is_ok());
         tx.rollback().await.unwrap();
     }
+
+    #[tokio::test]
+    async fn test_find_page_with_cursor() {
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+        let first = create_user(&mut tx).await.unwrap();
+        let second = create_user(&mut tx).await.unwrap();
+        let third = create_user(&mut tx).await.unwrap();
+        let repo = UserRepoImpl::new();
+        let mut page = PageRequest {
+            limit: 2,
+            sort: vec![SortField {
+                column: "id",
+                descending: true,
+            }],
+            with_total: true,
+            ..PageRequest::default()
+        };
+
+        let result = repo.find_page(&mut tx, &UserFilter::default(), &page).await.unwrap();
+        let ids: Vec<i32> = result.items.iter().map(|u| u.id).collect();
+        assert_eq!(ids, vec![third.id, second.id]);
+        assert!(result.total.unwrap() >= 3);
+
+        page.cursor = Cursor::decode(&result.next_cursor.unwrap());
+        let result = repo.find_page(&mut tx, &UserFilter::default(), &page).await.unwrap();
+        assert_eq!(result.items[0].id, first.id);
+        tx.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_find_page_with_filter() {
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+        let repo = UserRepoImpl::new();
+        let name = "filter_test_%_user".to_string();
+        let young = repo.create(&mut tx, &name, 17).await.unwrap();
+        let adult = repo.create(&mut tx, &name, 25).await.unwrap();
+        let filter = UserFilter {
+            name_like: Some("filter_test_%_".to_string()),
+            age_gte: Some(18),
+            age_is_null: Some(false),
+            ..UserFilter::default()
+        };
+        let page = PageRequest {
+            with_total: true,
+            ..PageRequest::default()
+        };
+
+        let result = repo.find_page(&mut tx, &filter, &page).await.unwrap();
+        let ids: Vec<i32> = result.items.iter().map(|u| u.id).collect();
+        assert_eq!(ids, vec![adult.id]);
+        assert!(!ids.contains(&young.id));
+        assert_eq!(result.total, Some(1));
+        tx.rollback().await.unwrap();
+    }
 }
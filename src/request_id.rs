@@ -0,0 +1,184 @@
+use crate::telemetry::otlp::remote_parent;
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::Header;
+use rocket::route::{Handler, Outcome};
+use rocket::{Data, Request, Response, Route};
+use tracing::Instrument;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+const MAX_LEN: usize = 128;
+
+// クライアントから受け取るか、無ければ生成したリクエストID
+pub struct RequestId(String);
+
+impl RequestId {
+    // RequestIdFairingがattachされていない場合はNone
+    pub fn get<'r>(req: &'r Request<'_>) -> Option<&'r str> {
+        req.local_cache(|| None::<RequestId>)
+            .as_ref()
+            .map(|id| id.0.as_str())
+    }
+
+    // ログやヘッダーを壊す値は受け付けずに新しく生成する
+    fn from_header(value: Option<&str>) -> Self {
+        match value {
+            Some(value)
+                if !value.is_empty()
+                    && value.len() <= MAX_LEN
+                    && value.bytes().all(|b| b.is_ascii_graphic()) =>
+            {
+                RequestId(value.to_string())
+            }
+            _ => RequestId(Uuid::new_v4().to_string()),
+        }
+    }
+}
+
+// X-Request-Idを受け取るか生成し、レスポンスのヘッダーに返す
+pub struct RequestIdFairing;
+
+#[rocket::async_trait]
+impl Fairing for RequestIdFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Request ID",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, req: &mut Request<'_>, _data: &mut Data<'_>) {
+        let id = RequestId::from_header(req.headers().get_one(REQUEST_ID_HEADER));
+        req.local_cache(|| Some(id));
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        if let Some(id) = RequestId::get(req) {
+            res.set_header(Header::new(REQUEST_ID_HEADER, id.to_string()));
+        }
+    }
+}
+
+// ハンドラをrequest{request_id=...}のspanの中で実行する
+// リクエストガードやハンドラ内の#[instrument]のspan、log_into!のログはすべてこのspanの子になる
+// traceparentヘッダーがあれば、OTLPで送るspanは呼び出し元のtraceに繋がる
+#[derive(Clone)]
+struct Traced(Box<dyn Handler>);
+
+#[rocket::async_trait]
+impl Handler for Traced {
+    async fn handle<'r>(&self, req: &'r Request<'_>, data: Data<'r>) -> Outcome<'r> {
+        let span = tracing::info_span!(
+            "request",
+            request_id = RequestId::get(req).unwrap_or_default(),
+            method = %req.method(),
+            path = %req.uri().path()
+        );
+        if let Some(parent) = remote_parent(req.headers()) {
+            span.set_parent(parent);
+        }
+        self.0.handle(req, data).instrument(span).await
+    }
+}
+
+// mountするルートをTracedで包む (fairingからはハンドラを包めないため)
+pub fn traced(routes: Vec<Route>) -> Vec<Route> {
+    routes
+        .into_iter()
+        .map(|mut route| {
+            route.handler = Box::new(Traced(route.handler.clone()));
+            route
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::app_error::AppError;
+    use crate::error::catchers::catchers;
+    use crate::test::log::LogBuffer;
+    use rocket::http::Status;
+    use rocket::local::asynchronous::Client;
+    use rocket::serde::json::Value;
+    use tracing::instrument;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[get("/fail")]
+    #[instrument(name = "test_controller/fail", skip_all)]
+    fn fail() -> Result<(), AppError> {
+        tracing::error!("something went wrong");
+        Err(AppError::InternalServerError)
+    }
+
+    async fn client() -> Client {
+        let rocket = rocket::build()
+            .attach(RequestIdFairing)
+            .register("/", catchers())
+            .mount("/", traced(routes![fail]));
+        Client::tracked(rocket)
+            .await
+            .expect("valid rocket instance")
+    }
+
+    #[test]
+    fn test_from_header() {
+        assert_eq!(RequestId::from_header(Some("abc-123")).0, "abc-123");
+        for value in [None, Some(""), Some("has space"), Some("line\nbreak")] {
+            assert_eq!(RequestId::from_header(value).0.len(), 36);
+        }
+        let long = "a".repeat(MAX_LEN + 1);
+        assert_ne!(RequestId::from_header(Some(&long)).0, long);
+    }
+
+    #[rocket::async_test]
+    async fn test_request_id_in_logs_header_and_body() {
+        let buffer = LogBuffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::registry().with(
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(move || writer.clone()),
+        );
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let client = client().await;
+        let response = client
+            .get("/fail")
+            .header(Header::new(REQUEST_ID_HEADER, "abc-123"))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::InternalServerError);
+        assert_eq!(
+            response.headers().get_one(REQUEST_ID_HEADER),
+            Some("abc-123")
+        );
+        let body: Value = response.into_json().await.expect("valid problem json");
+        assert_eq!(body["request_id"], "abc-123");
+        let logs = buffer.contents();
+        assert!(
+            logs.contains(
+                "request{request_id=\"abc-123\" method=GET path=/fail}:test_controller/fail: "
+            ),
+            "{}",
+            logs
+        );
+    }
+
+    #[rocket::async_test]
+    async fn test_generates_request_id() {
+        let client = client().await;
+        let response = client.get("/unknown").dispatch().await;
+        assert_eq!(response.status(), Status::NotFound);
+        let id = response
+            .headers()
+            .get_one(REQUEST_ID_HEADER)
+            .unwrap()
+            .to_string();
+        assert_eq!(id.len(), 36);
+        let body: Value = response.into_json().await.expect("valid problem json");
+        assert_eq!(body["request_id"], id.as_str());
+    }
+}
@@ -0,0 +1,98 @@
+use crate::telemetry::redact::{JsonVisitor, Redact};
+use chrono::{SecondsFormat, Utc};
+use serde_json::{Map, Value};
+use std::fmt;
+use tracing::span::Record;
+use tracing::{Event, Subscriber};
+use tracing_log::NormalizeEvent;
+use tracing_subscriber::field::RecordFields;
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, FormattedFields};
+use tracing_subscriber::registry::LookupSpan;
+
+// spanのフィールドをJSONの文字列として保存する (JsonFormatが読み戻す)
+pub struct JsonFields {
+    redact: Redact,
+}
+
+impl JsonFields {
+    pub fn new(redact: Redact) -> Self {
+        Self { redact }
+    }
+}
+
+impl<'w> FormatFields<'w> for JsonFields {
+    fn format_fields<R: RecordFields>(&self, mut writer: Writer<'w>, fields: R) -> fmt::Result {
+        let mut map = Map::new();
+        fields.record(&mut JsonVisitor::new(&mut map, &self.redact));
+        write!(writer, "{}", Value::Object(map))
+    }
+
+    fn add_fields(&self, current: &mut FormattedFields<Self>, fields: &Record<'_>) -> fmt::Result {
+        let mut map: Map<String, Value> = serde_json::from_str(&current.fields).unwrap_or_default();
+        fields.record(&mut JsonVisitor::new(&mut map, &self.redact));
+        current.fields = Value::Object(map).to_string();
+        Ok(())
+    }
+}
+
+// 1行1オブジェクトのJSON
+// {"timestamp", "level", "target", "message", "fields": {...}, "spans": [{"name", ...}]}
+// tracing-subscriberのjson形式はイベントのフィールドを差し替えられないので、redactのために自前で書き出す
+pub struct JsonFormat {
+    redact: Redact,
+}
+
+impl JsonFormat {
+    pub fn new(redact: Redact) -> Self {
+        Self { redact }
+    }
+}
+
+impl<S, N> FormatEvent<S, N> for JsonFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let normalized = event.normalized_metadata();
+        let metadata = normalized.as_ref().unwrap_or_else(|| event.metadata());
+        let mut fields = Map::new();
+        event.record(&mut JsonVisitor::new(&mut fields, &self.redact));
+
+        let mut line = Map::new();
+        line.insert(
+            "timestamp".to_string(),
+            Value::from(Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true)),
+        );
+        line.insert("level".to_string(), Value::from(metadata.level().as_str()));
+        line.insert("target".to_string(), Value::from(metadata.target()));
+        if let Some(message) = fields.remove("message") {
+            line.insert("message".to_string(), message);
+        }
+        line.insert("fields".to_string(), Value::Object(fields));
+        if let Some(scope) = ctx.event_scope() {
+            let spans = scope
+                .from_root()
+                .map(|span| {
+                    let mut object = Map::new();
+                    object.insert("name".to_string(), Value::from(span.name()));
+                    let extensions = span.extensions();
+                    if let Some(fields) = extensions.get::<FormattedFields<N>>() {
+                        if let Ok(Value::Object(fields)) = serde_json::from_str(&fields.fields) {
+                            object.extend(fields);
+                        }
+                    }
+                    Value::Object(object)
+                })
+                .collect();
+            line.insert("spans".to_string(), Value::Array(spans));
+        }
+        writeln!(writer, "{}", Value::Object(line))
+    }
+}
@@ -0,0 +1,232 @@
+use crate::config::{LogFileConfig, LogFormat, LogRotation, LoggingConfig};
+use crate::metrics::metrics::Metrics;
+use crate::metrics::span_layer::{is_repo_span, RepoSpanLayer};
+use crate::telemetry::json::{JsonFields, JsonFormat};
+use crate::telemetry::otlp::otlp_layer;
+use crate::telemetry::redact::{Redact, TextFields};
+use opentelemetry::trace::TraceError;
+use opentelemetry_sdk::trace::TracerProvider;
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::{Orbit, Rocket};
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{InitError, RollingFileAppender, Rotation};
+use tracing_subscriber::filter::{filter_fn, EnvFilter, ParseError};
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::{SubscriberInitExt, TryInitError};
+use tracing_subscriber::{Layer, Registry};
+
+pub(crate) type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+#[derive(Debug, Error)]
+pub enum LoggingError {
+    #[error("[LoggingError::Filter] {0}")]
+    Filter(#[from] ParseError),
+    #[error("[LoggingError::File] {0}")]
+    File(#[from] InitError),
+    #[error("[LoggingError::Otlp] {0}")]
+    Otlp(#[from] TraceError),
+    #[error("[LoggingError::Init] {0}")]
+    Init(#[from] TryInitError),
+}
+
+// ファイルへの書き込みは別スレッドで行う。dropされるまでに書き込まれなかったログは失われるので、
+// Rocketにfairingとしてattachしてプロセスの終了まで保持する
+pub struct LogGuard {
+    _guard: Option<WorkerGuard>,
+    tracer_provider: Mutex<Option<TracerProvider>>,
+}
+
+// OTLPへ送る前のspanはランタイム上のタスクが送るので、ランタイムが止まる前のshutdownで送りきる
+#[rocket::async_trait]
+impl Fairing for LogGuard {
+    fn info(&self) -> Info {
+        Info {
+            name: "Logging",
+            kind: Kind::Shutdown,
+        }
+    }
+
+    async fn on_shutdown(&self, _rocket: &Rocket<Orbit>) {
+        let provider = self.tracer_provider.lock().unwrap().take();
+        if let Some(provider) = provider {
+            // dropは残りのspanを送り終えるまでブロックする
+            let _ = tokio::task::spawn_blocking(move || drop(provider)).await;
+        }
+    }
+}
+
+pub fn init_logging(
+    config: &LoggingConfig,
+    metrics: Arc<Metrics>,
+) -> Result<LogGuard, LoggingError> {
+    let mut config = config.clone();
+    if let Ok(filter) = std::env::var(EnvFilter::DEFAULT_ENV) {
+        config.filter = Some(filter);
+    }
+    let redact = Redact::new(&config.redact);
+    let mut layers = vec![fmt_layer(&config, &redact, std::io::stdout, true)?];
+    let guard = match &config.file {
+        Some(file) => {
+            let (writer, guard) = tracing_appender::non_blocking(file_appender(file)?);
+            layers.push(fmt_layer(&config, &redact, writer, false)?);
+            Some(guard)
+        }
+        None => None,
+    };
+    let tracer_provider = match &config.otlp {
+        Some(otlp) => {
+            let (layer, provider) = otlp_layer(otlp)?;
+            layers.push(layer);
+            Some(provider)
+        }
+        None => None,
+    };
+    // メトリクスはログのfilterに関係なく、リポジトリのspanをすべて記録する
+    layers.push(
+        RepoSpanLayer::new(metrics)
+            .with_filter(filter_fn(|metadata| {
+                metadata.is_span() && is_repo_span(metadata.name())
+            }))
+            .boxed(),
+    );
+    tracing_subscriber::registry().with(layers).try_init()?;
+    Ok(LogGuard {
+        _guard: guard,
+        tracer_provider: Mutex::new(tracer_provider),
+    })
+}
+
+fn file_appender(config: &LogFileConfig) -> Result<RollingFileAppender, InitError> {
+    let rotation = match config.rotation {
+        LogRotation::Minutely => Rotation::MINUTELY,
+        LogRotation::Hourly => Rotation::HOURLY,
+        LogRotation::Daily => Rotation::DAILY,
+        LogRotation::Never => Rotation::NEVER,
+    };
+    RollingFileAppender::builder()
+        .rotation(rotation)
+        .filename_prefix(config.prefix.as_deref().unwrap_or("app.log"))
+        .build(&config.directory)
+}
+
+pub(crate) fn fmt_layer<W>(
+    config: &LoggingConfig,
+    redact: &Redact,
+    writer: W,
+    ansi: bool,
+) -> Result<BoxedLayer, ParseError>
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    // span_timingが有効な場合、spanが閉じる時にtime.busy/time.idleを含むイベントを出す
+    let span_events = if config.span_timing {
+        FmtSpan::CLOSE
+    } else {
+        FmtSpan::NONE
+    };
+    let layer = tracing_subscriber::fmt::layer()
+        .with_writer(writer)
+        .with_ansi(ansi)
+        .with_span_events(span_events);
+    let filter = EnvFilter::try_new(config.filter.as_deref().unwrap_or("info"))?;
+    let layer = match config.format {
+        LogFormat::Text => layer
+            .fmt_fields(TextFields::new(redact.clone()))
+            .with_filter(filter)
+            .boxed(),
+        LogFormat::Pretty => layer
+            .pretty()
+            .fmt_fields(TextFields::new(redact.clone()))
+            .with_filter(filter)
+            .boxed(),
+        LogFormat::Json => layer
+            .fmt_fields(JsonFields::new(redact.clone()))
+            .event_format(JsonFormat::new(redact.clone()))
+            .with_filter(filter)
+            .boxed(),
+    };
+    Ok(layer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::log::LogBuffer;
+    use serde_json::Value;
+    use tracing::instrument;
+
+    #[instrument(name = "user_use_case/create", skip_all, fields(email = %email))]
+    fn create_user(email: &str, name: &str) {
+        tracing::info!(name = %name, age = 20, "Received request to add user");
+        tracing::debug!("not logged at info");
+    }
+
+    fn log(config: LoggingConfig) -> String {
+        let buffer = LogBuffer::default();
+        let writer = buffer.clone();
+        let layer = fmt_layer(
+            &config,
+            &Redact::new(&config.redact),
+            move || writer.clone(),
+            false,
+        )
+        .unwrap();
+        tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), || {
+            create_user("taro@example.com", "taro");
+        });
+        buffer.contents()
+    }
+
+    #[test]
+    fn test_text_redacts_fields() {
+        let logs = log(LoggingConfig {
+            filter: Some("info".to_string()),
+            ..LoggingConfig::default()
+        });
+        assert!(logs.contains(
+            "user_use_case/create{email=[REDACTED]}: rust_rocket_sqlx_sample::telemetry::logging::tests: \
+             Received request to add user name=[REDACTED] age=20"
+        ), "{}", logs);
+        assert!(!logs.contains("taro"), "{}", logs);
+        assert!(!logs.contains("not logged"));
+    }
+
+    #[test]
+    fn test_json_with_span_timing() {
+        let logs = log(LoggingConfig {
+            format: LogFormat::Json,
+            filter: Some("info".to_string()),
+            span_timing: true,
+            redact: vec!["email".to_string()],
+            ..LoggingConfig::default()
+        });
+        let lines: Vec<Value> = logs
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["level"], "INFO");
+        assert_eq!(lines[0]["message"], "Received request to add user");
+        assert_eq!(lines[0]["fields"]["name"], "taro");
+        assert_eq!(lines[0]["fields"]["age"], 20);
+        assert_eq!(lines[0]["spans"][0]["name"], "user_use_case/create");
+        assert_eq!(lines[0]["spans"][0]["email"], "[REDACTED]");
+        assert_eq!(lines[1]["message"], "close");
+        assert!(lines[1]["fields"]["time.busy"].is_string());
+        assert!(!logs.contains("taro@example.com"));
+    }
+
+    #[test]
+    fn test_invalid_filter() {
+        let config = LoggingConfig {
+            filter: Some("info,=[".to_string()),
+            ..LoggingConfig::default()
+        };
+        let result = fmt_layer(&config, &Redact::default(), std::io::sink, false);
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,176 @@
+use crate::config::OtlpConfig;
+use crate::telemetry::logging::{BoxedLayer, LoggingError};
+use opentelemetry::propagation::TextMapPropagator;
+use opentelemetry::trace::{TraceContextExt, TracerProvider as _};
+use opentelemetry::{Context, KeyValue};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{self, Sampler, TracerProvider};
+use opentelemetry_sdk::{runtime, Resource};
+use rocket::http::HeaderMap;
+use std::collections::HashMap;
+use tracing_subscriber::filter::{filter_fn, EnvFilter, FilterExt};
+use tracing_subscriber::Layer;
+
+const TRACER_NAME: &str = env!("CARGO_PKG_NAME");
+
+// tracingのspanをOTLPで送るレイヤー
+// 送信はtokioのランタイム上のタスクでまとめて行う。TracerProviderがdropされると残りを送って終了する
+pub(crate) fn otlp_layer(
+    config: &OtlpConfig,
+) -> Result<(BoxedLayer, TracerProvider), LoggingError> {
+    let exporter = opentelemetry_otlp::new_exporter()
+        .http()
+        .with_endpoint(&config.endpoint)
+        .build_span_exporter()?;
+    // traceparentでサンプリングの有無が指定されていればそれに従う
+    let sampler = Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+        config.sample_ratio.unwrap_or(1.0),
+    )));
+    let service_name = config.service_name.as_deref().unwrap_or(TRACER_NAME);
+    let provider = TracerProvider::builder()
+        .with_batch_exporter(exporter, runtime::Tokio)
+        .with_config(
+            trace::config()
+                .with_sampler(sampler)
+                .with_resource(Resource::new([KeyValue::new(
+                    "service.name",
+                    service_name.to_string(),
+                )])),
+        )
+        .build();
+    // イベントはredactされるログにだけ出し、OTLPへはspanのみ送る
+    let filter = EnvFilter::try_new(config.filter.as_deref().unwrap_or("info"))?;
+    let layer = tracing_opentelemetry::layer()
+        .with_tracer(provider.tracer(TRACER_NAME))
+        .with_filter(filter_fn(|metadata| metadata.is_span()).and(filter))
+        .boxed();
+    Ok((layer, provider))
+}
+
+// W3Cのtraceparent (とtracestate) から呼び出し元のspanを取り出す。無いか不正な場合はNone
+pub fn remote_parent(headers: &HeaderMap<'_>) -> Option<Context> {
+    let propagator = TraceContextPropagator::new();
+    let carrier: HashMap<String, String> = propagator
+        .fields()
+        .filter_map(|field| {
+            headers
+                .get_one(field)
+                .map(|value| (field.to_string(), value.to_string()))
+        })
+        .collect();
+    let cx = propagator.extract_with_context(&Context::new(), &carrier);
+    if cx.span().span_context().is_valid() {
+        Some(cx)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request_id::traced;
+    use crate::test::collector::Collector;
+    use opentelemetry_proto::tonic::common::v1::any_value::Value;
+    use opentelemetry_proto::tonic::trace::v1::Span;
+    use rocket::http::Header;
+    use rocket::local::asynchronous::Client;
+    use tracing::instrument;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+    const PARENT_ID: &str = "00f067aa0ba902b7";
+
+    #[get("/hello")]
+    #[instrument(name = "test_controller/hello", skip_all)]
+    fn hello() -> &'static str {
+        tracing::info!(name = "taro", "hello");
+        "hello"
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn find<'a>(spans: &'a [Span], name: &str) -> &'a Span {
+        spans
+            .iter()
+            .find(|span| span.name == name)
+            .unwrap_or_else(|| panic!("span {} not exported", name))
+    }
+
+    #[test]
+    fn test_remote_parent() {
+        let mut headers = HeaderMap::new();
+        assert!(remote_parent(&headers).is_none());
+        headers.add(Header::new("traceparent", "00-invalid-01"));
+        assert!(remote_parent(&headers).is_none());
+
+        let mut headers = HeaderMap::new();
+        headers.add(Header::new(
+            "traceparent",
+            format!("00-{}-{}-01", TRACE_ID, PARENT_ID),
+        ));
+        let cx = remote_parent(&headers).unwrap();
+        let span = cx.span();
+        let parent = span.span_context();
+        assert!(parent.is_remote());
+        assert!(parent.is_sampled());
+        assert_eq!(parent.trace_id().to_string(), TRACE_ID);
+        assert_eq!(parent.span_id().to_string(), PARENT_ID);
+    }
+
+    #[rocket::async_test]
+    async fn test_exports_spans_with_remote_parent() {
+        let mut collector = Collector::start().await;
+        let config = OtlpConfig {
+            endpoint: collector.endpoint(),
+            service_name: Some("test-service".to_string()),
+            filter: None,
+            sample_ratio: None,
+        };
+        let (layer, provider) = otlp_layer(&config).unwrap();
+        let guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(layer));
+
+        let rocket = rocket::build().mount("/", traced(routes![hello]));
+        let client = Client::tracked(rocket).await.unwrap();
+        let response = client
+            .get("/hello")
+            .header(Header::new(
+                "traceparent",
+                format!("00-{}-{}-01", TRACE_ID, PARENT_ID),
+            ))
+            .dispatch()
+            .await;
+        assert_eq!(response.into_string().await.unwrap(), "hello");
+        drop(guard);
+        // force_flushはエクスポートが終わるまでブロックするので、ワーカースレッドの外で呼ぶ
+        tokio::task::spawn_blocking(move || provider.force_flush())
+            .await
+            .unwrap();
+
+        let request = collector.next().await;
+        let resource = request.resource_spans[0].resource.as_ref().unwrap();
+        assert!(resource.attributes.iter().any(|attr| {
+            attr.key == "service.name"
+                && matches!(
+                    attr.value.as_ref().and_then(|value| value.value.as_ref()),
+                    Some(Value::StringValue(name)) if name == "test-service"
+                )
+        }));
+        let spans: Vec<Span> = request
+            .resource_spans
+            .into_iter()
+            .flat_map(|resource| resource.scope_spans)
+            .flat_map(|scope| scope.spans)
+            .collect();
+        let root = find(&spans, "request");
+        let controller = find(&spans, "test_controller/hello");
+        assert_eq!(hex(&root.trace_id), TRACE_ID);
+        assert_eq!(hex(&root.parent_span_id), PARENT_ID);
+        assert_eq!(hex(&controller.trace_id), TRACE_ID);
+        assert_eq!(controller.parent_span_id, root.span_id);
+        assert!(controller.events.is_empty());
+    }
+}
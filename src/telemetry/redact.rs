@@ -0,0 +1,155 @@
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use std::fmt::{self, Debug, Write};
+use std::sync::Arc;
+use tracing::field::{Field, Visit};
+use tracing_subscriber::field::{MakeVisitor, VisitFmt, VisitOutput};
+use tracing_subscriber::fmt::format::Writer;
+
+pub const REDACTED: &str = "[REDACTED]";
+
+// 値をログに出さないフィールド名 (イベントとspanのフィールドの両方に適用する)
+#[derive(Clone, Debug, Default)]
+pub struct Redact(Arc<HashSet<String>>);
+
+impl Redact {
+    pub fn new(fields: &[String]) -> Self {
+        Self(Arc::new(fields.iter().cloned().collect()))
+    }
+
+    pub fn contains(&self, field: &Field) -> bool {
+        self.0.contains(field.name())
+    }
+}
+
+// tracing-logが付けるlog.target などのフィールドはメタデータと重複するので出さない
+fn is_log_field(field: &Field) -> bool {
+    field.name().starts_with("log.")
+}
+
+// text/pretty形式のフィールド。messageの後に key=value を空白区切りで並べる
+pub struct TextFields {
+    redact: Redact,
+}
+
+impl TextFields {
+    pub fn new(redact: Redact) -> Self {
+        Self { redact }
+    }
+}
+
+impl<'a> MakeVisitor<Writer<'a>> for TextFields {
+    type Visitor = TextVisitor<'a>;
+
+    fn make_visitor(&self, target: Writer<'a>) -> Self::Visitor {
+        TextVisitor {
+            writer: target,
+            redact: self.redact.clone(),
+            is_empty: true,
+            result: Ok(()),
+        }
+    }
+}
+
+pub struct TextVisitor<'a> {
+    writer: Writer<'a>,
+    redact: Redact,
+    is_empty: bool,
+    result: fmt::Result,
+}
+
+impl TextVisitor<'_> {
+    fn separator(&mut self) -> &'static str {
+        if std::mem::replace(&mut self.is_empty, false) {
+            ""
+        } else {
+            " "
+        }
+    }
+}
+
+impl Visit for TextVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.record_debug(field, &format_args!("{}", value));
+        } else {
+            self.record_debug(field, &value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if self.result.is_err() || is_log_field(field) {
+            return;
+        }
+        let separator = self.separator();
+        self.result = if field.name() == "message" {
+            write!(self.writer, "{}{:?}", separator, value)
+        } else if self.redact.contains(field) {
+            write!(self.writer, "{}{}={}", separator, field, REDACTED)
+        } else {
+            write!(self.writer, "{}{}={:?}", separator, field, value)
+        };
+    }
+}
+
+impl VisitOutput<fmt::Result> for TextVisitor<'_> {
+    fn finish(self) -> fmt::Result {
+        self.result
+    }
+}
+
+impl VisitFmt for TextVisitor<'_> {
+    fn writer(&mut self) -> &mut dyn Write {
+        &mut self.writer
+    }
+}
+
+// json形式のフィールドをMapに集める
+pub struct JsonVisitor<'a> {
+    map: &'a mut Map<String, Value>,
+    redact: &'a Redact,
+}
+
+impl<'a> JsonVisitor<'a> {
+    pub fn new(map: &'a mut Map<String, Value>, redact: &'a Redact) -> Self {
+        Self { map, redact }
+    }
+
+    fn insert(&mut self, field: &Field, value: Value) {
+        if is_log_field(field) {
+            return;
+        }
+        let value = if self.redact.contains(field) {
+            Value::from(REDACTED)
+        } else {
+            value
+        };
+        self.map.insert(field.name().to_string(), value);
+    }
+}
+
+impl Visit for JsonVisitor<'_> {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.insert(field, Value::from(format!("{:?}", value)));
+    }
+}
@@ -1,9 +1,16 @@
 use crate::app::App;
 use crate::repositories::{
-    product_repo::MockProductRepo, repositories::Repos, user_repo::MockUserRepo,
+    api_key_repo::MockApiKeyRepo, cart_repo::MockCartRepo, category_repo::MockCategoryRepo,
+    credential_repo::MockCredentialRepo, health_repo::MockHealthRepo, order_repo::MockOrderRepo,
+    product_repo::MockProductRepo, repositories::Repos, role_repo::MockRoleRepo,
+    user_repo::MockUserRepo,
 };
+use crate::test::auth::session_auth_use_case_for_test;
 use crate::use_cases::{
-    product_use_case::MockProductUseCase, use_cases::UseCases, user_use_case::MockUserUseCase,
+    api_key_use_case::MockApiKeyUseCase, cart_use_case::MockCartUseCase,
+    category_use_case::MockCategoryUseCase, health_use_case::MockHealthUseCase,
+    order_use_case::MockOrderUseCase, product_use_case::MockProductUseCase, use_cases::UseCases,
+    user_use_case::MockUserUseCase,
 };
 
 pub fn create_app_for_test() -> App {
@@ -15,11 +22,43 @@ pub fn create_app_for_test() -> App {
 pub fn create_repos_for_test() -> Repos {
     let user = Box::new(MockUserRepo::new());
     let product = Box::new(MockProductRepo::new());
-    Repos { user, product }
+    let category = Box::new(MockCategoryRepo::new());
+    let credential = Box::new(MockCredentialRepo::new());
+    let role = Box::new(MockRoleRepo::new());
+    let api_key = Box::new(MockApiKeyRepo::new());
+    let order = Box::new(MockOrderRepo::new());
+    let cart = Box::new(MockCartRepo::new());
+    let health = Box::new(MockHealthRepo::new());
+    Repos {
+        user,
+        product,
+        category,
+        credential,
+        role,
+        api_key,
+        order,
+        cart,
+        health,
+    }
 }
 
 pub fn create_use_cases_for_test() -> UseCases {
     let user = Box::new(MockUserUseCase::new());
     let product = Box::new(MockProductUseCase::new());
-    UseCases { user, product }
+    let category = Box::new(MockCategoryUseCase::new());
+    let auth = Box::new(session_auth_use_case_for_test());
+    let api_key = Box::new(MockApiKeyUseCase::new());
+    let order = Box::new(MockOrderUseCase::new());
+    let cart = Box::new(MockCartUseCase::new());
+    let health = Box::new(MockHealthUseCase::new());
+    UseCases {
+        user,
+        product,
+        category,
+        auth,
+        api_key,
+        order,
+        cart,
+        health,
+    }
 }
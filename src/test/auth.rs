@@ -0,0 +1,69 @@
+use crate::auth::authenticated_user::AuthenticatedUser;
+use crate::auth::authorized::{ApiKeysManage, Permission, ProductsWrite, UsersWrite};
+use crate::auth::jwt::{Claims, JwtVerifier};
+use crate::config::{JwtConfig, JwtKeyConfig};
+use crate::use_cases::auth_use_case::MockAuthUseCase;
+use chrono::Utc;
+use jsonwebtoken::{encode, get_current_timestamp, Algorithm, EncodingKey, Header as JwtHeader};
+use rocket::http::{Cookie, Header};
+
+const JWT_SECRET_FOR_TEST: &str = "test secret";
+
+// ログイン済みのリクエストにするためのcookie。LocalRequest::private_cookieに渡す
+pub fn session_cookie_for_test(user_id: i32) -> Cookie<'static> {
+    AuthenticatedUser::session_cookie(user_id, 0, Utc::now())
+}
+
+// session_cookie_for_testのcookieを有効なセッションとして扱うAuthUseCase
+pub fn session_auth_use_case_for_test() -> MockAuthUseCase {
+    let mut mock_auth_use_case = MockAuthUseCase::new();
+    mock_auth_use_case
+        .expect_session_epoch()
+        .returning(|_, _, _| Ok(Some(0)));
+    mock_auth_use_case
+}
+
+// すべての権限を持つユーザーとして扱うAuthUseCase
+pub fn authorized_auth_use_case_for_test() -> MockAuthUseCase {
+    let mut mock_auth_use_case = session_auth_use_case_for_test();
+    mock_auth_use_case
+        .expect_permissions()
+        .returning(|_, _, _| {
+            Ok(vec![
+                UsersWrite::NAME.to_string(),
+                ProductsWrite::NAME.to_string(),
+                ApiKeysManage::NAME.to_string(),
+            ])
+        });
+    mock_auth_use_case
+}
+
+// bearer_header_for_testのトークンを受け付けるJwtVerifier。AppState::jwtに入れる
+pub fn jwt_verifier_for_test() -> JwtVerifier {
+    let config = JwtConfig {
+        keys: vec![JwtKeyConfig {
+            kid: "test".to_string(),
+            algorithm: "HS256".to_string(),
+            secret: Some(JWT_SECRET_FOR_TEST.to_string()),
+            public_key: None,
+        }],
+        issuer: None,
+        audience: None,
+        leeway: None,
+    };
+    JwtVerifier::new(&config).unwrap()
+}
+
+// rolesを持つサービスのBearerトークンのAuthorizationヘッダ
+pub fn bearer_header_for_test(roles: &[&str]) -> Header<'static> {
+    let claims = Claims {
+        sub: "test-service".to_string(),
+        roles: roles.iter().map(|role| role.to_string()).collect(),
+        exp: get_current_timestamp() + 60,
+    };
+    let mut header = JwtHeader::new(Algorithm::HS256);
+    header.kid = Some("test".to_string());
+    let key = EncodingKey::from_secret(JWT_SECRET_FOR_TEST.as_bytes());
+    let token = encode(&header, &claims, &key).unwrap();
+    Header::new("Authorization", format!("Bearer {}", token))
+}
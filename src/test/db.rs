@@ -1,9 +1,9 @@
-use crate::db::DbCon;
 use dotenv::dotenv;
-use sqlx::{postgres::PgPoolOptions, Error};
+use sqlx::{pool::PoolConnection, postgres::PgPoolOptions, Error, PgPool, Postgres, Transaction};
 use std::env;
+use uuid::Uuid;
 
-pub async fn create_db_con_for_test() -> Result<DbCon, Error> {
+pub async fn create_db_con_for_test() -> Result<PoolConnection<Postgres>, Error> {
     dotenv().ok();
     let db_url = env::var("DATABASE_URL_TEST").expect("DATABASE_URL_TEST must be set");
     let db_pool = PgPoolOptions::new()
@@ -12,3 +12,63 @@ pub async fn create_db_con_for_test() -> Result<DbCon, Error> {
         .await?;
     db_pool.acquire().await
 }
+
+// ユースケース全体をロールバックされるトランザクションの中で実行するためのコネクション
+// ユースケース内のUnitOfWorkはSAVEPOINTになる
+pub async fn create_tx_for_test() -> Result<Transaction<'static, Postgres>, Error> {
+    dotenv().ok();
+    let db_url = env::var("DATABASE_URL_TEST").expect("DATABASE_URL_TEST must be set");
+    let db_pool = PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&db_url)
+        .await?;
+    db_pool.begin().await
+}
+
+pub async fn create_pool_for_test() -> Result<PgPool, Error> {
+    dotenv().ok();
+    let db_url = env::var("DATABASE_URL_TEST").expect("DATABASE_URL_TEST must be set");
+    PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&db_url)
+        .await
+}
+
+// マイグレーションのテスト用の空のデータベース。テストの最後にdropで削除する
+pub struct TempDatabase {
+    pub url: String,
+    name: String,
+}
+
+impl TempDatabase {
+    pub async fn pool(&self) -> Result<PgPool, Error> {
+        PgPoolOptions::new()
+            .max_connections(1)
+            .connect(&self.url)
+            .await
+    }
+
+    pub async fn drop(self) -> Result<(), Error> {
+        let pool = create_pool_for_test().await?;
+        sqlx::query(&format!("DROP DATABASE {} WITH (FORCE)", self.name))
+            .execute(&pool)
+            .await?;
+        Ok(())
+    }
+}
+
+pub async fn create_database_for_test() -> Result<TempDatabase, Error> {
+    let pool = create_pool_for_test().await?;
+    let name = format!("test_{}", Uuid::new_v4().simple());
+    sqlx::query(&format!("CREATE DATABASE {}", name))
+        .execute(&pool)
+        .await?;
+    let db_url = env::var("DATABASE_URL_TEST").expect("DATABASE_URL_TEST must be set");
+    let (server, _) = db_url
+        .rsplit_once('/')
+        .expect("DATABASE_URL_TEST has a database name");
+    Ok(TempDatabase {
+        url: format!("{}/{}", server, name),
+        name,
+    })
+}
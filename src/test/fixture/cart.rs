@@ -0,0 +1,17 @@
+use crate::models::cart_model::{CartLine, CartView};
+
+pub fn cart_view_fixture() -> CartView {
+    CartView {
+        items: vec![CartLine {
+            product_id: 3,
+            name: String::from("apple"),
+            sku: String::from("APPLE-003"),
+            unit_price: 120,
+            currency: String::from("JPY"),
+            quantity: 2,
+            subtotal: 240,
+        }],
+        currency: Some(String::from("JPY")),
+        total: 240,
+    }
+}
@@ -0,0 +1,24 @@
+use crate::models::order_model::{Order, OrderDetail, OrderItem};
+use chrono::Utc;
+
+pub fn order_detail_fixture(id: i32, user_id: i32) -> OrderDetail {
+    OrderDetail {
+        order: Order {
+            id,
+            user_id,
+            currency: String::from("JPY"),
+            total: 240,
+            created_at: Utc::now(),
+        },
+        items: vec![OrderItem {
+            id: 1,
+            order_id: id,
+            product_id: Some(3),
+            name: String::from("apple"),
+            sku: String::from("APPLE-003"),
+            unit_price: 120,
+            quantity: 2,
+            subtotal: 240,
+        }],
+    }
+}
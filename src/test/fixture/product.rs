@@ -1,9 +1,14 @@
+use crate::dto::product_dto::ProductInput;
 use crate::models::product_model::Product;
 
 pub fn product_fixture(id: usize) -> Product {
     Product {
         id: id as i32,
         name: String::from("apple"),
+        sku: format!("APPLE-{:03}", id),
+        price: 120,
+        currency: String::from("JPY"),
+        stock: 10,
     }
 }
 
@@ -14,3 +19,14 @@ pub fn products_fixture(num: usize) -> Vec<Product> {
     }
     products
 }
+
+// SKUはユニークなので、テストが並列に走っても衝突しないようにランダムにする
+pub fn product_input_fixture() -> ProductInput {
+    ProductInput {
+        name: String::from("apple"),
+        sku: format!("TEST-{}", uuid::Uuid::new_v4().simple()),
+        price: 120,
+        currency: String::from("JPY"),
+        stock: 10,
+    }
+}
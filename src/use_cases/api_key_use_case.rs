@@ -0,0 +1,175 @@
+use crate::auth::api_key::{generate_api_key, hash_api_key, key_prefix};
+use crate::db::DbCon;
+use crate::error::app_error::AppError;
+use crate::models::api_key_model::{ApiKey, ApiKeyUsage, MintedApiKey};
+use crate::repositories::repositories::Repos;
+use crate::use_cases::unit_of_work::UnitOfWork;
+use mockall::automock;
+use tracing::instrument;
+
+pub struct ApiKeyUseCaseImpl {}
+
+impl ApiKeyUseCaseImpl {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+#[automock]
+#[async_trait]
+pub trait ApiKeyUseCase: Send + Sync {
+    async fn mint(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        name: &str,
+        scopes: &[String],
+        created_by: Option<i32>,
+    ) -> Result<MintedApiKey, AppError>;
+
+    async fn find_all(&self, repos: &Repos, db_con: &mut DbCon) -> Result<Vec<ApiKey>, AppError>;
+
+    async fn revoke(&self, repos: &Repos, db_con: &mut DbCon, id: i32) -> Result<ApiKey, AppError>;
+
+    async fn authenticate(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        key: &str,
+    ) -> Result<ApiKey, AppError>;
+
+    async fn record_usage(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        usage: &[ApiKeyUsage],
+    ) -> Result<(), AppError>;
+}
+
+#[async_trait]
+impl ApiKeyUseCase for ApiKeyUseCaseImpl {
+    #[instrument(name = "api_key_use_case/mint", skip_all)]
+    async fn mint(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        name: &str,
+        scopes: &[String],
+        created_by: Option<i32>,
+    ) -> Result<MintedApiKey, AppError> {
+        let key = generate_api_key();
+        let api_key = repos
+            .api_key
+            .create(
+                &mut *db_con,
+                name,
+                key_prefix(&key),
+                &hash_api_key(&key),
+                scopes,
+                created_by,
+            )
+            .await?;
+        Ok(MintedApiKey { api_key, key })
+    }
+
+    #[instrument(name = "api_key_use_case/find_all", skip_all)]
+    async fn find_all(&self, repos: &Repos, db_con: &mut DbCon) -> Result<Vec<ApiKey>, AppError> {
+        let api_keys = repos.api_key.find_all(&mut *db_con).await?;
+        Ok(api_keys)
+    }
+
+    #[instrument(name = "api_key_use_case/revoke", skip_all, fields(id = %id))]
+    async fn revoke(&self, repos: &Repos, db_con: &mut DbCon, id: i32) -> Result<ApiKey, AppError> {
+        match repos.api_key.revoke(&mut *db_con, id).await {
+            Ok(api_key) => Ok(api_key),
+            Err(e) if e.is_row_not_found() => Err(AppError::NotFound),
+            Err(e) => Err(AppError::from(e)),
+        }
+    }
+
+    // 存在しないキーと取り消されたキーは区別せずに401にする
+    #[instrument(name = "api_key_use_case/authenticate", skip_all)]
+    async fn authenticate(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        key: &str,
+    ) -> Result<ApiKey, AppError> {
+        match repos
+            .api_key
+            .find_by_hash(&mut *db_con, &hash_api_key(key))
+            .await?
+        {
+            Some(api_key) if api_key.revoked_at.is_none() => Ok(api_key),
+            _ => Err(AppError::Unauthorized),
+        }
+    }
+
+    #[instrument(name = "api_key_use_case/record_usage", skip_all, fields(keys = usage.len()))]
+    async fn record_usage(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        usage: &[ApiKeyUsage],
+    ) -> Result<(), AppError> {
+        let mut uow = UnitOfWork::begin(db_con).await?;
+        let mut result = Ok(());
+        for entry in usage {
+            if let Err(e) = repos.api_key.record_usage(uow.con(), entry).await {
+                result = Err(AppError::from(e));
+                break;
+            }
+        }
+        uow.finish(result).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repositories::repositories::create_repos;
+    use crate::test::db::create_tx_for_test;
+
+    #[rocket::async_test]
+    async fn test_mint_authenticate_and_revoke() {
+        let repos = create_repos(None);
+        let mut tx = create_tx_for_test().await.unwrap();
+        let api_key_use_case = ApiKeyUseCaseImpl::new();
+        let scopes = vec!["products:write".to_string()];
+        let minted = api_key_use_case
+            .mint(&repos, &mut tx, "partner", &scopes, None)
+            .await
+            .unwrap();
+        assert!(minted.key.starts_with(&minted.api_key.prefix));
+
+        let api_key = api_key_use_case
+            .authenticate(&repos, &mut tx, &minted.key)
+            .await
+            .unwrap();
+        assert_eq!(api_key.id, minted.api_key.id);
+        let wrong = api_key_use_case
+            .authenticate(&repos, &mut tx, "rk_wrong")
+            .await;
+        assert!(matches!(wrong, Err(AppError::Unauthorized)));
+
+        api_key_use_case
+            .revoke(&repos, &mut tx, api_key.id)
+            .await
+            .unwrap();
+        let revoked = api_key_use_case
+            .authenticate(&repos, &mut tx, &minted.key)
+            .await;
+        assert!(matches!(revoked, Err(AppError::Unauthorized)));
+        tx.rollback().await.unwrap();
+    }
+
+    #[rocket::async_test]
+    async fn test_revoke_not_found() {
+        let repos = create_repos(None);
+        let mut tx = create_tx_for_test().await.unwrap();
+        let api_key_use_case = ApiKeyUseCaseImpl::new();
+        let result = api_key_use_case.revoke(&repos, &mut tx, -1).await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+        tx.rollback().await.unwrap();
+    }
+}
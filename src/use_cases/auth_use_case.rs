@@ -0,0 +1,455 @@
+use crate::auth::password::{hash_password_blocking, verify_password_blocking};
+use crate::db::DbCon;
+use crate::error::app_error::AppError;
+use crate::models::user_model::User;
+use crate::repositories::repositories::Repos;
+use crate::use_cases::unit_of_work::UnitOfWork;
+use mockall::automock;
+use tracing::instrument;
+
+// 登録したユーザーに付与するロール。書き込みの権限は管理者が別途付与する
+pub const DEFAULT_ROLE: &str = "viewer";
+
+pub struct AuthUseCaseImpl {}
+
+impl AuthUseCaseImpl {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+#[automock]
+#[async_trait]
+pub trait AuthUseCase: Send + Sync {
+    async fn register(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        name: &str,
+        age: i32,
+        email: &str,
+        password: &str,
+    ) -> Result<User, AppError>;
+
+    // ユーザーと、ログインのcookieに入れるセッションの世代を返す
+    async fn login(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        email: &str,
+        password: &str,
+    ) -> Result<(User, i32), AppError>;
+
+    // 現在のセッションの世代。資格情報の無いユーザーはNone
+    async fn session_epoch(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        user_id: i32,
+    ) -> Result<Option<i32>, AppError>;
+
+    // セッションの世代を進め、発行済みのcookieをすべて無効にする
+    async fn logout(&self, repos: &Repos, db_con: &mut DbCon, user_id: i32)
+        -> Result<(), AppError>;
+
+    // 他のセッションは無効になる。新しいセッションの世代を返す
+    async fn change_password(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        user_id: i32,
+        current_password: &str,
+        new_password: &str,
+    ) -> Result<i32, AppError>;
+
+    async fn current_user(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        user_id: i32,
+    ) -> Result<User, AppError>;
+
+    async fn permissions(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        user_id: i32,
+    ) -> Result<Vec<String>, AppError>;
+
+    async fn role_permissions(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        roles: &[String],
+    ) -> Result<Vec<String>, AppError>;
+
+    async fn assign_role(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        user_id: i32,
+        role: &str,
+    ) -> Result<(), AppError>;
+}
+
+#[async_trait]
+impl AuthUseCase for AuthUseCaseImpl {
+    #[instrument(name = "auth_use_case/register", skip_all)]
+    async fn register(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        name: &str,
+        age: i32,
+        email: &str,
+        password: &str,
+    ) -> Result<User, AppError> {
+        let password_hash = hash_password_blocking(password).await?;
+        let mut uow = UnitOfWork::begin(db_con).await?;
+        let result = async {
+            let user = repos.user.create(uow.con(), &name.to_string(), age).await?;
+            match repos
+                .credential
+                .create(uow.con(), user.id, email, &password_hash)
+                .await
+            {
+                Ok(_) => {}
+                Err(e) if e.is_unique_violation() => {
+                    return Err(AppError::new(409, "email is already registered"))
+                }
+                Err(e) => return Err(AppError::from(e)),
+            }
+            repos.role.assign(uow.con(), user.id, DEFAULT_ROLE).await?;
+            Ok(user)
+        }
+        .await;
+        uow.finish(result).await
+    }
+
+    #[instrument(name = "auth_use_case/login", skip_all)]
+    async fn login(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        email: &str,
+        password: &str,
+    ) -> Result<(User, i32), AppError> {
+        let credential = repos.credential.find_by_email(&mut *db_con, email).await?;
+        let credential = match credential {
+            Some(credential) => {
+                if !verify_password_blocking(password, &credential.password_hash).await? {
+                    return Err(AppError::Unauthorized);
+                }
+                credential
+            }
+            None => {
+                // メールアドレスが存在しない場合も同じだけ時間をかけ、応答時間から推測されないようにする
+                let _ = hash_password_blocking(password).await;
+                return Err(AppError::Unauthorized);
+            }
+        };
+        let user = repos
+            .user
+            .find_by_id(&mut *db_con, credential.user_id)
+            .await?
+            .ok_or(AppError::Unauthorized)?;
+        Ok((user, credential.session_epoch))
+    }
+
+    #[instrument(name = "auth_use_case/session_epoch", skip_all, fields(user_id = %user_id))]
+    async fn session_epoch(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        user_id: i32,
+    ) -> Result<Option<i32>, AppError> {
+        let credential = repos
+            .credential
+            .find_by_user_id(&mut *db_con, user_id)
+            .await?;
+        Ok(credential.map(|credential| credential.session_epoch))
+    }
+
+    #[instrument(name = "auth_use_case/logout", skip_all, fields(user_id = %user_id))]
+    async fn logout(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        user_id: i32,
+    ) -> Result<(), AppError> {
+        repos
+            .credential
+            .bump_session_epoch(&mut *db_con, user_id)
+            .await?;
+        Ok(())
+    }
+
+    #[instrument(name = "auth_use_case/change_password", skip_all, fields(user_id = %user_id))]
+    async fn change_password(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        user_id: i32,
+        current_password: &str,
+        new_password: &str,
+    ) -> Result<i32, AppError> {
+        let credential = repos
+            .credential
+            .find_by_user_id(&mut *db_con, user_id)
+            .await?
+            .ok_or(AppError::Unauthorized)?;
+        if !verify_password_blocking(current_password, &credential.password_hash).await? {
+            return Err(AppError::new(400, "current password is incorrect"));
+        }
+        let password_hash = hash_password_blocking(new_password).await?;
+        repos
+            .credential
+            .update_password(&mut *db_con, user_id, &password_hash)
+            .await?
+            .ok_or(AppError::Unauthorized)
+    }
+
+    #[instrument(name = "auth_use_case/current_user", skip_all, fields(user_id = %user_id))]
+    async fn current_user(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        user_id: i32,
+    ) -> Result<User, AppError> {
+        repos
+            .user
+            .find_by_id(&mut *db_con, user_id)
+            .await?
+            .ok_or(AppError::Unauthorized)
+    }
+
+    #[instrument(name = "auth_use_case/permissions", skip_all, fields(user_id = %user_id))]
+    async fn permissions(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        user_id: i32,
+    ) -> Result<Vec<String>, AppError> {
+        let permissions = repos.role.find_permissions(&mut *db_con, user_id).await?;
+        Ok(permissions)
+    }
+
+    #[instrument(name = "auth_use_case/role_permissions", skip_all)]
+    async fn role_permissions(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        roles: &[String],
+    ) -> Result<Vec<String>, AppError> {
+        let permissions = repos
+            .role
+            .find_role_permissions(&mut *db_con, roles)
+            .await?;
+        Ok(permissions)
+    }
+
+    #[instrument(name = "auth_use_case/assign_role", skip_all, fields(user_id = %user_id, role = %role))]
+    async fn assign_role(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        user_id: i32,
+        role: &str,
+    ) -> Result<(), AppError> {
+        // 存在しないユーザーやロールは外部キー制約で弾かれる
+        match repos.role.assign(&mut *db_con, user_id, role).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.is_foreign_key_violation() => Err(AppError::new(
+                400,
+                &format!("unknown user {} or role {}", user_id, role),
+            )),
+            Err(e) => Err(AppError::from(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::password::hash_password;
+    use crate::models::credential_model::Credential;
+    use crate::repositories::credential_repo::MockCredentialRepo;
+    use crate::repositories::repositories::create_repos;
+    use crate::repositories::user_repo::MockUserRepo;
+    use crate::test::app::create_repos_for_test;
+    use crate::test::db::{create_db_con_for_test, create_tx_for_test};
+    use crate::test::fixture::user::user_fixture;
+
+    fn repos_with_credential(password: &str) -> Repos {
+        let password_hash = hash_password(password).unwrap();
+        let mut mock_credential_repo = MockCredentialRepo::new();
+        mock_credential_repo
+            .expect_find_by_email()
+            .returning(move |_, email| {
+                Ok(Some(Credential {
+                    user_id: 1,
+                    email: email.to_string(),
+                    password_hash: password_hash.clone(),
+                    session_epoch: 3,
+                }))
+            });
+        let mut mock_user_repo = MockUserRepo::new();
+        mock_user_repo
+            .expect_find_by_id()
+            .returning(|_, id| Ok(Some(user_fixture(id as usize))));
+        let mut repos = create_repos_for_test();
+        repos.credential = Box::new(mock_credential_repo);
+        repos.user = Box::new(mock_user_repo);
+        repos
+    }
+
+    #[rocket::async_test]
+    async fn test_login_success() {
+        let repos = repos_with_credential("password");
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let auth_use_case = AuthUseCaseImpl::new();
+        let result = auth_use_case
+            .login(&repos, &mut db_con, "taro@example.com", "password")
+            .await;
+        let (user, session_epoch) = result.unwrap();
+        assert_eq!(user.id, 1);
+        assert_eq!(session_epoch, 3);
+    }
+
+    #[rocket::async_test]
+    async fn test_login_wrong_password() {
+        let repos = repos_with_credential("password");
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let auth_use_case = AuthUseCaseImpl::new();
+        let result = auth_use_case
+            .login(&repos, &mut db_con, "taro@example.com", "wrong")
+            .await;
+        assert!(matches!(result, Err(AppError::Unauthorized)));
+    }
+
+    #[rocket::async_test]
+    async fn test_register_duplicate_email_is_rolled_back() {
+        let repos = create_repos(None);
+        let mut tx = create_tx_for_test().await.unwrap();
+        let auth_use_case = AuthUseCaseImpl::new();
+        let name = "register_rollback_test".to_string();
+        auth_use_case
+            .register(&repos, &mut tx, &name, 20, "taro@example.com", "password")
+            .await
+            .unwrap();
+        let result = auth_use_case
+            .register(&repos, &mut tx, &name, 20, "taro@example.com", "password")
+            .await;
+        assert_eq!(result.unwrap_err().status_code(), 409);
+        let count = sqlx::query_scalar!("SELECT COUNT(*) FROM users WHERE name = $1", name)
+            .fetch_one(&mut *tx)
+            .await
+            .unwrap();
+        assert_eq!(count, Some(1));
+        tx.rollback().await.unwrap();
+    }
+
+    #[rocket::async_test]
+    async fn test_register_duplicate_email_ignores_case() {
+        let repos = create_repos(None);
+        let mut tx = create_tx_for_test().await.unwrap();
+        let auth_use_case = AuthUseCaseImpl::new();
+        let name = "register_case_test".to_string();
+        auth_use_case
+            .register(&repos, &mut tx, &name, 20, "Hanako@example.com", "password")
+            .await
+            .unwrap();
+        let result = auth_use_case
+            .register(&repos, &mut tx, &name, 20, "hanako@EXAMPLE.com", "password")
+            .await;
+        assert_eq!(result.unwrap_err().status_code(), 409);
+        let result = auth_use_case
+            .login(&repos, &mut tx, "HANAKO@example.com", "password")
+            .await;
+        assert!(result.is_ok());
+        tx.rollback().await.unwrap();
+    }
+
+    #[rocket::async_test]
+    async fn test_logout_and_change_password_bump_session_epoch() {
+        let repos = create_repos(None);
+        let mut tx = create_tx_for_test().await.unwrap();
+        let auth_use_case = AuthUseCaseImpl::new();
+        let name = "session_epoch_test".to_string();
+        let user = auth_use_case
+            .register(&repos, &mut tx, &name, 20, "jiro@example.com", "password")
+            .await
+            .unwrap();
+        let (_, epoch) = auth_use_case
+            .login(&repos, &mut tx, "jiro@example.com", "password")
+            .await
+            .unwrap();
+        assert_eq!(epoch, 0);
+        auth_use_case
+            .logout(&repos, &mut tx, user.id)
+            .await
+            .unwrap();
+        let epoch = auth_use_case.session_epoch(&repos, &mut tx, user.id).await;
+        assert_eq!(epoch.unwrap(), Some(1));
+
+        let result = auth_use_case
+            .change_password(&repos, &mut tx, user.id, "wrong", "new password")
+            .await;
+        assert_eq!(result.unwrap_err().status_code(), 400);
+        let epoch = auth_use_case
+            .change_password(&repos, &mut tx, user.id, "password", "new password")
+            .await
+            .unwrap();
+        assert_eq!(epoch, 2);
+        let result = auth_use_case
+            .login(&repos, &mut tx, "jiro@example.com", "password")
+            .await;
+        assert!(matches!(result, Err(AppError::Unauthorized)));
+        let (_, epoch) = auth_use_case
+            .login(&repos, &mut tx, "jiro@example.com", "new password")
+            .await
+            .unwrap();
+        assert_eq!(epoch, 2);
+        tx.rollback().await.unwrap();
+    }
+
+    #[rocket::async_test]
+    async fn test_register_assigns_default_role() {
+        let repos = create_repos(None);
+        let mut tx = create_tx_for_test().await.unwrap();
+        let auth_use_case = AuthUseCaseImpl::new();
+        let name = "register_role_test".to_string();
+        let user = auth_use_case
+            .register(&repos, &mut tx, &name, 20, "viewer@example.com", "password")
+            .await
+            .unwrap();
+        let roles = repos.role.find_roles(&mut tx, user.id).await.unwrap();
+        assert_eq!(roles, vec![DEFAULT_ROLE]);
+        let permissions = auth_use_case
+            .permissions(&repos, &mut tx, user.id)
+            .await
+            .unwrap();
+        assert!(permissions.is_empty());
+        tx.rollback().await.unwrap();
+    }
+
+    #[rocket::async_test]
+    async fn test_assign_role() {
+        let repos = create_repos(None);
+        let mut tx = create_tx_for_test().await.unwrap();
+        let auth_use_case = AuthUseCaseImpl::new();
+        let name = "assign_role_test".to_string();
+        let user = repos.user.create(&mut tx, &name, 20).await.unwrap();
+        auth_use_case
+            .assign_role(&repos, &mut tx, user.id, "admin")
+            .await
+            .unwrap();
+        let roles = repos.role.find_roles(&mut tx, user.id).await.unwrap();
+        assert_eq!(roles, vec!["admin"]);
+        let result = auth_use_case
+            .assign_role(&repos, &mut tx, user.id, "owner")
+            .await;
+        assert_eq!(result.unwrap_err().status_code(), 400);
+        tx.rollback().await.unwrap();
+    }
+}
@@ -0,0 +1,408 @@
+use crate::db::DbCon;
+use crate::dto::order_dto::OrderItemInput;
+use crate::error::app_error::AppError;
+use crate::models::cart_model::{CartLine, CartOwner, CartView};
+use crate::models::order_model::OrderDetail;
+use crate::repositories::repositories::Repos;
+use crate::use_cases::order_use_case::place_order;
+use crate::use_cases::unit_of_work::UnitOfWork;
+use mockall::automock;
+use tracing::instrument;
+use uuid::Uuid;
+
+pub struct CartUseCaseImpl {}
+
+impl CartUseCaseImpl {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+#[automock]
+#[async_trait]
+pub trait CartUseCase: Send + Sync {
+    async fn view(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        owner: &CartOwner,
+    ) -> Result<CartView, AppError>;
+
+    async fn add_item(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        owner: &CartOwner,
+        product_id: i32,
+        quantity: i32,
+    ) -> Result<CartView, AppError>;
+
+    async fn set_quantity(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        owner: &CartOwner,
+        product_id: i32,
+        quantity: i32,
+    ) -> Result<CartView, AppError>;
+
+    async fn remove_item(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        owner: &CartOwner,
+        product_id: i32,
+    ) -> Result<CartView, AppError>;
+
+    async fn claim(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        user_id: i32,
+        token: Uuid,
+    ) -> Result<(), AppError>;
+
+    async fn checkout(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        owner: &CartOwner,
+    ) -> Result<OrderDetail, AppError>;
+}
+
+#[async_trait]
+impl CartUseCase for CartUseCaseImpl {
+    // まだカートが無ければ空のカートを返す (GETで行を作らない)
+    #[instrument(name = "cart_use_case/view", skip_all)]
+    async fn view(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        owner: &CartOwner,
+    ) -> Result<CartView, AppError> {
+        let lines = match repos.cart.find(&mut *db_con, owner).await? {
+            Some(cart) => repos.cart.find_lines(&mut *db_con, cart.id).await?,
+            None => vec![],
+        };
+        cart_view(lines)
+    }
+
+    #[instrument(name = "cart_use_case/add_item", skip_all, fields(product_id = %product_id))]
+    async fn add_item(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        owner: &CartOwner,
+        product_id: i32,
+        quantity: i32,
+    ) -> Result<CartView, AppError> {
+        let mut uow = UnitOfWork::begin(db_con).await?;
+        let result = add_to_cart(repos, &mut uow, owner, product_id, quantity).await;
+        uow.finish(result).await
+    }
+
+    #[instrument(name = "cart_use_case/set_quantity", skip_all, fields(product_id = %product_id))]
+    async fn set_quantity(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        owner: &CartOwner,
+        product_id: i32,
+        quantity: i32,
+    ) -> Result<CartView, AppError> {
+        let cart = repos
+            .cart
+            .find(&mut *db_con, owner)
+            .await?
+            .ok_or(AppError::NotFound)?;
+        match repos
+            .cart
+            .set_quantity(&mut *db_con, cart.id, product_id, quantity)
+            .await
+        {
+            Ok(()) => {}
+            Err(e) if e.is_row_not_found() => return Err(AppError::NotFound),
+            Err(e) => return Err(AppError::from(e)),
+        }
+        cart_view(repos.cart.find_lines(&mut *db_con, cart.id).await?)
+    }
+
+    #[instrument(name = "cart_use_case/remove_item", skip_all, fields(product_id = %product_id))]
+    async fn remove_item(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        owner: &CartOwner,
+        product_id: i32,
+    ) -> Result<CartView, AppError> {
+        let cart = repos
+            .cart
+            .find(&mut *db_con, owner)
+            .await?
+            .ok_or(AppError::NotFound)?;
+        match repos
+            .cart
+            .remove_item(&mut *db_con, cart.id, product_id)
+            .await
+        {
+            Ok(()) => {}
+            Err(e) if e.is_row_not_found() => return Err(AppError::NotFound),
+            Err(e) => return Err(AppError::from(e)),
+        }
+        cart_view(repos.cart.find_lines(&mut *db_con, cart.id).await?)
+    }
+
+    // ログイン前に使っていた匿名のカートをユーザーのカートにまとめる
+    #[instrument(name = "cart_use_case/claim", skip_all, fields(user_id = %user_id))]
+    async fn claim(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        user_id: i32,
+        token: Uuid,
+    ) -> Result<(), AppError> {
+        let mut uow = UnitOfWork::begin(db_con).await?;
+        let result = claim_cart(repos, &mut uow, user_id, token).await;
+        uow.finish(result).await
+    }
+
+    // 注文の作成(在庫の引き当てを含む)とカートの削除を1つのトランザクションで行う
+    #[instrument(name = "cart_use_case/checkout", skip_all)]
+    async fn checkout(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        owner: &CartOwner,
+    ) -> Result<OrderDetail, AppError> {
+        let user_id = match owner {
+            CartOwner::User(user_id) => *user_id,
+            CartOwner::Anonymous(_) => {
+                return Err(AppError::new(401, "Login is required to check out"))
+            }
+        };
+        let mut uow = UnitOfWork::begin(db_con).await?;
+        let result = checkout_cart(repos, &mut uow, owner, user_id).await;
+        uow.finish(result).await
+    }
+}
+
+async fn add_to_cart(
+    repos: &Repos,
+    uow: &mut UnitOfWork<'_>,
+    owner: &CartOwner,
+    product_id: i32,
+    quantity: i32,
+) -> Result<CartView, AppError> {
+    let product = repos
+        .product
+        .find_by_id(uow.con(), product_id)
+        .await?
+        .ok_or_else(|| AppError::new(400, &format!("product {} does not exist", product_id)))?;
+    let cart = repos.cart.find_or_create(uow.con(), owner).await?;
+    let lines = repos.cart.find_lines(uow.con(), cart.id).await?;
+    // 注文と同じく、1つのカートに別の通貨の商品は入れられない
+    if lines.iter().any(|line| line.currency != product.currency) {
+        return Err(AppError::new(
+            400,
+            "products in a cart must have the same currency",
+        ));
+    }
+    repos
+        .cart
+        .add_item(uow.con(), cart.id, product_id, quantity)
+        .await?;
+    cart_view(repos.cart.find_lines(uow.con(), cart.id).await?)
+}
+
+async fn claim_cart(
+    repos: &Repos,
+    uow: &mut UnitOfWork<'_>,
+    user_id: i32,
+    token: Uuid,
+) -> Result<(), AppError> {
+    let from = match repos
+        .cart
+        .find(uow.con(), &CartOwner::Anonymous(token))
+        .await?
+    {
+        Some(cart) => cart,
+        None => return Ok(()),
+    };
+    let into = repos
+        .cart
+        .find_or_create(uow.con(), &CartOwner::User(user_id))
+        .await?;
+    repos.cart.merge(uow.con(), from.id, into.id).await?;
+    Ok(())
+}
+
+async fn checkout_cart(
+    repos: &Repos,
+    uow: &mut UnitOfWork<'_>,
+    owner: &CartOwner,
+    user_id: i32,
+) -> Result<OrderDetail, AppError> {
+    let cart = repos.cart.find(uow.con(), owner).await?;
+    let lines = match &cart {
+        Some(cart) => repos.cart.find_lines(uow.con(), cart.id).await?,
+        None => vec![],
+    };
+    let cart = match cart {
+        Some(cart) if !lines.is_empty() => cart,
+        _ => return Err(AppError::new(400, "cart is empty")),
+    };
+    let items: Vec<OrderItemInput> = lines
+        .iter()
+        .map(|line| OrderItemInput {
+            product_id: line.product_id,
+            quantity: line.quantity,
+        })
+        .collect();
+    let order = place_order(repos, uow, user_id, &items).await?;
+    repos.cart.delete(uow.con(), cart.id).await?;
+    Ok(order)
+}
+
+fn cart_view(lines: Vec<CartLine>) -> Result<CartView, AppError> {
+    let total = lines
+        .iter()
+        .try_fold(0i64, |total, line| total.checked_add(line.subtotal))
+        .ok_or_else(|| AppError::new(400, "cart total is too large"))?;
+    let currency = lines.first().map(|line| line.currency.clone());
+    Ok(CartView {
+        items: lines,
+        currency,
+        total,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repositories::repositories::create_repos;
+    use crate::test::db::create_tx_for_test;
+    use crate::test::repositories::prepare::product::create_product;
+    use crate::test::repositories::prepare::user::create_user;
+
+    #[rocket::async_test]
+    async fn test_add_change_and_remove() {
+        let repos = create_repos(None);
+        let mut tx = create_tx_for_test().await.unwrap();
+        let apple = create_product(&mut tx).await.unwrap();
+        let orange = create_product(&mut tx).await.unwrap();
+        let owner = CartOwner::Anonymous(Uuid::new_v4());
+        let cart_use_case = CartUseCaseImpl::new();
+
+        let cart = cart_use_case.view(&repos, &mut tx, &owner).await.unwrap();
+        assert_eq!(cart.items, vec![]);
+        assert_eq!(cart.currency, None);
+        assert_eq!(cart.total, 0);
+
+        cart_use_case
+            .add_item(&repos, &mut tx, &owner, apple.id, 2)
+            .await
+            .unwrap();
+        let cart = cart_use_case
+            .add_item(&repos, &mut tx, &owner, orange.id, 1)
+            .await
+            .unwrap();
+        assert_eq!(cart.items.len(), 2);
+        assert_eq!(cart.currency.as_deref(), Some("JPY"));
+        assert_eq!(cart.total, apple.price * 2 + orange.price);
+
+        let cart = cart_use_case
+            .set_quantity(&repos, &mut tx, &owner, apple.id, 5)
+            .await
+            .unwrap();
+        assert_eq!(cart.items[0].quantity, 5);
+        assert_eq!(cart.total, apple.price * 5 + orange.price);
+
+        let cart = cart_use_case
+            .remove_item(&repos, &mut tx, &owner, orange.id)
+            .await
+            .unwrap();
+        assert_eq!(cart.total, apple.price * 5);
+        let result = cart_use_case
+            .remove_item(&repos, &mut tx, &owner, orange.id)
+            .await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+
+        let result = cart_use_case.add_item(&repos, &mut tx, &owner, -1, 1).await;
+        assert!(matches!(result, Err(e) if e.status_code() == 400));
+        tx.rollback().await.unwrap();
+    }
+
+    #[rocket::async_test]
+    async fn test_claim_and_checkout() {
+        let repos = create_repos(None);
+        let mut tx = create_tx_for_test().await.unwrap();
+        let user = create_user(&mut tx).await.unwrap();
+        let product = create_product(&mut tx).await.unwrap();
+        let token = Uuid::new_v4();
+        let anonymous = CartOwner::Anonymous(token);
+        let owner = CartOwner::User(user.id);
+        let cart_use_case = CartUseCaseImpl::new();
+        cart_use_case
+            .add_item(&repos, &mut tx, &anonymous, product.id, 3)
+            .await
+            .unwrap();
+
+        let result = cart_use_case.checkout(&repos, &mut tx, &anonymous).await;
+        assert!(matches!(result, Err(e) if e.status_code() == 401));
+
+        cart_use_case
+            .claim(&repos, &mut tx, user.id, token)
+            .await
+            .unwrap();
+        let cart = cart_use_case
+            .view(&repos, &mut tx, &anonymous)
+            .await
+            .unwrap();
+        assert!(cart.items.is_empty());
+        let cart = cart_use_case.view(&repos, &mut tx, &owner).await.unwrap();
+        assert_eq!(cart.items[0].quantity, 3);
+
+        let order = cart_use_case
+            .checkout(&repos, &mut tx, &owner)
+            .await
+            .unwrap();
+        assert_eq!(order.order.user_id, user.id);
+        assert_eq!(order.order.total, cart.total);
+        assert_eq!(order.items[0].quantity, 3);
+        let stock = repos
+            .product
+            .find_by_id(&mut tx, product.id)
+            .await
+            .unwrap()
+            .unwrap()
+            .stock;
+        assert_eq!(stock, product.stock - 3);
+        let cart = cart_use_case.view(&repos, &mut tx, &owner).await.unwrap();
+        assert!(cart.items.is_empty());
+
+        let result = cart_use_case.checkout(&repos, &mut tx, &owner).await;
+        assert!(matches!(result, Err(e) if e.to_string() == "cart is empty"));
+        tx.rollback().await.unwrap();
+    }
+
+    // 在庫が足りなければ注文は作られず、カートもそのまま残る
+    #[rocket::async_test]
+    async fn test_checkout_out_of_stock_keeps_cart() {
+        let repos = create_repos(None);
+        let mut tx = create_tx_for_test().await.unwrap();
+        let user = create_user(&mut tx).await.unwrap();
+        let product = create_product(&mut tx).await.unwrap();
+        let owner = CartOwner::User(user.id);
+        let cart_use_case = CartUseCaseImpl::new();
+        cart_use_case
+            .add_item(&repos, &mut tx, &owner, product.id, product.stock + 1)
+            .await
+            .unwrap();
+
+        let result = cart_use_case.checkout(&repos, &mut tx, &owner).await;
+        assert!(matches!(result, Err(AppError::OutOfStock { .. })));
+        let cart = cart_use_case.view(&repos, &mut tx, &owner).await.unwrap();
+        assert_eq!(cart.items[0].quantity, product.stock + 1);
+        tx.rollback().await.unwrap();
+    }
+}
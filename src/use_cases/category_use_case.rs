@@ -0,0 +1,126 @@
+use crate::db::DbCon;
+use crate::dto::category_dto::CategoryInput;
+use crate::error::app_error::AppError;
+use crate::models::category_model::Category;
+use crate::repositories::repositories::Repos;
+use crate::use_cases::unit_of_work::UnitOfWork;
+use mockall::automock;
+use tracing::instrument;
+
+pub struct CategoryUseCaseImpl {}
+
+impl CategoryUseCaseImpl {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+#[automock]
+#[async_trait]
+pub trait CategoryUseCase: Send + Sync {
+    async fn create(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        input: &CategoryInput,
+    ) -> Result<Category, AppError>;
+
+    async fn find_all(&self, repos: &Repos, db_con: &mut DbCon) -> Result<Vec<Category>, AppError>;
+}
+
+#[async_trait]
+impl CategoryUseCase for CategoryUseCaseImpl {
+    #[instrument(name = "category_use_case/create", skip_all)]
+    async fn create(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        input: &CategoryInput,
+    ) -> Result<Category, AppError> {
+        let mut uow = UnitOfWork::begin(db_con).await?;
+        let result = create_category(repos, &mut uow, input).await;
+        uow.finish(result).await
+    }
+
+    #[instrument(name = "category_use_case/find_all", skip_all)]
+    async fn find_all(&self, repos: &Repos, db_con: &mut DbCon) -> Result<Vec<Category>, AppError> {
+        let categories = repos.category.find_all(&mut *db_con).await?;
+        Ok(categories)
+    }
+}
+
+async fn create_category(
+    repos: &Repos,
+    uow: &mut UnitOfWork<'_>,
+    input: &CategoryInput,
+) -> Result<Category, AppError> {
+    if let Some(parent_id) = input.parent_id {
+        if repos
+            .category
+            .find_by_id(uow.con(), parent_id)
+            .await?
+            .is_none()
+        {
+            return Err(category_not_found(parent_id));
+        }
+    }
+    let category = repos.category.create(uow.con(), input).await?;
+    Ok(category)
+}
+
+// 入力で指定されたカテゴリが無いのはリクエストの誤りなので400にする
+pub fn category_not_found(id: i32) -> AppError {
+    AppError::new(400, &format!("category {} does not exist", id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repositories::repositories::create_repos;
+    use crate::test::db::create_tx_for_test;
+
+    #[rocket::async_test]
+    async fn test_create_with_parent() {
+        let repos = create_repos(None);
+        let mut tx = create_tx_for_test().await.unwrap();
+        let category_use_case = CategoryUseCaseImpl::new();
+        let fruit = category_use_case
+            .create(
+                &repos,
+                &mut tx,
+                &CategoryInput {
+                    name: "fruit".to_string(),
+                    parent_id: None,
+                },
+            )
+            .await
+            .unwrap();
+        let citrus = category_use_case
+            .create(
+                &repos,
+                &mut tx,
+                &CategoryInput {
+                    name: "citrus".to_string(),
+                    parent_id: Some(fruit.id),
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(citrus.parent_id, Some(fruit.id));
+
+        let result = category_use_case
+            .create(
+                &repos,
+                &mut tx,
+                &CategoryInput {
+                    name: "orphan".to_string(),
+                    parent_id: Some(-1),
+                },
+            )
+            .await;
+        let e = result.unwrap_err();
+        assert_eq!(e.status_code(), 400);
+        assert_eq!(e.to_string(), "category -1 does not exist");
+        tx.rollback().await.unwrap();
+    }
+}
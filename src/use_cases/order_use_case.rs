@@ -0,0 +1,434 @@
+use crate::db::DbCon;
+use crate::dto::order_dto::OrderItemInput;
+use crate::error::app_error::AppError;
+use crate::models::order_model::{NewOrderItem, Order, OrderDetail};
+use crate::models::product_model::{Product, StockReservation};
+use crate::repositories::repositories::Repos;
+use crate::use_cases::unit_of_work::UnitOfWork;
+use mockall::automock;
+use std::collections::BTreeMap;
+use tracing::instrument;
+
+pub struct OrderUseCaseImpl {}
+
+impl OrderUseCaseImpl {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+#[automock]
+#[async_trait]
+pub trait OrderUseCase: Send + Sync {
+    async fn place(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        user_id: i32,
+        items: &[OrderItemInput],
+    ) -> Result<OrderDetail, AppError>;
+
+    async fn find_by_user(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        user_id: i32,
+    ) -> Result<Vec<Order>, AppError>;
+
+    async fn find_by_id(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        user_id: i32,
+        id: i32,
+    ) -> Result<OrderDetail, AppError>;
+}
+
+#[async_trait]
+impl OrderUseCase for OrderUseCaseImpl {
+    // 商品の確認から明細の書き込みまで1つのトランザクションで行い、途中で失敗したら何も残さない
+    #[instrument(name = "order_use_case/place", skip_all, fields(user_id = %user_id))]
+    async fn place(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        user_id: i32,
+        items: &[OrderItemInput],
+    ) -> Result<OrderDetail, AppError> {
+        let mut uow = UnitOfWork::begin(db_con).await?;
+        let result = place_order(repos, &mut uow, user_id, items).await;
+        uow.finish(result).await
+    }
+
+    #[instrument(name = "order_use_case/find_by_user", skip_all, fields(user_id = %user_id))]
+    async fn find_by_user(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        user_id: i32,
+    ) -> Result<Vec<Order>, AppError> {
+        let orders = repos.order.find_by_user(&mut *db_con, user_id).await?;
+        Ok(orders)
+    }
+
+    // 他のユーザーの注文は存在しないものとして扱う
+    #[instrument(name = "order_use_case/find_by_id", skip_all, fields(id = %id))]
+    async fn find_by_id(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        user_id: i32,
+        id: i32,
+    ) -> Result<OrderDetail, AppError> {
+        let order = match repos.order.find_by_id(&mut *db_con, id).await? {
+            Some(order) if order.user_id == user_id => order,
+            _ => return Err(AppError::NotFound),
+        };
+        let items = repos.order.find_items(&mut *db_con, id).await?;
+        Ok(OrderDetail { order, items })
+    }
+}
+
+// カートのチェックアウトからも同じトランザクションの中で使う
+pub async fn place_order(
+    repos: &Repos,
+    uow: &mut UnitOfWork<'_>,
+    user_id: i32,
+    items: &[OrderItemInput],
+) -> Result<OrderDetail, AppError> {
+    let quantities = merge_quantities(items)?;
+    let ids: Vec<i32> = quantities.keys().copied().collect();
+    let products = repos.product.find_by_ids(uow.con(), &ids).await?;
+    let lines = order_lines(&quantities, &products)?;
+    let currency = order_currency(&products)?;
+    let total = order_total(&lines)?;
+    reserve_stock(repos, uow, &lines).await?;
+    let order = repos
+        .order
+        .create(uow.con(), user_id, &currency, total)
+        .await?;
+    let items = repos
+        .order
+        .create_items(uow.con(), order.id, &lines)
+        .await?;
+    Ok(OrderDetail { order, items })
+}
+
+// 明細はid順なので、複数の注文が同じ商品の行ロックを逆の順番で取ってデッドロックすることはない
+// 足りない商品があればエラーを返し、それまでに減らした在庫はUnitOfWorkのロールバックで戻る
+async fn reserve_stock(
+    repos: &Repos,
+    uow: &mut UnitOfWork<'_>,
+    lines: &[NewOrderItem],
+) -> Result<(), AppError> {
+    for line in lines {
+        match repos
+            .product
+            .reserve_stock(uow.con(), line.product_id, line.quantity)
+            .await?
+        {
+            StockReservation::Reserved { .. } => {}
+            StockReservation::Insufficient { available } => {
+                return Err(AppError::OutOfStock {
+                    product_id: line.product_id,
+                    available,
+                })
+            }
+        }
+    }
+    Ok(())
+}
+
+// 商品ごとに数量をまとめる。BTreeMapなのでid順になる
+fn merge_quantities(items: &[OrderItemInput]) -> Result<BTreeMap<i32, i32>, AppError> {
+    let mut quantities = BTreeMap::new();
+    for item in items {
+        let quantity = quantities.entry(item.product_id).or_insert(0i32);
+        *quantity = quantity
+            .checked_add(item.quantity)
+            .ok_or_else(|| AppError::new(400, "quantity is too large"))?;
+    }
+    Ok(quantities)
+}
+
+// 通貨が混ざった注文は合計できないので受け付けない
+fn order_currency(products: &[Product]) -> Result<String, AppError> {
+    let currency = match products.first() {
+        Some(product) => &product.currency,
+        None => return Err(AppError::new(400, "order has no products")),
+    };
+    if products.iter().any(|product| &product.currency != currency) {
+        return Err(AppError::new(
+            400,
+            "products in an order must have the same currency",
+        ));
+    }
+    Ok(currency.clone())
+}
+
+fn order_lines(
+    quantities: &BTreeMap<i32, i32>,
+    products: &[Product],
+) -> Result<Vec<NewOrderItem>, AppError> {
+    let products: BTreeMap<i32, &Product> = products
+        .iter()
+        .map(|product| (product.id, product))
+        .collect();
+    let mut lines = vec![];
+    for (product_id, quantity) in quantities {
+        let product = products
+            .get(product_id)
+            .ok_or_else(|| AppError::new(400, &format!("product {} does not exist", product_id)))?;
+        let subtotal = product
+            .price
+            .checked_mul(*quantity as i64)
+            .ok_or_else(|| AppError::new(400, "order total is too large"))?;
+        lines.push(NewOrderItem {
+            product_id: product.id,
+            name: product.name.clone(),
+            sku: product.sku.clone(),
+            unit_price: product.price,
+            quantity: *quantity,
+            subtotal,
+        });
+    }
+    Ok(lines)
+}
+
+fn order_total(lines: &[NewOrderItem]) -> Result<i64, AppError> {
+    lines
+        .iter()
+        .try_fold(0i64, |total, line| total.checked_add(line.subtotal))
+        .ok_or_else(|| AppError::new(400, "order total is too large"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migration;
+    use crate::repositories::repositories::create_repos;
+    use crate::test::db::{create_database_for_test, create_tx_for_test};
+    use crate::test::repositories::prepare::product::create_product;
+    use crate::test::repositories::prepare::user::create_user;
+    use sqlx::postgres::PgPoolOptions;
+    use std::sync::Arc;
+
+    async fn count_orders(con: &mut DbCon, user_id: i32) -> i64 {
+        sqlx::query_scalar!("SELECT COUNT(*) FROM orders WHERE user_id = $1", user_id)
+            .fetch_one(&mut *con)
+            .await
+            .unwrap()
+            .unwrap_or(0)
+    }
+
+    #[rocket::async_test]
+    async fn test_place_computes_totals() {
+        let repos = create_repos(None);
+        let mut tx = create_tx_for_test().await.unwrap();
+        let user = create_user(&mut tx).await.unwrap();
+        let apple = create_product(&mut tx).await.unwrap();
+        let orange = create_product(&mut tx).await.unwrap();
+        let items = vec![
+            OrderItemInput {
+                product_id: orange.id,
+                quantity: 1,
+            },
+            OrderItemInput {
+                product_id: apple.id,
+                quantity: 2,
+            },
+            OrderItemInput {
+                product_id: orange.id,
+                quantity: 2,
+            },
+        ];
+        let order_use_case = OrderUseCaseImpl::new();
+        let placed = order_use_case
+            .place(&repos, &mut tx, user.id, &items)
+            .await
+            .unwrap();
+        // 同じ商品はまとめられ、id順に並ぶ
+        let quantities: Vec<(Option<i32>, i32, i64)> = placed
+            .items
+            .iter()
+            .map(|item| (item.product_id, item.quantity, item.subtotal))
+            .collect();
+        assert_eq!(
+            quantities,
+            vec![(Some(apple.id), 2, 240), (Some(orange.id), 3, 360)]
+        );
+        assert_eq!(placed.order.total, 600);
+        assert_eq!(placed.order.currency, "JPY");
+
+        let found = order_use_case
+            .find_by_id(&repos, &mut tx, user.id, placed.order.id)
+            .await
+            .unwrap();
+        assert_eq!(found, placed);
+        let result = order_use_case
+            .find_by_id(&repos, &mut tx, user.id + 1, placed.order.id)
+            .await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+        tx.rollback().await.unwrap();
+    }
+
+    #[rocket::async_test]
+    async fn test_place_reserves_stock() {
+        let repos = create_repos(None);
+        let mut tx = create_tx_for_test().await.unwrap();
+        let user = create_user(&mut tx).await.unwrap();
+        let apple = create_product(&mut tx).await.unwrap();
+        let orange = create_product(&mut tx).await.unwrap();
+        let order_use_case = OrderUseCaseImpl::new();
+        let items = vec![OrderItemInput {
+            product_id: apple.id,
+            quantity: 4,
+        }];
+        order_use_case
+            .place(&repos, &mut tx, user.id, &items)
+            .await
+            .unwrap();
+        let found = repos.product.find_by_id(&mut tx, apple.id).await.unwrap();
+        assert_eq!(found.unwrap().stock, 6);
+
+        // 2つ目の商品が足りなければ、1つ目の引き当ても取り消される
+        let items = vec![
+            OrderItemInput {
+                product_id: apple.id,
+                quantity: 1,
+            },
+            OrderItemInput {
+                product_id: orange.id,
+                quantity: 11,
+            },
+        ];
+        let result = order_use_case.place(&repos, &mut tx, user.id, &items).await;
+        assert!(matches!(
+            result,
+            Err(AppError::OutOfStock { product_id, available: 10 }) if product_id == orange.id
+        ));
+        let found = repos.product.find_by_id(&mut tx, apple.id).await.unwrap();
+        assert_eq!(found.unwrap().stock, 6);
+        assert_eq!(count_orders(&mut tx, user.id).await, 1);
+        tx.rollback().await.unwrap();
+    }
+
+    // 別々のコネクションから同時に注文し、在庫の数だけが成功することを確かめる
+    // コミットが必要なので使い捨てのデータベースで行う
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_orders_never_oversell() {
+        const STOCK: i32 = 5;
+        const BUYERS: usize = 20;
+        let database = create_database_for_test().await.unwrap();
+        let pool = PgPoolOptions::new()
+            .max_connections(BUYERS as u32)
+            .connect(&database.url)
+            .await
+            .unwrap();
+        migration::up(&pool).await.unwrap();
+        let mut con = pool.acquire().await.unwrap();
+        let user = create_user(&mut con).await.unwrap();
+        let product = create_product(&mut con).await.unwrap();
+        sqlx::query!(
+            "UPDATE products SET stock = $1 WHERE id = $2",
+            STOCK,
+            product.id
+        )
+        .execute(&mut *con)
+        .await
+        .unwrap();
+        drop(con);
+
+        let repos = Arc::new(create_repos(None));
+        let mut tasks = vec![];
+        for _ in 0..BUYERS {
+            let pool = pool.clone();
+            let repos = repos.clone();
+            tasks.push(tokio::spawn(async move {
+                let mut con = pool.acquire().await.unwrap();
+                let items = vec![OrderItemInput {
+                    product_id: product.id,
+                    quantity: 1,
+                }];
+                OrderUseCaseImpl::new()
+                    .place(&repos, &mut con, user.id, &items)
+                    .await
+            }));
+        }
+        let mut placed = 0;
+        for task in tasks {
+            match task.await.unwrap() {
+                Ok(_) => placed += 1,
+                Err(AppError::OutOfStock { available, .. }) => assert_eq!(available, 0),
+                Err(e) => panic!("unexpected error {}", e),
+            }
+        }
+        assert_eq!(placed, STOCK);
+
+        let mut con = pool.acquire().await.unwrap();
+        let stock = repos
+            .product
+            .find_by_id(&mut con, product.id)
+            .await
+            .unwrap()
+            .unwrap()
+            .stock;
+        assert_eq!(stock, 0);
+        assert_eq!(count_orders(&mut con, user.id).await, STOCK as i64);
+        drop(con);
+        pool.close().await;
+        database.drop().await.unwrap();
+    }
+
+    #[rocket::async_test]
+    async fn test_place_rejects_missing_product() {
+        let repos = create_repos(None);
+        let mut tx = create_tx_for_test().await.unwrap();
+        let user = create_user(&mut tx).await.unwrap();
+        let product = create_product(&mut tx).await.unwrap();
+        let items = vec![
+            OrderItemInput {
+                product_id: product.id,
+                quantity: 1,
+            },
+            OrderItemInput {
+                product_id: -1,
+                quantity: 1,
+            },
+        ];
+        let order_use_case = OrderUseCaseImpl::new();
+        let result = order_use_case.place(&repos, &mut tx, user.id, &items).await;
+        let e = result.unwrap_err();
+        assert_eq!(e.status_code(), 400);
+        assert_eq!(e.to_string(), "product -1 does not exist");
+        assert_eq!(count_orders(&mut tx, user.id).await, 0);
+        tx.rollback().await.unwrap();
+    }
+
+    #[rocket::async_test]
+    async fn test_place_rejects_mixed_currencies() {
+        let repos = create_repos(None);
+        let mut tx = create_tx_for_test().await.unwrap();
+        let user = create_user(&mut tx).await.unwrap();
+        let yen = create_product(&mut tx).await.unwrap();
+        let dollar = create_product(&mut tx).await.unwrap();
+        sqlx::query!(
+            "UPDATE products SET currency = 'USD' WHERE id = $1",
+            dollar.id
+        )
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+        let items: Vec<OrderItemInput> = [yen.id, dollar.id]
+            .into_iter()
+            .map(|product_id| OrderItemInput {
+                product_id,
+                quantity: 1,
+            })
+            .collect();
+        let order_use_case = OrderUseCaseImpl::new();
+        let result = order_use_case.place(&repos, &mut tx, user.id, &items).await;
+        assert!(matches!(result, Err(e) if e.status_code() == 400));
+        tx.rollback().await.unwrap();
+    }
+}
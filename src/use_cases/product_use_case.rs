@@ -1,7 +1,13 @@
 use crate::db::DbCon;
+use crate::dto::product_dto::ProductInput;
 use crate::error::app_error::AppError;
-use crate::models::product_model::Product;
+use crate::models::category_model::Category;
+use crate::models::page_model::Page;
+use crate::models::product_model::{normalize_tag, Product, ProductFilter};
+use crate::repositories::pagination::PageRequest;
 use crate::repositories::repositories::Repos;
+use crate::use_cases::category_use_case::category_not_found;
+use crate::use_cases::unit_of_work::UnitOfWork;
 use mockall::automock;
 use tracing::instrument;
 
@@ -20,9 +26,52 @@ pub trait ProductUseCase: Send + Sync {
         &self,
         repos: &Repos,
         db_con: &mut DbCon,
-        name: &String,
+        input: &ProductInput,
     ) -> Result<Product, AppError>;
     async fn find_all(&self, repos: &Repos, db_con: &mut DbCon) -> Result<Vec<Product>, AppError>;
+    async fn find_page(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        filter: &ProductFilter,
+        page: &PageRequest,
+    ) -> Result<Page<Product>, AppError>;
+    async fn find_by_id(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        id: i32,
+    ) -> Result<Product, AppError>;
+    async fn update(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        id: i32,
+        input: &ProductInput,
+    ) -> Result<Product, AppError>;
+    async fn delete(&self, repos: &Repos, db_con: &mut DbCon, id: i32) -> Result<(), AppError>;
+    async fn find_page_in_category(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        category_id: i32,
+        filter: &ProductFilter,
+        page: &PageRequest,
+    ) -> Result<Page<Product>, AppError>;
+    async fn set_categories(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        id: i32,
+        category_ids: &[i32],
+    ) -> Result<Vec<Category>, AppError>;
+    async fn set_tags(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        id: i32,
+        tags: &[String],
+    ) -> Result<Vec<String>, AppError>;
 }
 
 #[async_trait]
@@ -32,10 +81,15 @@ impl ProductUseCase for ProductUseCaseImpl {
         &self,
         repos: &Repos,
         db_con: &mut DbCon,
-        name: &String,
+        input: &ProductInput,
     ) -> Result<Product, AppError> {
-        let product = repos.product.create(&mut *db_con, name).await?;
-        Ok(product)
+        let mut uow = UnitOfWork::begin(db_con).await?;
+        let result = match repos.product.create(uow.con(), input).await {
+            Ok(product) => Ok(product),
+            Err(e) if e.is_unique_violation() => Err(sku_conflict(input)),
+            Err(e) => Err(AppError::from(e)),
+        };
+        uow.finish(result).await
     }
 
     #[instrument(name = "product_use_case/find_all", skip_all)]
@@ -43,4 +97,363 @@ impl ProductUseCase for ProductUseCaseImpl {
         let products = repos.product.find_all(&mut *db_con).await?;
         Ok(products)
     }
+
+    #[instrument(name = "product_use_case/find_page", skip_all)]
+    async fn find_page(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        filter: &ProductFilter,
+        page: &PageRequest,
+    ) -> Result<Page<Product>, AppError> {
+        let products = repos.product.find_page(&mut *db_con, filter, page).await?;
+        Ok(products)
+    }
+
+    #[instrument(name = "product_use_case/find_by_id", skip_all, fields(id = %id))]
+    async fn find_by_id(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        id: i32,
+    ) -> Result<Product, AppError> {
+        repos
+            .product
+            .find_by_id(&mut *db_con, id)
+            .await?
+            .ok_or(AppError::NotFound)
+    }
+
+    #[instrument(name = "product_use_case/update", skip_all, fields(id = %id))]
+    async fn update(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        id: i32,
+        input: &ProductInput,
+    ) -> Result<Product, AppError> {
+        let mut uow = UnitOfWork::begin(db_con).await?;
+        let result = match repos.product.update(uow.con(), id, input).await {
+            Ok(product) => Ok(product),
+            Err(e) if e.is_row_not_found() => Err(AppError::NotFound),
+            Err(e) if e.is_unique_violation() => Err(sku_conflict(input)),
+            Err(e) => Err(AppError::from(e)),
+        };
+        uow.finish(result).await
+    }
+
+    #[instrument(name = "product_use_case/delete", skip_all, fields(id = %id))]
+    async fn delete(&self, repos: &Repos, db_con: &mut DbCon, id: i32) -> Result<(), AppError> {
+        let mut uow = UnitOfWork::begin(db_con).await?;
+        let result = match repos.product.delete(uow.con(), id).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.is_row_not_found() => Err(AppError::NotFound),
+            Err(e) => Err(AppError::from(e)),
+        };
+        uow.finish(result).await
+    }
+
+    // 存在しないカテゴリは空の一覧ではなく404にする
+    #[instrument(name = "product_use_case/find_page_in_category", skip_all, fields(category_id = %category_id))]
+    async fn find_page_in_category(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        category_id: i32,
+        filter: &ProductFilter,
+        page: &PageRequest,
+    ) -> Result<Page<Product>, AppError> {
+        repos
+            .category
+            .find_by_id(&mut *db_con, category_id)
+            .await?
+            .ok_or(AppError::NotFound)?;
+        let filter = ProductFilter {
+            category_id: Some(category_id),
+            ..filter.clone()
+        };
+        let products = repos.product.find_page(&mut *db_con, &filter, page).await?;
+        Ok(products)
+    }
+
+    #[instrument(name = "product_use_case/set_categories", skip_all, fields(id = %id))]
+    async fn set_categories(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        id: i32,
+        category_ids: &[i32],
+    ) -> Result<Vec<Category>, AppError> {
+        let mut uow = UnitOfWork::begin(db_con).await?;
+        let result = set_product_categories(repos, &mut uow, id, category_ids).await;
+        uow.finish(result).await
+    }
+
+    #[instrument(name = "product_use_case/set_tags", skip_all, fields(id = %id))]
+    async fn set_tags(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        id: i32,
+        tags: &[String],
+    ) -> Result<Vec<String>, AppError> {
+        let mut uow = UnitOfWork::begin(db_con).await?;
+        let result = set_product_tags(repos, &mut uow, id, tags).await;
+        uow.finish(result).await
+    }
+}
+
+async fn set_product_categories(
+    repos: &Repos,
+    uow: &mut UnitOfWork<'_>,
+    id: i32,
+    category_ids: &[i32],
+) -> Result<Vec<Category>, AppError> {
+    repos
+        .product
+        .find_by_id(uow.con(), id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    let found = repos.category.find_by_ids(uow.con(), category_ids).await?;
+    if let Some(missing) = category_ids
+        .iter()
+        .find(|category_id| !found.iter().any(|category| category.id == **category_id))
+    {
+        return Err(category_not_found(*missing));
+    }
+    repos
+        .product
+        .set_categories(uow.con(), id, category_ids)
+        .await?;
+    let categories = repos.product.find_categories(uow.con(), id).await?;
+    Ok(categories)
+}
+
+async fn set_product_tags(
+    repos: &Repos,
+    uow: &mut UnitOfWork<'_>,
+    id: i32,
+    tags: &[String],
+) -> Result<Vec<String>, AppError> {
+    repos
+        .product
+        .find_by_id(uow.con(), id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    let tags: Vec<String> = tags.iter().map(|tag| normalize_tag(tag)).collect();
+    repos.product.set_tags(uow.con(), id, &tags).await?;
+    let tags = repos.product.find_tags(uow.con(), id).await?;
+    Ok(tags)
+}
+
+// productsの一意制約はskuだけ
+fn sku_conflict(input: &ProductInput) -> AppError {
+    AppError::new(409, &format!("sku {} is already in use", input.sku))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dto::category_dto::CategoryInput;
+    use crate::repositories::error::DbRepoError;
+    use crate::repositories::product_repo::MockProductRepo;
+    use crate::repositories::repositories::create_repos;
+    use crate::test::app::create_repos_for_test;
+    use crate::test::db::{create_db_con_for_test, create_tx_for_test};
+    use crate::test::fixture::product::{product_fixture, product_input_fixture};
+    use crate::test::repositories::prepare::product::create_product;
+    use crate::use_cases::category_use_case::{CategoryUseCase, CategoryUseCaseImpl};
+
+    #[rocket::async_test]
+    async fn test_find_by_id_not_found() {
+        let mut mock_product_repo = MockProductRepo::new();
+        mock_product_repo
+            .expect_find_by_id()
+            .returning(|_, _| Ok(None));
+        let mut repos = create_repos_for_test();
+        repos.product = Box::new(mock_product_repo);
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let product_use_case = ProductUseCaseImpl::new();
+        let result = product_use_case.find_by_id(&repos, &mut db_con, 1).await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+    }
+
+    #[rocket::async_test]
+    async fn test_update_success() {
+        let mut mock_product_repo = MockProductRepo::new();
+        mock_product_repo
+            .expect_update()
+            .returning(|_, id, _| Ok(product_fixture(id as usize)));
+        let mut repos = create_repos_for_test();
+        repos.product = Box::new(mock_product_repo);
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let product_use_case = ProductUseCaseImpl::new();
+        let input = product_input_fixture();
+        let result = product_use_case
+            .update(&repos, &mut db_con, 3, &input)
+            .await;
+        assert_eq!(result.unwrap().id, 3);
+    }
+
+    #[rocket::async_test]
+    async fn test_delete_not_found() {
+        let mut mock_product_repo = MockProductRepo::new();
+        mock_product_repo
+            .expect_delete()
+            .returning(|_, _| Err(DbRepoError::SqlxError(sqlx::Error::RowNotFound)));
+        let mut repos = create_repos_for_test();
+        repos.product = Box::new(mock_product_repo);
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let product_use_case = ProductUseCaseImpl::new();
+        let result = product_use_case.delete(&repos, &mut db_con, 1).await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+    }
+
+    #[rocket::async_test]
+    async fn test_use_case_in_rolled_back_transaction() {
+        let repos = create_repos(None);
+        let mut tx = create_tx_for_test().await.unwrap();
+        let product_use_case = ProductUseCaseImpl::new();
+        let mut input = product_input_fixture();
+        input.name = "banana".to_string();
+        let product = product_use_case
+            .create(&repos, &mut tx, &input)
+            .await
+            .unwrap();
+        let result = product_use_case
+            .find_by_id(&repos, &mut tx, product.id)
+            .await;
+        assert_eq!(result.unwrap().name, "banana");
+        tx.rollback().await.unwrap();
+    }
+
+    #[rocket::async_test]
+    async fn test_create_duplicate_sku_is_conflict() {
+        let repos = create_repos(None);
+        let mut tx = create_tx_for_test().await.unwrap();
+        let product_use_case = ProductUseCaseImpl::new();
+        let input = product_input_fixture();
+        product_use_case
+            .create(&repos, &mut tx, &input)
+            .await
+            .unwrap();
+        let result = product_use_case.create(&repos, &mut tx, &input).await;
+        let e = result.unwrap_err();
+        assert_eq!(e.status_code(), 409);
+        assert_eq!(
+            e.to_string(),
+            format!("sku {} is already in use", input.sku)
+        );
+        // 失敗したのはセーブポイントまでなので、トランザクションはそのまま使える
+        let products = product_use_case.find_all(&repos, &mut tx).await;
+        assert!(products.is_ok());
+        tx.rollback().await.unwrap();
+    }
+
+    #[rocket::async_test]
+    async fn test_find_page_in_category_subtree() {
+        let repos = create_repos(None);
+        let mut tx = create_tx_for_test().await.unwrap();
+        let product_use_case = ProductUseCaseImpl::new();
+        let category_use_case = CategoryUseCaseImpl::new();
+        let category = |name: &str, parent_id: Option<i32>| CategoryInput {
+            name: name.to_string(),
+            parent_id,
+        };
+        let fruit = category_use_case
+            .create(&repos, &mut tx, &category("fruit", None))
+            .await
+            .unwrap();
+        let citrus = category_use_case
+            .create(&repos, &mut tx, &category("citrus", Some(fruit.id)))
+            .await
+            .unwrap();
+        let lemon = category_use_case
+            .create(&repos, &mut tx, &category("lemon", Some(citrus.id)))
+            .await
+            .unwrap();
+        let apple = create_product(&mut tx).await.unwrap();
+        let yuzu = create_product(&mut tx).await.unwrap();
+        // カテゴリの無い商品は含まれない
+        create_product(&mut tx).await.unwrap();
+        product_use_case
+            .set_categories(&repos, &mut tx, apple.id, &[fruit.id])
+            .await
+            .unwrap();
+        let categories = product_use_case
+            .set_categories(&repos, &mut tx, yuzu.id, &[lemon.id, citrus.id])
+            .await
+            .unwrap();
+        assert_eq!(categories, vec![citrus.clone(), lemon.clone()]);
+
+        let ids = |page: Page<Product>| page.items.iter().map(|p| p.id).collect::<Vec<_>>();
+        let filter = ProductFilter::default();
+        let page = PageRequest::default();
+        let found = product_use_case
+            .find_page_in_category(&repos, &mut tx, fruit.id, &filter, &page)
+            .await
+            .unwrap();
+        assert_eq!(ids(found), vec![apple.id, yuzu.id]);
+        let found = product_use_case
+            .find_page_in_category(&repos, &mut tx, lemon.id, &filter, &page)
+            .await
+            .unwrap();
+        assert_eq!(ids(found), vec![yuzu.id]);
+
+        let result = product_use_case
+            .find_page_in_category(&repos, &mut tx, -1, &filter, &page)
+            .await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+        let result = product_use_case
+            .set_categories(&repos, &mut tx, apple.id, &[fruit.id, -1])
+            .await;
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "category -1 does not exist"
+        );
+        tx.rollback().await.unwrap();
+    }
+
+    #[rocket::async_test]
+    async fn test_set_tags_and_find_by_tag() {
+        let repos = create_repos(None);
+        let mut tx = create_tx_for_test().await.unwrap();
+        let product_use_case = ProductUseCaseImpl::new();
+        let product = create_product(&mut tx).await.unwrap();
+        let tag = format!("tag-{}", uuid::Uuid::new_v4().simple());
+        let tags = product_use_case
+            .set_tags(
+                &repos,
+                &mut tx,
+                product.id,
+                &[
+                    format!(" {} ", tag.to_uppercase()),
+                    tag.clone(),
+                    "Red".to_string(),
+                ],
+            )
+            .await
+            .unwrap();
+        assert_eq!(tags, vec!["red".to_string(), tag.clone()]);
+
+        let filter = ProductFilter {
+            tag: Some(tag.to_uppercase()),
+            ..ProductFilter::default()
+        };
+        let found = product_use_case
+            .find_page(&repos, &mut tx, &filter, &PageRequest::default())
+            .await
+            .unwrap();
+        assert_eq!(found.items.len(), 1);
+        assert_eq!(found.items[0].id, product.id);
+
+        let tags = product_use_case
+            .set_tags(&repos, &mut tx, product.id, &[])
+            .await
+            .unwrap();
+        assert!(tags.is_empty());
+        let result = product_use_case.set_tags(&repos, &mut tx, -1, &[]).await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+        tx.rollback().await.unwrap();
+    }
 }
@@ -0,0 +1,156 @@
+use crate::error::app_error::AppError;
+use crate::log_into;
+use crate::repositories::cache::{CacheScope, Invalidation};
+use crate::repositories::error::DbRepoError;
+use sqlx::{Connection, PgConnection, Postgres, Transaction};
+use std::ops::{Deref, DerefMut};
+
+// 複数のリポジトリ呼び出しを1つのトランザクションにまとめる
+// commitせずにdropされた場合(?で早期リターンした場合など)はロールバックされる
+// 既にトランザクション中のコネクションを渡すとSAVEPOINTになるので、
+// テストでは外側のトランザクションをロールバックすればユースケース全体の変更が残らない
+// キャッシュの無効化はコミットされるまで保留し、ロールバックされたら捨てる
+pub struct UnitOfWork<'c> {
+    con: UnitOfWorkCon<'c>,
+}
+
+// UnitOfWorkがリポジトリに渡すコネクション
+// PgConnectionとして使え、キャッシュするリポジトリには無効化の保留先を渡す
+pub struct UnitOfWorkCon<'c> {
+    tx: Transaction<'c, Postgres>,
+    invalidations: Vec<Invalidation>,
+}
+
+impl Deref for UnitOfWorkCon<'_> {
+    type Target = PgConnection;
+
+    fn deref(&self) -> &PgConnection {
+        &self.tx
+    }
+}
+
+impl DerefMut for UnitOfWorkCon<'_> {
+    fn deref_mut(&mut self) -> &mut PgConnection {
+        &mut self.tx
+    }
+}
+
+impl CacheScope for UnitOfWorkCon<'_> {
+    fn con(&mut self) -> &mut PgConnection {
+        &mut self.tx
+    }
+
+    fn pending(&mut self) -> Option<&mut Vec<Invalidation>> {
+        Some(&mut self.invalidations)
+    }
+}
+
+impl<'c> UnitOfWork<'c> {
+    pub async fn begin(con: &'c mut PgConnection) -> Result<UnitOfWork<'c>, AppError> {
+        let tx = con.begin().await.map_err(|e| log_into!(e, DbRepoError))?;
+        Ok(Self {
+            con: UnitOfWorkCon {
+                tx,
+                invalidations: Vec::new(),
+            },
+        })
+    }
+
+    // リポジトリに渡すコネクション
+    pub fn con(&mut self) -> &mut UnitOfWorkCon<'c> {
+        &mut self.con
+    }
+
+    pub async fn commit(self) -> Result<(), AppError> {
+        let UnitOfWorkCon { tx, invalidations } = self.con;
+        tx.commit().await.map_err(|e| log_into!(e, DbRepoError))?;
+        for invalidation in invalidations {
+            invalidation.run().await;
+        }
+        Ok(())
+    }
+
+    pub async fn rollback(self) -> Result<(), AppError> {
+        self.con
+            .tx
+            .rollback()
+            .await
+            .map_err(|e| log_into!(e, DbRepoError))?;
+        Ok(())
+    }
+
+    // Okならcommit、Errならrollbackして結果を返す
+    pub async fn finish<T>(self, result: Result<T, AppError>) -> Result<T, AppError> {
+        match result {
+            Ok(value) => {
+                self.commit().await?;
+                Ok(value)
+            }
+            Err(e) => {
+                self.rollback().await?;
+                Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_err;
+    use crate::test::db::create_db_con_for_test;
+    use sqlx::query_scalar;
+
+    async fn count_products(con: &mut PgConnection, name: &str) -> i64 {
+        query_scalar!("SELECT COUNT(*) FROM products WHERE name = $1", name)
+            .fetch_one(&mut *con)
+            .await
+            .unwrap()
+            .unwrap_or(0)
+    }
+
+    async fn insert_product(con: &mut PgConnection, name: &str) {
+        sqlx::query!(
+            "INSERT INTO products (name, sku, price, currency) VALUES ($1, gen_random_uuid()::text, 0, 'JPY')",
+            name
+        )
+        .execute(&mut *con)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_finish_commits_on_ok() {
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+        let mut uow = UnitOfWork::begin(&mut tx).await.unwrap();
+        insert_product(uow.con(), "uow_commit").await;
+        uow.finish(Ok(())).await.unwrap();
+        assert_eq!(count_products(&mut tx, "uow_commit").await, 1);
+        tx.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_finish_rolls_back_on_err() {
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+        let mut uow = UnitOfWork::begin(&mut tx).await.unwrap();
+        insert_product(uow.con(), "uow_rollback").await;
+        let result: Result<(), AppError> = uow.finish(app_err!(500, "error")).await;
+        assert!(result.is_err());
+        assert_eq!(count_products(&mut tx, "uow_rollback").await, 0);
+        tx.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_drop_rolls_back() {
+        let mut db_con = create_db_con_for_test().await.unwrap();
+        let mut tx = db_con.begin().await.unwrap();
+        {
+            let mut uow = UnitOfWork::begin(&mut tx).await.unwrap();
+            insert_product(uow.con(), "uow_drop").await;
+        }
+        assert_eq!(count_products(&mut tx, "uow_drop").await, 0);
+        tx.rollback().await.unwrap();
+    }
+}
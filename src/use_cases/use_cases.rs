@@ -1,4 +1,10 @@
 use crate::use_cases::{
+    api_key_use_case::{ApiKeyUseCase, ApiKeyUseCaseImpl},
+    auth_use_case::{AuthUseCase, AuthUseCaseImpl},
+    cart_use_case::{CartUseCase, CartUseCaseImpl},
+    category_use_case::{CategoryUseCase, CategoryUseCaseImpl},
+    health_use_case::{HealthUseCase, HealthUseCaseImpl},
+    order_use_case::{OrderUseCase, OrderUseCaseImpl},
     product_use_case::{ProductUseCase, ProductUseCaseImpl},
     user_use_case::{UserUseCase, UserUseCaseImpl},
 };
@@ -6,10 +12,31 @@ use crate::use_cases::{
 pub struct UseCases {
     pub user: Box<dyn UserUseCase>,
     pub product: Box<dyn ProductUseCase>,
+    pub category: Box<dyn CategoryUseCase>,
+    pub auth: Box<dyn AuthUseCase>,
+    pub api_key: Box<dyn ApiKeyUseCase>,
+    pub order: Box<dyn OrderUseCase>,
+    pub cart: Box<dyn CartUseCase>,
+    pub health: Box<dyn HealthUseCase>,
 }
 
 pub fn create_use_cases() -> UseCases {
     let user = Box::new(UserUseCaseImpl::new());
     let product = Box::new(ProductUseCaseImpl::new());
-    UseCases { user, product }
+    let category = Box::new(CategoryUseCaseImpl::new());
+    let auth = Box::new(AuthUseCaseImpl::new());
+    let api_key = Box::new(ApiKeyUseCaseImpl::new());
+    let order = Box::new(OrderUseCaseImpl::new());
+    let cart = Box::new(CartUseCaseImpl::new());
+    let health = Box::new(HealthUseCaseImpl::new());
+    UseCases {
+        user,
+        product,
+        category,
+        auth,
+        api_key,
+        order,
+        cart,
+        health,
+    }
 }
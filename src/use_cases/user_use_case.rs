@@ -1,8 +1,11 @@
 use crate::db::DbCon;
 use crate::error::app_error::AppError;
-use crate::models::user_model::User;
+use crate::models::page_model::Page;
+use crate::models::user_model::{User, UserFilter};
 use crate::repositories::error::DbRepoError;
+use crate::repositories::pagination::PageRequest;
 use crate::repositories::repositories::Repos;
+use crate::use_cases::unit_of_work::UnitOfWork;
 use mockall::automock;
 use tracing::instrument;
 
@@ -18,6 +21,14 @@ impl UserUseCaseImpl {
 pub trait UserUseCase: Send + Sync {
     async fn find_all(&self, repos: &Repos, db_con: &mut DbCon) -> Result<Vec<User>, AppError>;
 
+    async fn find_page(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        filter: &UserFilter,
+        page: &PageRequest,
+    ) -> Result<Page<User>, AppError>;
+
     async fn create(
         &self,
         repos: &Repos,
@@ -49,6 +60,18 @@ impl UserUseCase for UserUseCaseImpl {
             .map_err(|e| AppError::from(e))
     }
 
+    #[instrument(name = "user_use_case/find_page", skip_all)]
+    async fn find_page(
+        &self,
+        repos: &Repos,
+        db_con: &mut DbCon,
+        filter: &UserFilter,
+        page: &PageRequest,
+    ) -> Result<Page<User>, AppError> {
+        let users = repos.user.find_page(&mut *db_con, filter, page).await?;
+        Ok(users)
+    }
+
     #[instrument(name = "user_use_case/create", skip_all)]
     async fn create(
         &self,
@@ -57,11 +80,13 @@ impl UserUseCase for UserUseCaseImpl {
         name: &String,
         age: i32,
     ) -> Result<User, AppError> {
-        repos
+        let mut uow = UnitOfWork::begin(db_con).await?;
+        let result = repos
             .user
-            .create(&mut *db_con, name, age)
+            .create(uow.con(), name, age)
             .await
-            .map_err(|e| AppError::from(e))
+            .map_err(|e| AppError::from(e));
+        uow.finish(result).await
     }
 
     #[instrument(name = "user_use_case/update", skip_all, fields(id = %id))]
@@ -73,7 +98,8 @@ impl UserUseCase for UserUseCaseImpl {
         name: &String,
         age: i32,
     ) -> Result<User, AppError> {
-        match repos.user.update(&mut *db_con, id, name, age).await {
+        let mut uow = UnitOfWork::begin(db_con).await?;
+        let result = match repos.user.update(uow.con(), id, name, age).await {
             Ok(user) => Ok(user),
             Err(e) => match &e {
                 DbRepoError::SqlxError(sqlx_error) => match sqlx_error {
@@ -82,16 +108,19 @@ impl UserUseCase for UserUseCaseImpl {
                 },
                 _ => Err(AppError::from(e)),
             },
-        }
+        };
+        uow.finish(result).await
     }
 
     #[instrument(name = "user_use_case/delete", skip_all, fields(id = %id))]
     async fn delete(&self, repos: &Repos, db_con: &mut DbCon, id: i32) -> Result<(), AppError> {
-        repos
+        let mut uow = UnitOfWork::begin(db_con).await?;
+        let result = repos
             .user
-            .delete(&mut *db_con, id)
+            .delete(uow.con(), id)
             .await
-            .map_err(|e| AppError::from(e))
+            .map_err(|e| AppError::from(e));
+        uow.finish(result).await
     }
 }
 